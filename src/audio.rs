@@ -1,7 +1,6 @@
 //! this file describes how the audio can be used and gives pub fn that enable this by leveraging
 //! what is already loaded
 //! data_read/audio.rs describes how the audio data is loaded and setup
-use bracket_lib::random::RandomNumberGenerator;
 use kira::sound::static_sound::StaticSoundData;
 use log::warn;
 
@@ -31,8 +30,7 @@ pub fn play_sound_effect(sfx: &str) {
     let sfx: StaticSoundData = match sfx_file {
         SoundFiles::Single(single) => *single.clone(),
         SoundFiles::Sample(sample) => {
-            let mut rng = RandomNumberGenerator::new();
-            let idx = rng.range(0, sample.len());
+            let idx = audioman.sample_rng.range(0, sample.len());
             sample[idx].clone()
         }
     };
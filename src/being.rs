@@ -1,4 +1,3 @@
-use bracket_lib::random::RandomNumberGenerator;
 use bracket_lib::terminal::Point;
 use log::{info, warn};
 use pathfinding::prelude::astar;
@@ -7,18 +6,18 @@ use serde::{Deserialize, Serialize};
 use specs::error::NoError;
 use specs::{
     saveload::{ConvertSaveload, Marker},
-    shred::PanicHandler,
     Component, ConvertSaveload, Entities, Entity, Join, ReadExpect, ReadStorage, System, VecStorage, Write,
     WriteExpect, WriteStorage,
 };
 
 use crate::{
     components::{AttackAction, BreakAction, GoalMoverAI, MoveAction, Name, Position, RandomWalkerAI},
-    data_read::ENTITY_DB,
+    data_read::{GameData, GameDataRes},
     droptables::Drops,
+    events::{GameEvent, GameEvents},
     map::{distance, is_goal, successors, MapRes, TileEntity},
+    rng::GameRng,
     stats::Stats,
-    ui::message_log::MessageLog,
 };
 
 pub struct Being {
@@ -50,31 +49,34 @@ pub struct RandomMonsterMovementSystem;
 
 impl<'a> System<'a> for RandomMonsterMovementSystem {
     type SystemData = (
-        WriteStorage<'a, Position>,
+        ReadStorage<'a, Position>,
         WriteStorage<'a, BreakAction>,
         WriteStorage<'a, MoveAction>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, RandomWalkerAI>,
-        WriteExpect<'a, MessageLog>,
+        Write<'a, GameEvents>,
+        WriteExpect<'a, GameRng>,
         ReadExpect<'a, MapRes>,
+        ReadExpect<'a, GameDataRes>,
         Entities<'a>,
     );
 
     fn run(
         &mut self,
         (
-            mut positions,
+            positions,
             mut break_actions,
             mut move_actions,
             names,
             randwalks,
-            mut log,
+            mut events,
+            mut rng,
             map,
+            game_data,
             entities,
         ): Self::SystemData,
     ) {
-        let mut rng = RandomNumberGenerator::new();
-        for (entity, pos, name, _) in (&entities, &mut positions, &names, &randwalks).join() {
+        for (entity, pos, name, _) in (&entities, &positions, &names, &randwalks).join() {
             let delta: Point = match rng.range::<u32>(0, 100) {
                 0..=10 => Point::new(1, 0),
                 11..=20 => Point::new(0, 1),
@@ -84,7 +86,7 @@ impl<'a> System<'a> for RandomMonsterMovementSystem {
                     continue;
                 }
                 98..=99 => {
-                    say_random_quip(name, &mut log);
+                    say_random_quip(entity, name, &game_data.0, &mut events);
                     continue;
                 }
                 _ => unreachable!("rng.range(0, 100) should have range of "),
@@ -114,11 +116,10 @@ impl<'a> System<'a> for RandomMonsterMovementSystem {
     }
 }
 
-fn say_random_quip(name: &Name, log: &mut Write<MessageLog, PanicHandler>) {
-    let edb = &ENTITY_DB.lock().unwrap();
+fn say_random_quip(speaker: Entity, name: &Name, edb: &GameData, events: &mut GameEvents) {
     if let Some(monster) = edb.beings.get_by_name(&name.0) {
         if let Some(quip) = monster.quips.as_ref().and_then(|quips| quips.first()) {
-            log.enhance(quip)
+            events.send(GameEvent::QuipSpoken { speaker, quip: quip.clone() })
         }
     }
 }
@@ -232,25 +233,21 @@ impl<'a> System<'a> for HandleMoveActions {
 
     fn run(&mut self, (mut move_actions, mut positions, mut map, entities): Self::SystemData) {
         for (entity, want, mover_pos) in (&entities, &move_actions, &mut positions).join() {
+            // the spatial index catches up next frame but moves later this turn need to see the blocker moved now
             let idx = mover_pos.to_idx(map.0.width);
-            match map.0.tile_entities[idx].iter().position(|tile| tile == &TileEntity::Blocking(entity)) {
-                Some(remove_idx) => {
-                    map.0.tile_entities[idx].remove(remove_idx);
-                }
-                None => {
-                    // if ever a monster exists that doesn't block their own position, then change
-                    // to an info maybe
-                    warn!(
-                        "Move action user was not blocking their previous position {}. Continuing move anyways.",
-                        mover_pos
-                    );
-                }
+            if !map.0.remove_tile_entity(idx, &TileEntity::Blocking(entity)) {
+                // if ever a monster exists that doesn't block their own position, then change
+                // to an info maybe
+                warn!(
+                    "Move action user was not blocking their previous position {}. Continuing move anyways.",
+                    mover_pos
+                );
             }
 
             *mover_pos = want.new_pos;
 
             let idx = mover_pos.to_idx(map.0.width);
-            map.0.tile_entities[idx].push(TileEntity::Blocking(entity));
+            map.0.place_tile_entity(idx, TileEntity::Blocking(entity));
         }
 
         move_actions.clear();
@@ -0,0 +1,434 @@
+//! Generated worlds are split into chunks and only the chunks around the player are kept in the `Map`. When the
+//! player walks into another chunk the chunks left behind are unloaded to their own file along with the entities
+//! standing in them, and the chunks coming into range are read back or generated for the first time.
+//! Only the loaded chunks are part of the save file so saving and loading stays fast no matter the world size.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    path::PathBuf,
+};
+
+use bracket_lib::terminal::{Point, Rect};
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+use specs::{
+    saveload::{MarkerAllocator, SimpleMarker, SimpleMarkerAllocator},
+    Entity, Join, World, WorldExt,
+};
+
+use crate::{
+    components::{DeleteCondition, Equipped, InBag, LevelPersistent, Position, Transform},
+    game_init::PlayerEntity,
+    indexing::SpatialIndex,
+    map::{Map, MapRes, RevealedTiles, WorldTile},
+    map_gen::{empty_world_map, gen_chunk, WorldConfig},
+    saveload::{read_components, recreate_being_ai, write_components, SerializeChunk, SerializeMe, SAVE_PATH},
+};
+
+/// Width and height of a chunk in tiles
+pub const CHUNK_SIZE: usize = 64;
+/// How many chunks are kept loaded on every side of the chunk the player is in
+const LOADED_RADIUS: usize = 1;
+/// Unloaded chunks go here until the game is saved
+const UNSAVED_DIR: &str = "unsaved";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ChunkPos {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl ChunkPos {
+    /// The chunk a world tile is in
+    pub fn containing(world_x: usize, world_y: usize) -> Self {
+        Self { x: world_x / CHUNK_SIZE, y: world_y / CHUNK_SIZE }
+    }
+
+    /// The part of `map` this chunk covers, in map tiles
+    pub fn area_in(&self, map: &Map) -> Rect {
+        let x = self.x * CHUNK_SIZE - map.chunk_coords.x;
+        let y = self.y * CHUNK_SIZE - map.chunk_coords.y;
+        Rect::with_size(x as i32, y as i32, CHUNK_SIZE as i32, CHUNK_SIZE as i32)
+    }
+
+    fn file_name(&self) -> String {
+        format!("{}_{}.json", self.x, self.y)
+    }
+}
+
+/// The block of chunks a map holds
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ChunkWindow {
+    corner: ChunkPos,
+    across: usize,
+    down: usize,
+}
+
+impl ChunkWindow {
+    /// The chunks within `LOADED_RADIUS` of `center`, pushed back inside the world when near its edge
+    pub fn around(wc: &WorldConfig, center: ChunkPos) -> Self {
+        let (world_across, world_down) = world_chunks(wc);
+        let across = world_across.min(LOADED_RADIUS * 2 + 1);
+        let down = world_down.min(LOADED_RADIUS * 2 + 1);
+        let corner = ChunkPos {
+            x: center.x.saturating_sub(LOADED_RADIUS).min(world_across - across),
+            y: center.y.saturating_sub(LOADED_RADIUS).min(world_down - down),
+        };
+        Self { corner, across, down }
+    }
+
+    /// Every chunk of the world at once
+    pub fn whole_world(wc: &WorldConfig) -> Self {
+        let (across, down) = world_chunks(wc);
+        Self { corner: ChunkPos { x: 0, y: 0 }, across, down }
+    }
+
+    /// The chunks `map` currently holds
+    pub fn of_map(map: &Map) -> Self {
+        Self {
+            corner: ChunkPos::containing(map.chunk_coords.x, map.chunk_coords.y),
+            across: map.width / CHUNK_SIZE,
+            down: map.height / CHUNK_SIZE,
+        }
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        (self.corner.y..self.corner.y + self.down)
+            .flat_map(move |y| (self.corner.x..self.corner.x + self.across).map(move |x| ChunkPos { x, y }))
+    }
+
+    pub fn contains(&self, chunk: &ChunkPos) -> bool {
+        (self.corner.x..self.corner.x + self.across).contains(&chunk.x)
+            && (self.corner.y..self.corner.y + self.down).contains(&chunk.y)
+    }
+
+    /// The world tile of the window's top left corner
+    pub fn origin(&self) -> (usize, usize) {
+        (self.corner.x * CHUNK_SIZE, self.corner.y * CHUNK_SIZE)
+    }
+
+    pub fn width(&self) -> usize {
+        self.across * CHUNK_SIZE
+    }
+
+    pub fn height(&self) -> usize {
+        self.down * CHUNK_SIZE
+    }
+}
+
+/// The number of chunks across and down the world, a world is always at least one chunk.
+/// Worlds saved before their size had to be whole chunks are rounded up.
+pub fn world_chunks(wc: &WorldConfig) -> (usize, usize) {
+    (wc.width.div_ceil(CHUNK_SIZE).max(1), wc.height.div_ceil(CHUNK_SIZE).max(1))
+}
+
+/// Moves the loaded chunks along with the player, call after anything that can move the player.
+/// Does nothing on maps that are not part of a generated world.
+pub fn stream_chunks(ecs: &mut World) {
+    let (wc, old_window, player_chunk) = {
+        let map_res = ecs.read_resource::<MapRes>();
+        let map = &map_res.0;
+        let wc = match &map.world {
+            Some(wc) => wc.clone(),
+            None => return,
+        };
+        let player_entity = ecs.read_resource::<PlayerEntity>().0;
+        let player_pos = match ecs.read_storage::<Position>().get(player_entity) {
+            Some(pos) => *pos,
+            None => return,
+        };
+        let player_chunk = ChunkPos::containing(map.chunk_coords.x + player_pos.x, map.chunk_coords.y + player_pos.y);
+        (wc, ChunkWindow::of_map(map), player_chunk)
+    };
+    let new_window = ChunkWindow::around(&wc, player_chunk);
+    if new_window == old_window {
+        return;
+    }
+    debug!("Chunks moving from {:?} to {:?}", old_window, new_window);
+
+    let old_map = ecs.read_resource::<MapRes>().0.clone();
+    for chunk in old_window.chunks().filter(|chunk| !new_window.contains(chunk)) {
+        unload_area(ecs, &wc.world_name, &chunk.file_name(), &old_map, chunk.area_in(&old_map));
+    }
+
+    let mut new_map = empty_world_map(&wc, &new_window);
+    for chunk in old_window.chunks().filter(|chunk| new_window.contains(chunk)) {
+        let (from, to) = (chunk.area_in(&old_map), chunk.area_in(&new_map));
+        for row in 0..CHUNK_SIZE {
+            let from_idx = old_map.xy_to_idx(from.x1 as usize, from.y1 as usize + row);
+            let to_idx = new_map.xy_to_idx(to.x1 as usize, to.y1 as usize + row);
+            new_map.tiles[to_idx..to_idx + CHUNK_SIZE]
+                .clone_from_slice(&old_map.tiles[from_idx..from_idx + CHUNK_SIZE]);
+            new_map.revealed.copy_from(to_idx, &old_map.revealed, from_idx, CHUNK_SIZE);
+        }
+    }
+    shift_entities(ecs, &old_map, &new_map);
+
+    for chunk in new_window.chunks().filter(|chunk| !old_window.contains(chunk)) {
+        load_or_gen_chunk(ecs, &wc, &mut new_map, chunk);
+    }
+
+    ecs.insert(MapRes(new_map));
+    ecs.write_resource::<SpatialIndex>().request_rebuild();
+}
+
+/// Builds the map of the chunks around `around`, a position in world tiles, from their files or by generating them
+pub fn load_world_around(ecs: &mut World, wc: &WorldConfig, around: &Position) -> Map {
+    let window = ChunkWindow::around(wc, ChunkPos::containing(around.x, around.y));
+    let mut map = empty_world_map(wc, &window);
+    for chunk in window.chunks() {
+        load_or_gen_chunk(ecs, wc, &mut map, chunk);
+    }
+    map
+}
+
+/// Unloads every chunk `map` holds, for when the player leaves the world without walking
+pub fn unload_world(ecs: &mut World, map: &Map) {
+    let wc = match &map.world {
+        Some(wc) => wc,
+        None => return,
+    };
+    for chunk in ChunkWindow::of_map(map).chunks() {
+        unload_area(ecs, &wc.world_name, &chunk.file_name(), map, chunk.area_in(map));
+    }
+}
+
+fn load_or_gen_chunk(ecs: &mut World, wc: &WorldConfig, map: &mut Map, chunk: ChunkPos) {
+    let area = chunk.area_in(map);
+    if !load_area(ecs, &wc.world_name, &chunk.file_name(), map, area) {
+        gen_chunk(ecs, wc, map, chunk);
+    }
+}
+
+/// What is in a chunk file before its entities
+#[derive(Serialize, Deserialize)]
+struct ChunkTiles {
+    tiles: Vec<WorldTile>,
+    #[serde(default)]
+    revealed: RevealedTiles,
+}
+
+/// Writes the tiles and entities of an area of `map` to `file_name` then deletes the entities. Used for chunks
+/// and for anything else of the world that is put away while the player is not around, such as dungeon floors.
+/// Entities in a bag or equipped go along with their owner, short lived entities are deleted without being saved.
+pub(crate) fn unload_area(ecs: &mut World, world_name: &str, file_name: &str, map: &Map, area: Rect) {
+    let (saved, doomed) = {
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<Position>();
+        let persistents = ecs.read_storage::<LevelPersistent>();
+        let delete_conditions = ecs.read_storage::<DeleteCondition>();
+        let in_bags = ecs.read_storage::<InBag>();
+        let equipped = ecs.read_storage::<Equipped>();
+
+        let standing: Vec<Entity> = (&entities, &positions, !&persistents)
+            .join()
+            .filter(|(_, pos, ())| area.point_in_rect(Point::new(pos.x, pos.y)))
+            .map(|(entity, _, ())| entity)
+            .collect();
+        let mut saved: HashSet<Entity> =
+            standing.iter().copied().filter(|entity| !delete_conditions.contains(*entity)).collect();
+        let carried: Vec<Entity> = (&entities, &in_bags)
+            .join()
+            .filter(|(_, bag)| saved.contains(&bag.owner))
+            .map(|(entity, _)| entity)
+            .chain((&entities, &equipped).join().filter(|(_, eq)| saved.contains(&eq.on)).map(|(entity, _)| entity))
+            .collect();
+        saved.extend(carried.iter().copied());
+        let doomed: Vec<Entity> = standing.into_iter().chain(carried).collect();
+        (saved, doomed)
+    };
+
+    {
+        let mut positions = ecs.write_storage::<Position>();
+        let mut markers = ecs.write_storage::<SimpleMarker<SerializeChunk>>();
+        let mut allocator = ecs.write_resource::<SimpleMarkerAllocator<SerializeChunk>>();
+        *allocator = SimpleMarkerAllocator::new();
+        markers.clear();
+        for entity in saved.iter() {
+            allocator.mark(*entity, &mut markers);
+            if let Some(pos) = positions.get_mut(*entity) {
+                pos.x -= area.x1 as usize;
+                pos.y -= area.y1 as usize;
+            }
+        }
+    }
+
+    let width = area.width() as usize;
+    let mut tiles = Vec::with_capacity(width * area.height() as usize);
+    let mut revealed = RevealedTiles::default();
+    for y in area.y1 as usize..area.y2 as usize {
+        let idx = map.xy_to_idx(area.x1 as usize, y);
+        revealed.copy_from(tiles.len(), &map.revealed, idx, width);
+        tiles.extend_from_slice(&map.tiles[idx..idx + width]);
+    }
+    match create_unsaved_file(world_name, file_name) {
+        Ok(writer) => {
+            let mut serializer = serde_json::Serializer::new(writer);
+            if let Err(e) = (ChunkTiles { tiles, revealed }).serialize(&mut serializer) {
+                error!("{} could not be written: {}", file_name, e);
+            }
+            write_components::<SimpleMarker<SerializeChunk>, _>(ecs, &mut serializer);
+        }
+        Err(e) => error!("{} could not be saved, its changes are lost: {}", file_name, e),
+    }
+
+    ecs.write_storage::<SimpleMarker<SerializeChunk>>().clear();
+    if let Err(e) = ecs.delete_entities(&doomed) {
+        warn!("{} was not fully unloaded: {:?}", file_name, e);
+    }
+}
+
+/// Reads an area and its entities back from `file_name` into `map`, returns false when it was never unloaded
+pub(crate) fn load_area(ecs: &mut World, world_name: &str, file_name: &str, map: &mut Map, area: Rect) -> bool {
+    let unsaved_path = unsaved_dir(world_name).join(file_name);
+    let path = if unsaved_path.exists() { unsaved_path } else { chunks_dir(world_name).join(file_name) };
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+
+    let width = area.width() as usize;
+    let mut deserializer = serde_json::Deserializer::from_str(&data);
+    let ChunkTiles { tiles, revealed } = match ChunkTiles::deserialize(&mut deserializer) {
+        Ok(chunk_tiles) if chunk_tiles.tiles.len() == width * area.height() as usize => chunk_tiles,
+        _ => {
+            error!("{} is broken, it is generated again", path.display());
+            return false;
+        }
+    };
+    for (row, row_tiles) in tiles.chunks(width).enumerate() {
+        let idx = map.xy_to_idx(area.x1 as usize, area.y1 as usize + row);
+        map.tiles[idx..idx + width].clone_from_slice(row_tiles);
+        map.revealed.copy_from(idx, &revealed, row * width, width);
+    }
+
+    *ecs.write_resource::<SimpleMarkerAllocator<SerializeChunk>>() = SimpleMarkerAllocator::new();
+    read_components::<SimpleMarker<SerializeChunk>, _>(ecs, &mut deserializer);
+    let loaded: HashSet<Entity> = {
+        let entities = ecs.entities();
+        let mut markers = ecs.write_storage::<SimpleMarker<SerializeChunk>>();
+        let loaded = (&entities, &markers).join().map(|(entity, _)| entity).collect();
+        markers.clear();
+        loaded
+    };
+    {
+        let mut positions = ecs.write_storage::<Position>();
+        let mut markers = ecs.write_storage::<SimpleMarker<SerializeMe>>();
+        let mut allocator = ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>();
+        for entity in loaded.iter() {
+            allocator.mark(*entity, &mut markers);
+            if let Some(pos) = positions.get_mut(*entity) {
+                pos.x += area.x1 as usize;
+                pos.y += area.y1 as usize;
+            }
+        }
+    }
+    recreate_being_ai(ecs, |entity| loaded.contains(&entity));
+
+    // the area lives in the ecs again, the saved file stays until the game is saved in case it never is
+    let _ = fs::remove_file(unsaved_dir(world_name).join(file_name));
+    debug!("{} loaded with {} entities", file_name, loaded.len());
+    true
+}
+
+/// Moves every entity still on the map to where its tile is on `new_map`
+fn shift_entities(ecs: &mut World, old_map: &Map, new_map: &Map) {
+    let dx = old_map.chunk_coords.x as f32 - new_map.chunk_coords.x as f32;
+    let dy = old_map.chunk_coords.y as f32 - new_map.chunk_coords.y as f32;
+    let mut positions = ecs.write_storage::<Position>();
+    let mut transforms = ecs.write_storage::<Transform>();
+
+    for pos in (&mut positions).join() {
+        pos.x = (pos.x + old_map.chunk_coords.x).saturating_sub(new_map.chunk_coords.x);
+        pos.y = (pos.y + old_map.chunk_coords.y).saturating_sub(new_map.chunk_coords.y);
+    }
+    for transform in (&mut transforms).join() {
+        transform.sprite_pos.x += dx;
+        transform.sprite_pos.y += dy;
+    }
+}
+
+fn chunks_dir(world_name: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}.chunks", SAVE_PATH, world_name))
+}
+
+fn unsaved_dir(world_name: &str) -> PathBuf {
+    chunks_dir(world_name).join(UNSAVED_DIR)
+}
+
+fn create_unsaved_file(world_name: &str, file_name: &str) -> std::io::Result<File> {
+    let dir = unsaved_dir(world_name);
+    fs::create_dir_all(&dir)?;
+    File::create(dir.join(file_name))
+}
+
+/// Keeps the chunks and dungeon floors unloaded since the last save, called when the game is saved
+pub fn commit_chunks(world_name: &str) {
+    let unsaved = match fs::read_dir(unsaved_dir(world_name)) {
+        Ok(unsaved) => unsaved,
+        Err(_) => return,
+    };
+    for file in unsaved.flatten() {
+        if let Err(e) = fs::rename(file.path(), chunks_dir(world_name).join(file.file_name())) {
+            error!("Chunk {} could not be saved: {}", file.path().display(), e);
+        }
+    }
+}
+
+/// Throws away the chunks unloaded after the last save, called when the game is loaded
+pub fn discard_unsaved_chunks(world_name: &str) {
+    let _ = fs::remove_dir_all(unsaved_dir(world_name));
+}
+
+/// Removes every chunk of a world, called before a world is generated
+pub fn delete_world_chunks(world_name: &str) {
+    let _ = fs::remove_dir_all(chunks_dir(world_name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::Name, simulation::Simulation};
+
+    /// The name and spot of every entity that belongs to the chunks rather than following the player
+    fn chunk_entities(ecs: &World) -> Vec<(String, usize, usize)> {
+        let names = ecs.read_storage::<Name>();
+        let positions = ecs.read_storage::<Position>();
+        let persistents = ecs.read_storage::<LevelPersistent>();
+        let mut found: Vec<(String, usize, usize)> =
+            (&names, &positions, !&persistents).join().map(|(name, pos, ())| (name.0.clone(), pos.x, pos.y)).collect();
+        found.sort();
+        found
+    }
+
+    fn tile_summary(map: &Map) -> Vec<(String, usize, u8, bool)> {
+        map.tiles.iter().map(|tile| (tile.name.clone(), tile.atlas_idx, tile.height, tile.is_blocked)).collect()
+    }
+
+    #[test]
+    fn unloaded_chunks_load_back_the_same() {
+        let wc = WorldConfig { world_name: "chunks_test_reload".to_string(), seed: 3, ..Default::default() };
+        let mut sim = Simulation::new(&wc).expect("the test world should start");
+        let mut map = sim.ecs.read_resource::<MapRes>().0.clone();
+        // changes only the saved chunk knows about, a regenerated chunk would not have them
+        map.tiles[0].name = "Marked".to_string();
+        map.revealed.set(1, true);
+        let entities_before = chunk_entities(&sim.ecs);
+        assert!(!entities_before.is_empty());
+
+        unload_world(&mut sim.ecs, &map);
+        sim.ecs.maintain();
+        assert!(chunk_entities(&sim.ecs).is_empty());
+
+        let wc = map.world_config().cloned().unwrap();
+        let center = Position::new(map.chunk_coords.x + map.width / 2, map.chunk_coords.y + map.height / 2);
+        let reloaded = load_world_around(&mut sim.ecs, &wc, &center);
+        sim.ecs.maintain();
+
+        assert_eq!(ChunkWindow::of_map(&reloaded), ChunkWindow::of_map(&map));
+        assert_eq!(tile_summary(&reloaded), tile_summary(&map));
+        assert!(reloaded.revealed.get(1));
+        assert_eq!(chunk_entities(&sim.ecs), entities_before);
+    }
+}
@@ -65,7 +65,15 @@ pub const DARKBLUE: Color = (42, 41, 66);
 pub const WHITE: Color = (222, 222, 222);
 pub const PARCHMENT: Color = (255, 241, 169);
 pub const TEXASROSE: Color = (235, 184, 91);
-#[allow(unused)]
 pub const DARKESTBROWN: Color = (64, 46, 43);
 pub const DARKERBROWN: Color = (118, 64, 50);
 pub const DARKBROWN: Color = (161, 92, 52);
+pub const OCEANBLUE: Color = (44, 86, 145);
+pub const FERNGREEN: Color = (79, 121, 66);
+pub const KHAKI: Color = (176, 160, 110);
+pub const ASHGREY: Color = (178, 178, 172);
+pub const PINEGREEN: Color = (28, 72, 44);
+pub const SUNLIGHT: Color = (255, 255, 255);
+pub const DAWNPINK: Color = (236, 196, 200);
+pub const DUSKORANGE: Color = (226, 164, 128);
+pub const MIDNIGHTBLUE: Color = (84, 92, 150);
@@ -1,8 +1,9 @@
+use log::{error, info};
 use specs::{Entities, Join, ReadStorage, System, Write, WriteStorage};
 
 use crate::{
     components::{AttackAction, AttackBonus, EntityStats, Equipped, HealAction, HealthStats, Name, SufferDamage},
-    ui::message_log::MessageLog,
+    events::{GameEvent, GameEvents},
 };
 
 pub struct AttackActionHandler;
@@ -11,7 +12,7 @@ impl<'a> System<'a> for AttackActionHandler {
     type SystemData = (
         WriteStorage<'a, AttackAction>,
         WriteStorage<'a, SufferDamage>,
-        Write<'a, MessageLog>,
+        Write<'a, GameEvents>,
         ReadStorage<'a, EntityStats>,
         ReadStorage<'a, HealthStats>,
         ReadStorage<'a, Name>,
@@ -25,7 +26,7 @@ impl<'a> System<'a> for AttackActionHandler {
         (
             mut attack_actions,
             mut suffer_damage,
-            mut log,
+            mut events,
             stats,
             health_stats,
             names,
@@ -34,13 +35,12 @@ impl<'a> System<'a> for AttackActionHandler {
             entities,
         ): Self::SystemData,
     ) {
-        for (attacker, stats_set, action, name) in (&entities, &stats, &attack_actions, &names).join() {
+        for (attacker, stats_set, action, _) in (&entities, &stats, &attack_actions, &names).join() {
             if let Some(target_stats) = health_stats.get(action.target) {
                 if target_stats.defense > stats_set.set.strength {
-                    log.log("Took no damage because defense is greater");
+                    events.send(GameEvent::AttackBlocked { attacker, target: action.target });
                     continue;
                 }
-                let target_name = names.get(action.target).unwrap();
                 let mut damage = stats_set.set.strength - target_stats.defense;
 
                 // collect all attack bonuses
@@ -52,7 +52,7 @@ impl<'a> System<'a> for AttackActionHandler {
                     };
                 }
 
-                log.log(format!("{} dealt {} damage to {}", name, damage, target_name));
+                events.send(GameEvent::DamageDealt { attacker, target: action.target, amount: damage });
                 SufferDamage::new_damage(&mut suffer_damage, action.target, -(damage as i32));
             }
         }
@@ -73,3 +73,26 @@ impl<'a> System<'a> for HealActionHandler {
         heal_actions.clear();
     }
 }
+
+/// Deletes every tile or being whose health has run out, whether it was broken or killed in combat
+pub struct RemoveDeadEntities;
+
+impl<'a> System<'a> for RemoveDeadEntities {
+    type SystemData = (ReadStorage<'a, HealthStats>, ReadStorage<'a, Name>, Write<'a, GameEvents>, Entities<'a>);
+
+    fn run(&mut self, (healths, names, mut events, entities): Self::SystemData) {
+        for (stats, e, name) in (&healths, &entities, &names).join() {
+            if stats.hp == 0 {
+                match entities.delete(e) {
+                    Ok(..) => {
+                        info!("{} is dead and was deleted, items should have spawned if any.", name);
+                        events.send(GameEvent::EntityDied { entity: e, name: name.clone() });
+                    }
+                    Err(err) => {
+                        error!("Failed to clean up {} : {}", e.id(), err);
+                    }
+                }
+            }
+        }
+    }
+}
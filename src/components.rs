@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 use specs::error::NoError;
 use specs::{
     saveload::{ConvertSaveload, Marker},
-    Component, ConvertSaveload, Entity, NullStorage, VecStorage,
+    Component, ConvertSaveload, Entity, FlaggedStorage, NullStorage, VecStorage,
 };
 
 use crate::{
@@ -21,6 +21,7 @@ use crate::{
     indexing::idx_to_point,
     inventory::UseMenuResult,
     items::ItemID,
+    map::WorldCoords,
 };
 
 #[derive(Debug, Component, ConvertSaveload, Clone)]
@@ -59,13 +60,17 @@ impl Transform {
 }
 
 /// Represents a position of anything that exists physically in the game world
-#[derive(Debug, Component, Copy, Clone, PartialEq, Eq, Hash, ConvertSaveload, Serialize)]
-#[storage(VecStorage)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, ConvertSaveload, Serialize)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
 }
 
+// The storages that decide what is in a tile are flagged so `UpdateSpatialIndex` only looks at what changed
+impl Component for Position {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
 impl Position {
     pub fn new(x: usize, y: usize) -> Self {
         Self { x, y }
@@ -144,16 +149,22 @@ impl From<Stats> for EntityStats {
 }
 
 /// Prevents gameobjects from passing through it
-#[derive(Debug, Component, Default, Clone, Serialize, Deserialize)]
-#[storage(NullStorage)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Blocking {}
 
-#[derive(Debug, Component, Default, ConvertSaveload)]
-#[storage(VecStorage)]
+impl Component for Blocking {
+    type Storage = FlaggedStorage<Self, NullStorage<Self>>;
+}
+
+#[derive(Debug, Default, ConvertSaveload)]
 pub struct Fishable {
     pub time_left: Duration,
 }
 
+impl Component for Fishable {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
 #[derive(Component)]
 #[storage(VecStorage)]
 pub struct FishAction {
@@ -238,12 +249,15 @@ impl HealthStats {
     }
 }
 
-#[derive(Debug, Component, Clone, ConvertSaveload)]
-#[storage(VecStorage)]
+#[derive(Debug, Clone, ConvertSaveload)]
 pub struct Breakable {
     pub by: ToolType,
 }
 
+impl Component for Breakable {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
 impl Breakable {
     pub fn new(by: ToolType) -> Self {
         Self { by }
@@ -334,13 +348,16 @@ pub enum DeleteCondition {
 #[storage(NullStorage)]
 pub struct FinishedActivity {}
 
-#[derive(Component, Default, ConvertSaveload, Clone)]
-#[storage(VecStorage)]
+#[derive(Default, ConvertSaveload, Clone)]
 pub struct Item {
     pub id: ItemID,
     pub qty: ItemQty,
 }
 
+impl Component for Item {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
 impl Item {
     pub fn new(id: ItemID, qty: ItemQty) -> Self {
         Self { id, qty }
@@ -361,10 +378,10 @@ pub enum Consumable {
 }
 
 impl Consumable {
-    pub fn from_str(str: &str, amt: usize) -> Self {
+    pub fn from_str(str: &str, amt: usize) -> Option<Self> {
         match str {
-            "instant_regen" => Self::InstantRegen(amt),
-            _ => panic!("Bad string based to consumable."),
+            "instant_regen" => Some(Self::InstantRegen(amt)),
+            _ => None,
         }
     }
 }
@@ -393,24 +410,6 @@ pub struct Equipable {
     pub slot: EquipmentSlot,
 }
 
-impl Equipable {
-    pub fn from_str(str: &str) -> Self {
-        let slot = match str {
-            "Hand" => EquipmentSlot::Hand,
-            "Torso" => EquipmentSlot::Torso,
-            "Head" => EquipmentSlot::Head,
-            "Legs" => EquipmentSlot::Legs,
-            "Feet" => EquipmentSlot::Feet,
-            "Tail" => EquipmentSlot::Tail,
-            _ => {
-                eprintln!("{} is not a valid name for an equipment slot, using Head instead", str);
-                EquipmentSlot::Head
-            }
-        };
-        Equipable { slot }
-    }
-}
-
 #[derive(PartialEq, Clone, Serialize, Deserialize)]
 pub enum EquipmentSlot {
     Hand,
@@ -421,6 +420,31 @@ pub enum EquipmentSlot {
     Tail,
 }
 
+impl EquipmentSlot {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Hand" => Some(EquipmentSlot::Hand),
+            "Torso" => Some(EquipmentSlot::Torso),
+            "Head" => Some(EquipmentSlot::Head),
+            "Legs" => Some(EquipmentSlot::Legs),
+            "Feet" => Some(EquipmentSlot::Feet),
+            "Tail" => Some(EquipmentSlot::Tail),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EquipmentSlot::Hand => "Hand",
+            EquipmentSlot::Torso => "Torso",
+            EquipmentSlot::Head => "Head",
+            EquipmentSlot::Legs => "Legs",
+            EquipmentSlot::Feet => "Feet",
+            EquipmentSlot::Tail => "Tail",
+        }
+    }
+}
+
 #[derive(Component, ConvertSaveload, Clone)]
 #[storage(VecStorage)]
 pub struct EquipmentSlots {
@@ -454,6 +478,27 @@ pub struct PickupAction {
 #[storage(NullStorage)]
 pub struct Water {}
 
+/// Water that is part of a river, always found alongside `Water`
+#[derive(Component, Default, Serialize, Deserialize, Clone)]
+#[storage(NullStorage)]
+pub struct River {}
+
+/// Stepping onto it takes the player to `destination`, a tile position in the LDtk world inside `level_name`
+#[derive(Component, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Doorway {
+    pub level_name: String,
+    pub destination: WorldCoords,
+}
+
+/// Stepping onto it takes the player to `depth` of `dungeon`, depth 0 being the world the dungeon was entered from
+#[derive(Component, Serialize, Deserialize, Clone)]
+#[storage(VecStorage)]
+pub struct Stairs {
+    pub dungeon: String,
+    pub depth: u32,
+}
+
 /// A delicious treat loved by many animals and other beings...
 #[derive(Component, Default, Serialize, Deserialize, Clone)]
 #[storage(NullStorage)]
@@ -539,3 +584,10 @@ pub struct Viewshed {
     pub tiles: HashSet<Point>,
     pub range: usize,
 }
+
+/// Lights up the tiles around it when it isn't day, from where it stands or from whoever has it equipped
+#[derive(Component, Clone, ConvertSaveload)]
+#[storage(VecStorage)]
+pub struct LightSource {
+    pub radius: usize,
+}
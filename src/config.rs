@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use crate::settings::SettingsConfig;
+use crate::{keybindings::Keybindings, settings::SettingsConfig};
 
 /// Contains all configs for various game things
 /// Note - For now it resides on the `State` variable since we shouldn't have systems modifying it as that
@@ -8,11 +8,16 @@ use crate::settings::SettingsConfig;
 pub struct ConfigMaster {
     pub inventory: InventoryConfig,
     pub general: SettingsConfig,
+    pub keybindings: Keybindings,
 }
 
 impl ConfigMaster {
     pub fn load() -> Self {
-        Self { inventory: InventoryConfig { sort_mode: SortMode::NameABC }, general: SettingsConfig::load() }
+        Self {
+            inventory: InventoryConfig { sort_mode: SortMode::NameABC },
+            general: SettingsConfig::load(),
+            keybindings: Keybindings::load(),
+        }
     }
 }
 
@@ -4,8 +4,8 @@ use specs::{Entities, Entity, Join, ReadStorage, System, Write, WriteStorage};
 use crate::{
     components::{CraftAction, InBag, Item},
     data_read::prelude::RECIPE_DB,
+    events::{GameEvent, GameEvents},
     items::{ItemID, ItemQty, ItemSpawner, SpawnType},
-    ui::message_log::MessageLog,
 };
 
 pub struct UseWithRecipe {
@@ -24,7 +24,7 @@ impl<'a> System<'a> for HandleCraftingSystem {
     type SystemData = (
         WriteStorage<'a, CraftAction>,
         Write<'a, ItemSpawner>,
-        Write<'a, MessageLog>,
+        Write<'a, GameEvents>,
         WriteStorage<'a, Item>,
         ReadStorage<'a, InBag>,
         Entities<'a>,
@@ -33,7 +33,7 @@ impl<'a> System<'a> for HandleCraftingSystem {
     /// TODO: check for item qty in recipes
     fn run(
         &mut self,
-        (mut craft_actions, mut spawn_requests, mut log, mut items, in_bags, entities): Self::SystemData,
+        (mut craft_actions, mut spawn_requests, mut events, mut items, in_bags, entities): Self::SystemData,
     ) {
         let rdb = &RECIPE_DB.lock().unwrap();
         'outer: for (crafter, craft_action) in (&entities, &craft_actions).join() {
@@ -53,7 +53,7 @@ impl<'a> System<'a> for HandleCraftingSystem {
             }) {
                 Some(recipe) => recipe,
                 None => {
-                    log.log("These things had no effect on each other.");
+                    events.send(GameEvent::CraftFailed { crafter });
                     continue;
                 }
             };
@@ -79,6 +79,7 @@ impl<'a> System<'a> for HandleCraftingSystem {
             }
 
             spawn_requests.request(recipe_crafted.output, SpawnType::InBag(crafter));
+            events.send(GameEvent::CraftSucceeded { crafter, output: recipe_crafted.output });
         }
 
         craft_actions.clear();
@@ -1,12 +1,11 @@
-use std::{collections::HashMap, fs, sync::Mutex};
+use std::{collections::HashMap, sync::Mutex};
 
-use bracket_lib::color::PURPLE;
-use json5::from_str;
-use log::error;
 use serde::Deserialize;
 
 use crate::{colors::WHITE, frame_animation::Animation};
 
+use super::{read_raws, DataError};
+
 use lazy_static::lazy_static;
 
 lazy_static! {
@@ -28,32 +27,19 @@ impl AnimationDatabase {
         Self { animations: HashMap::new() }
     }
 
-    pub fn load(&mut self) {
+    pub fn load() -> Result<Self, DataError> {
         let mut anim_db = Self::new();
-        let data = match fs::read_to_string(ANIMATION_FILE) {
-            Ok(d) => d,
-            Err(e) => {
-                error!("Error while reading animation file: {}", e);
-                *self = anim_db;
-                return;
-            }
-        };
-
-        let anims: Vec<RawAnimation> = match from_str(&data) {
-            Ok(e) => e,
-            Err(e) => {
-                error!("Error while parsing animation file: {}", e);
-                *self = anim_db;
-                return;
-            }
-        };
+        let anims: Vec<RawAnimation> = read_raws(ANIMATION_FILE)?;
 
         for anim in anims {
             let mut hash = HashMap::new();
             for (glyph, raw_color) in anim.key_colors {
                 let value = match raw_color.as_str() {
                     "white" => WHITE,
-                    _ => PURPLE,
+                    _ => {
+                        let reason = format!("{} is not a key color", raw_color);
+                        return Err(DataError::bad_entry(ANIMATION_FILE, &anim.name, reason));
+                    }
                 };
                 hash.insert(glyph, value);
             }
@@ -62,11 +48,11 @@ impl AnimationDatabase {
                 .insert(anim.name, Animation::from_vec(&anim.frames, &anim.frame_keys, &hash, anim.time_between_ms));
         }
 
-        *self = anim_db;
+        Ok(anim_db)
     }
 }
 
-const ANIMATION_FILE: &str = "./raws/animations.json5";
+pub(crate) const ANIMATION_FILE: &str = "./raws/animations.json5";
 
 #[derive(Deserialize)]
 struct RawAnimation {
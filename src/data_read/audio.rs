@@ -1,21 +1,27 @@
 use crate::audio::SoundFiles;
-use std::{collections::HashMap, fs, sync::Mutex};
+use std::{collections::HashMap, sync::Mutex};
 
+use bracket_lib::random::RandomNumberGenerator;
 use kira::{sound::static_sound::StaticSoundData, AudioManager, AudioManagerSettings, DefaultBackend};
 use lazy_static::lazy_static;
 use log::{debug, error, info, warn};
 use serde::Deserialize;
 
+use super::{read_raws, DataError};
+
 lazy_static! {
     pub static ref AUDIOMAN: Mutex<AudioPlayer> = Mutex::new(AudioPlayer::new());
     pub static ref AUDIO_DB: Mutex<AudioDatabase> = Mutex::new(AudioDatabase::new());
 }
 
 pub const AUDIO_DIRECTORY: &str = "./resources/sounds";
-const AUDIO_DEFINTIONS_FILE: &str = "./raws/audio.json5";
+pub(crate) const AUDIO_DEFINTIONS_FILE: &str = "./raws/audio.json5";
 
 pub struct AudioPlayer {
     pub player: Option<AudioManager>,
+    /// Picks which sound in a sample is played. It's kept apart from the `GameRng` since sounds are skipped when
+    /// there is no audio device and that shouldn't change the rolls made by gameplay.
+    pub sample_rng: RandomNumberGenerator,
 }
 
 impl AudioPlayer {
@@ -30,8 +36,14 @@ impl AudioPlayer {
                     None
                 }
             },
+            sample_rng: RandomNumberGenerator::seeded(0),
         }
     }
+
+    /// Restarts the sample picks from a seed, called whenever a world is started or loaded
+    pub fn reseed(&mut self, seed: u64) {
+        self.sample_rng = RandomNumberGenerator::seeded(seed);
+    }
 }
 
 pub struct AudioDatabase {
@@ -43,24 +55,10 @@ impl AudioDatabase {
         Self { sounds: HashMap::new() }
     }
 
-    pub fn load(&mut self) {
-        let raw_audio_defs = match fs::read_to_string(AUDIO_DEFINTIONS_FILE) {
-            Ok(ad) => ad,
-            Err(e) => {
-                warn!("Can't load the audio defintions, the audio database will be empty.");
-                error!("Internal Reading Error: {}", e);
-                return;
-            }
-        };
-
-        let audio_defs: Vec<AudioDefinitions> = match json5::from_str(&raw_audio_defs) {
-            Ok(ad) => ad,
-            Err(e) => {
-                warn!("Can't load the audio defintions, the audio database will be empty.");
-                error!("Internal Parsing Error: {}", e);
-                return;
-            }
-        };
+    /// Sound files that can't be read are skipped so the game still runs without them
+    pub fn load() -> Result<Self, DataError> {
+        let mut audio_db = Self::new();
+        let audio_defs: Vec<AudioDefinitions> = read_raws(AUDIO_DEFINTIONS_FILE)?;
 
         for def in audio_defs {
             let mut samples = vec![];
@@ -86,13 +84,14 @@ impl AudioDatabase {
             }
             if def.file_names.len() == 1 {
                 let single = samples[0].clone();
-                self.sounds.insert(def.name.clone(), SoundFiles::Single(Box::new(single)));
+                audio_db.sounds.insert(def.name.clone(), SoundFiles::Single(Box::new(single)));
                 debug!("inserted {} with sound ", def.name);
                 continue;
             }
 
-            self.sounds.insert(def.name.clone(), SoundFiles::Sample(samples.clone()));
+            audio_db.sounds.insert(def.name.clone(), SoundFiles::Sample(samples.clone()));
         }
+        Ok(audio_db)
     }
 }
 
@@ -1,8 +1,5 @@
-use std::fs;
-
 use log::warn;
 use serde::Deserialize;
-use serde_json::from_str;
 use specs::{
     saveload::{MarkedBuilder, SimpleMarker},
     Builder, Entity, World, WorldExt,
@@ -17,10 +14,12 @@ use crate::{
     z_order::BEING_Z,
 };
 
-use super::{EntityBuildError, GameData, OptionalStats, ENTITY_DB};
+use super::{game_data, read_raws, DataError, GameData, IndexedTable, OptionalStats};
+
+pub(crate) const BEINGS_FILE: &str = "raws/beings.json";
 
 pub struct BeingDatabase {
-    data: Vec<Being>,
+    data: IndexedTable<u32, Being>,
 }
 
 #[derive(Deserialize)]
@@ -51,52 +50,51 @@ pub struct RawLoot {
 
 impl BeingDatabase {
     pub(crate) fn empty() -> Self {
-        Self { data: Vec::new() }
+        Self { data: IndexedTable::empty() }
     }
 
     // Uses GameData in order to transform string names into item ids
-    pub fn load(game_db: &GameData) -> Self {
-        let contents: String =
-            fs::read_to_string("raws/beings.json").expect("Unable to find beings.json at `raws/beings.json`");
-        let beings: Vec<RawBeing> = from_str(&contents).expect("Bad JSON in beings.json fix it");
-        BeingDatabase {
-            data: beings
-                .iter()
-                .map(|raw| Being {
-                    identifier: raw.identifier,
-                    name: raw.name.clone(),
-                    ai: raw.ai.clone(),
-                    is_blocking: raw.is_blocking,
-                    atlas_index: raw.atlas_index,
-                    fg: raw.fg,
-                    quips: raw.quips.to_owned(),
-                    stats: raw.stats.as_ref().map_or_else(Stats::zero, Stats::from_optional),
-                    loot: raw.loot.as_ref().map(|raw| Drops::from_raw(raw, game_db)),
-                })
-                .collect(),
+    pub fn load(game_db: &GameData) -> Result<Self, DataError> {
+        let beings: Vec<RawBeing> = read_raws(BEINGS_FILE)?;
+        let mut data = vec![];
+        for raw in beings.iter() {
+            data.push(Being {
+                identifier: raw.identifier,
+                name: raw.name.clone(),
+                ai: raw.ai.clone(),
+                is_blocking: raw.is_blocking,
+                atlas_index: raw.atlas_index,
+                fg: raw.fg,
+                quips: raw.quips.to_owned(),
+                stats: raw.stats.as_ref().map_or_else(Stats::zero, Stats::from_optional),
+                loot: raw
+                    .loot
+                    .as_ref()
+                    .map(|loot| Drops::from_raw(loot, game_db))
+                    .transpose()
+                    .map_err(|reason| DataError::bad_entry(BEINGS_FILE, &raw.name, reason))?,
+            });
         }
+        Ok(BeingDatabase { data: IndexedTable::new(data, |being| (being.identifier.0, &being.name)) })
     }
 
-    pub fn get_by_name(&self, name: &String) -> Option<&Being> {
-        self.data.iter().find(|i| i.name.eq(name))
+    pub fn get_by_name(&self, name: &str) -> Option<&Being> {
+        self.data.get_by_name(name)
     }
 
     #[allow(dead_code)]
     pub fn get_by_id(&self, id: u32) -> Option<&Being> {
-        self.data.iter().find(|i| i.identifier.0 == id)
+        self.data.get_by_id(&id)
     }
 }
 
 /// Attempts to create the specified entity directly into the world
-pub fn build_being(name: impl ToString, pos: Position, world: &mut World) -> Result<Entity, EntityBuildError> {
-    let edb = &ENTITY_DB.lock().unwrap();
+pub fn build_being(name: impl ToString, pos: Position, world: &mut World) -> Result<Entity, DataError> {
+    let edb = game_data();
 
     let raw = match edb.beings.get_by_name(&name.to_string()) {
         Some(raw) => raw,
-        None => {
-            eprintln!("No being found named: {}", name.to_string());
-            return Err(EntityBuildError);
-        }
+        None => return Err(DataError::NotFound { file: BEINGS_FILE, entry: name.to_string() }),
     };
 
     let mut builder = world
@@ -114,17 +112,10 @@ pub fn build_being(name: impl ToString, pos: Position, world: &mut World) -> Res
     if let Some(ai) = &raw.ai {
         builder = match ai.start_mode.as_str() {
             "random_walk" => builder.with(RandomWalkerAI {}),
-            "goal" => {
-                let goals = match &ai.goals {
-                    Some(goals) => goals.iter().map(|goal| Name(goal.to_string())).collect::<Vec<Name>>(),
-                    None => {
-                        warn!("{} has Goal ai type but no defined goals.", &raw.name);
-                        vec![]
-                    }
-                };
-                builder.with(GoalMoverAI::with_desires(&goals, ai.goal_range.unwrap()))
+            "goal" => builder.with(goal_mover_ai(&raw.name, ai)?),
+            mode => {
+                return Err(DataError::bad_entry(BEINGS_FILE, &raw.name, format!("{} is not an ai start mode", mode)))
             }
-            _ => builder,
         };
     }
 
@@ -141,3 +132,17 @@ pub fn build_being(name: impl ToString, pos: Position, world: &mut World) -> Res
 
     Ok(builder.build())
 }
+
+/// The goal ai of a being whose ai starts in the "goal" mode
+pub fn goal_mover_ai(name: &str, ai: &AIDefinition) -> Result<GoalMoverAI, DataError> {
+    let goals = match &ai.goals {
+        Some(goals) => goals.iter().map(|goal| Name(goal.to_string())).collect::<Vec<Name>>(),
+        None => {
+            warn!("{} has Goal ai type but no defined goals.", name);
+            vec![]
+        }
+    };
+    let goal_range =
+        ai.goal_range.ok_or_else(|| DataError::bad_entry(BEINGS_FILE, name, "goal ai has no goal_range"))?;
+    Ok(GoalMoverAI::with_desires(&goals, goal_range))
+}
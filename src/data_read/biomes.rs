@@ -0,0 +1,118 @@
+//! The biome table generated worlds are painted from. The temperature, moisture and height noises under a tile pick
+//! its biome, and the biome decides the ground tiles, the tileset they are drawn with and the resources on them.
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::sync::Mutex;
+
+use crate::{
+    map::WorldTile,
+    noise::{find_tile_map, RawWorldTile},
+};
+
+use super::{ldtk::tileset_font, read_raws, DataError};
+
+pub(crate) const BIOMES_FILE: &str = "raws/biomes.json5";
+
+lazy_static! {
+    pub static ref BIOME_DB: Mutex<BiomeDatabase> = Mutex::new(BiomeDatabase::empty());
+}
+
+pub struct BiomeDatabase {
+    pub biomes: Vec<Biome>,
+}
+
+pub struct Biome {
+    pub name: String,
+    temperature: [f32; 2],
+    moisture: [f32; 2],
+    height: [f32; 2],
+    /// The font the biome's tiles are drawn with
+    pub font: usize,
+    /// Picked by the height noise the same way as a noise's tile mapping
+    tiles: Vec<RawWorldTile>,
+    /// World objects picked by the resources noise, the weight is the chance one is placed
+    resources: Vec<RawWorldTile>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RawBiome {
+    pub(crate) name: String,
+    #[serde(default = "full_range")]
+    pub(crate) temperature: [f32; 2],
+    #[serde(default = "full_range")]
+    pub(crate) moisture: [f32; 2],
+    #[serde(default = "full_range")]
+    pub(crate) height: [f32; 2],
+    pub(crate) font: String,
+    pub(crate) tiles: Vec<RawWorldTile>,
+    #[serde(default)]
+    pub(crate) resources: Vec<RawWorldTile>,
+}
+
+/// A biome without a range for a noise fits every value of it
+fn full_range() -> [f32; 2] {
+    [0.0, 1.0]
+}
+
+impl BiomeDatabase {
+    pub fn empty() -> Self {
+        BiomeDatabase { biomes: Vec::new() }
+    }
+
+    pub fn load() -> Result<Self, DataError> {
+        let raw_biomes: Vec<RawBiome> = read_raws(BIOMES_FILE)?;
+
+        let mut biomes = vec![];
+        for raw in raw_biomes {
+            let font = match tileset_font(&raw.font) {
+                Some(font) => font,
+                None => {
+                    return Err(DataError::bad_entry(BIOMES_FILE, &raw.name, format!("{} is not a font", raw.font)))
+                }
+            };
+            if raw.tiles.is_empty() || raw.tiles.iter().any(|tile| tile.atlas_idx.is_none()) {
+                return Err(DataError::bad_entry(BIOMES_FILE, &raw.name, "every biome needs tiles with an atlas_idx"));
+            }
+            biomes.push(Biome {
+                name: raw.name,
+                temperature: raw.temperature,
+                moisture: raw.moisture,
+                height: raw.height,
+                font,
+                tiles: raw.tiles,
+                resources: raw.resources,
+            });
+        }
+        Ok(Self { biomes })
+    }
+
+    /// Index of the first biome whose ranges hold every noise value, the last biome is used when none do.
+    /// `None` only when there are no biomes.
+    pub fn biome_at(&self, temperature: f32, moisture: f32, height: f32) -> Option<usize> {
+        self.biomes
+            .iter()
+            .position(|biome| biome.fits(temperature, moisture, height))
+            .or_else(|| self.biomes.len().checked_sub(1))
+    }
+}
+
+impl Biome {
+    fn fits(&self, temperature: f32, moisture: f32, height: f32) -> bool {
+        let within = |range: [f32; 2], value: f32| (range[0]..=range[1]).contains(&value);
+        within(self.temperature, temperature) && within(self.moisture, moisture) && within(self.height, height)
+    }
+
+    /// The ground tile where the height noise gave `height`, `None` when the biome has no tile that low
+    pub fn tile_at(&self, height: f32) -> Option<WorldTile> {
+        let mut tile = find_tile_map(&self.tiles, height)?.to_world_tile(height)?;
+        tile.font = Some(self.font);
+        Some(tile)
+    }
+
+    /// The world object and its chance to be placed where the resources noise gave `value`
+    pub fn resource_at(&self, value: f32) -> Option<(String, f32)> {
+        let raw = find_tile_map(&self.resources, value)?;
+        raw.weight.map(|weight| (raw.name, weight))
+    }
+}
@@ -0,0 +1,188 @@
+//! The kinds of dungeon found under generated worlds. A dungeon picks the layout its floors are generated with,
+//! the tiles and world objects it is built from and what lives in it at each depth.
+
+use bracket_lib::random::RandomNumberGenerator;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::sync::Mutex;
+
+use crate::map::WorldTile;
+
+use super::{ldtk::tileset_font, read_raws, DataError, GameData, RawTile};
+
+pub(crate) const DUNGEONS_FILE: &str = "raws/dungeons.json5";
+
+lazy_static! {
+    pub static ref DUNGEON_DB: Mutex<DungeonDatabase> = Mutex::new(DungeonDatabase::empty());
+}
+
+pub struct DungeonDatabase {
+    pub dungeons: Vec<Dungeon>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DungeonLayout {
+    /// Rectangular rooms joined by corridors
+    Rooms,
+    /// Caves grown with a cellular automaton
+    Caves,
+}
+
+pub struct Dungeon {
+    pub name: String,
+    pub layout: DungeonLayout,
+    /// The deepest floor, it has no stairs down
+    pub depths: u32,
+    pub width: usize,
+    pub height: usize,
+    /// The font the floor and wall tiles are drawn with
+    pub font: usize,
+    pub floor: WorldTile,
+    pub wall: WorldTile,
+    /// World objects standing in for the way in and the stairs between floors
+    pub entrance: String,
+    pub stairs_down: String,
+    pub stairs_up: String,
+    /// Chance for each chunk of a generated world to get an entrance
+    pub entrance_chance: f32,
+    pub creatures: DepthTable,
+    pub loot: DepthTable,
+}
+
+/// What is spawned on a floor, more is spawned the deeper the floor is
+#[derive(Deserialize, Clone)]
+pub struct DepthTable {
+    /// How many are spawned on the first floor
+    pub count: usize,
+    /// How many more are spawned on every floor below the first
+    #[serde(default)]
+    pub per_depth: usize,
+    pub table: Vec<DepthEntry>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct DepthEntry {
+    pub name: String,
+    /// The shallowest floor it is spawned on
+    #[serde(default = "first_depth")]
+    pub min_depth: u32,
+    pub weight: u32,
+}
+
+fn first_depth() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RawDungeon {
+    pub(crate) name: String,
+    pub(crate) layout: String,
+    pub(crate) depths: u32,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) font: String,
+    pub(crate) floor: RawTile,
+    pub(crate) wall: RawTile,
+    pub(crate) entrance: String,
+    pub(crate) stairs_down: String,
+    pub(crate) stairs_up: String,
+    pub(crate) entrance_chance: f32,
+    pub(crate) creatures: DepthTable,
+    pub(crate) loot: DepthTable,
+}
+
+/// Floors smaller than this have no room for a layout
+pub(crate) const MIN_FLOOR_SIZE: usize = 20;
+
+impl DungeonLayout {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rooms" => Some(Self::Rooms),
+            "caves" => Some(Self::Caves),
+            _ => None,
+        }
+    }
+}
+
+impl DungeonDatabase {
+    pub fn empty() -> Self {
+        DungeonDatabase { dungeons: Vec::new() }
+    }
+
+    pub fn load(game_db: &GameData) -> Result<Self, DataError> {
+        let raw_dungeons: Vec<RawDungeon> = read_raws(DUNGEONS_FILE)?;
+
+        let mut dungeons = vec![];
+        for raw in raw_dungeons {
+            let bad_entry = |reason: String| DataError::bad_entry(DUNGEONS_FILE, &raw.name, reason);
+            let layout = DungeonLayout::from_name(&raw.layout)
+                .ok_or_else(|| bad_entry(format!("{} is not a dungeon layout", raw.layout)))?;
+            let font = tileset_font(&raw.font).ok_or_else(|| bad_entry(format!("{} is not a font", raw.font)))?;
+            if raw.depths == 0 {
+                return Err(bad_entry("a dungeon needs at least one depth".to_string()));
+            }
+            if raw.width < MIN_FLOOR_SIZE || raw.height < MIN_FLOOR_SIZE {
+                return Err(bad_entry(format!("floors must be at least {} tiles across", MIN_FLOOR_SIZE)));
+            }
+            if !(0.0..=1.0).contains(&raw.entrance_chance) {
+                return Err(bad_entry(format!("entrance_chance {} is not between 0 and 1", raw.entrance_chance)));
+            }
+            for obj in [&raw.entrance, &raw.stairs_down, &raw.stairs_up] {
+                if game_db.world_objs.get_by_name(obj).is_none() {
+                    return Err(bad_entry(format!("{} is not a world object", obj)));
+                }
+            }
+            if let Some(creature) = raw.creatures.table.iter().find(|c| game_db.beings.get_by_name(&c.name).is_none()) {
+                return Err(bad_entry(format!("{} is not a being", creature.name)));
+            }
+            if let Some(item) = raw.loot.table.iter().find(|item| game_db.items.get_by_name(&item.name).is_none()) {
+                return Err(bad_entry(format!("{} has no definition in items", item.name)));
+            }
+            dungeons.push(Dungeon {
+                layout,
+                depths: raw.depths,
+                width: raw.width,
+                height: raw.height,
+                font,
+                floor: raw.floor.to_world_tile(false, None),
+                wall: raw.wall.to_world_tile(true, None),
+                entrance: raw.entrance,
+                stairs_down: raw.stairs_down,
+                stairs_up: raw.stairs_up,
+                entrance_chance: raw.entrance_chance,
+                creatures: raw.creatures,
+                loot: raw.loot,
+                name: raw.name,
+            });
+        }
+        Ok(Self { dungeons })
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&Dungeon> {
+        self.dungeons.iter().find(|dungeon| dungeon.name == name)
+    }
+}
+
+impl DepthTable {
+    /// How many are spawned on the floor at `depth`
+    pub fn count_at(&self, depth: u32) -> usize {
+        self.count + self.per_depth * depth.saturating_sub(1) as usize
+    }
+
+    /// A weighted pick from the entries found at `depth`, `None` when there are none
+    pub fn roll(&self, depth: u32, rng: &mut RandomNumberGenerator) -> Option<&str> {
+        let found = || self.table.iter().filter(move |entry| entry.min_depth <= depth);
+        let total: u32 = found().map(|entry| entry.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.range(0, total);
+        for entry in found() {
+            if roll < entry.weight {
+                return Some(&entry.name);
+            }
+            roll -= entry.weight;
+        }
+        None
+    }
+}
@@ -1,46 +1,40 @@
-use std::fs;
-
 use serde::Deserialize;
-use serde_json::from_str;
 
 use crate::{
-    components::{AttackBonus, Consumable, Equipable},
+    components::{AttackBonus, Consumable, Equipable, EquipmentSlot, LightSource},
     items::{ItemID, ItemInfo},
 };
 
+use super::{read_raws, DataError, IndexedTable};
+
+pub(crate) const ITEMS_FILE: &str = "raws/items.json";
+
 pub struct ItemDatabase {
-    data: Vec<ItemInfo>,
+    data: IndexedTable<ItemID, ItemInfo>,
 }
 
 #[derive(Deserialize)]
 pub struct RawItemDatabase {
-    data: Vec<RawItemInfo>,
+    pub(crate) data: Vec<RawItemInfo>,
 }
 
 impl ItemDatabase {
     pub(crate) fn empty() -> Self {
-        Self { data: Vec::new() }
+        Self { data: IndexedTable::empty() }
     }
 
-    pub fn load() -> Self {
-        let contents: String =
-            fs::read_to_string("raws/items.json").expect("Unable to find items.json at `raws/items.json`");
-        let raw_info_db: RawItemDatabase = from_str(&contents).expect("Bad JSON in items.json fix it");
-        ItemDatabase { data: raw_info_db.data.iter().map(ItemInfo::from_raw).collect() }
+    pub fn load() -> Result<Self, DataError> {
+        let raw_info_db: RawItemDatabase = read_raws(ITEMS_FILE)?;
+        let data = raw_info_db.data.iter().map(ItemInfo::from_raw).collect::<Result<_, _>>()?;
+        Ok(ItemDatabase { data: IndexedTable::new(data, |info| (info.identifier, &info.name)) })
     }
 
     pub fn get_by_name(&self, name: &str) -> Option<&ItemInfo> {
-        self.data.iter().find(|i| i.name.eq(name))
-    }
-
-    /// Gets the entity by name without ensuring it exists.
-    /// This could by panic but can be used when certain a name would exist for an item.
-    pub fn get_by_name_unchecked(&self, name: &String) -> &ItemInfo {
-        self.data.iter().find(|i| i.name.eq(name)).unwrap()
+        self.data.get_by_name(name)
     }
 
     pub fn get_by_id(&self, id: ItemID) -> Option<&ItemInfo> {
-        self.data.iter().find(|i| i.identifier == id)
+        self.data.get_by_id(&id)
     }
 }
 
@@ -56,6 +50,7 @@ pub struct RawItemInfo {
     pub equipable: Option<String>,
     pub attack_bonus: Option<usize>,
     pub consumable: Option<RawConsumable>,
+    pub light_radius: Option<usize>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -65,17 +60,32 @@ pub struct RawConsumable {
 }
 
 impl ItemInfo {
-    fn from_raw(value: &RawItemInfo) -> Self {
-        Self {
+    fn from_raw(value: &RawItemInfo) -> Result<Self, DataError> {
+        let consumable = match &value.consumable {
+            Some(rc) => Some(Consumable::from_str(&rc.effect, rc.amount.unwrap_or(0)).ok_or_else(|| {
+                DataError::bad_entry(ITEMS_FILE, &value.name, format!("{} is not a consumable effect", rc.effect))
+            })?),
+            None => None,
+        };
+        let equipable = match &value.equipable {
+            Some(slot) => Some(Equipable {
+                slot: EquipmentSlot::from_name(slot).ok_or_else(|| {
+                    DataError::bad_entry(ITEMS_FILE, &value.name, format!("{} is not an equipment slot", slot))
+                })?,
+            }),
+            None => None,
+        };
+        Ok(Self {
             identifier: value.identifier,
             name: value.name.clone(),
             examine_text: value.examine_text.clone(),
             atlas_index: value.atlas_index,
             fg: value.fg,
             pickup_text: value.pickup_text.clone(),
-            equipable: value.equipable.clone().map(|e| Equipable::from_str(&e)),
+            equipable,
             attack_bonus: value.attack_bonus.map(|bonus| AttackBonus(bonus as i32)),
-            consumable: value.consumable.clone().map(|rc| Consumable::from_str(&rc.effect, rc.amount.unwrap())),
-        }
+            consumable,
+            light: value.light_radius.map(|radius| LightSource { radius }),
+        })
     }
 }
@@ -0,0 +1,160 @@
+//! The parts of an LDtk project that the game reads. Only what is needed to build levels and prefabs is
+//! deserialized, everything else in the project is ignored.
+
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{FONT_INTERACTABLES, FONT_TERRAIN_FOREST, FONT_TERRAIN_TOWN_FOREST};
+
+use super::{read_raws, DataError};
+
+pub(crate) const LEVELS_FILE: &str = "resources/ldtk/rpg_world_v2.ldtk";
+pub(crate) const PREFABS_LDTK_FILE: &str = "resources/ldtk/prefabs.ldtk";
+
+lazy_static! {
+    /// The hand made levels, read once along with the raws rather than on every level change
+    pub static ref LEVEL_DB: Mutex<LdtkProject> = Mutex::new(LdtkProject::default());
+}
+
+#[derive(Deserialize, Default)]
+pub struct LdtkProject {
+    pub defs: LdtkDefs,
+    pub levels: Vec<LdtkLevel>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct LdtkDefs {
+    pub layers: Vec<LdtkLayerDef>,
+    pub tilesets: Vec<LdtkTileset>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LdtkLayerDef {
+    pub uid: i64,
+    #[serde(default)]
+    pub int_grid_values: Vec<LdtkIntGridValue>,
+}
+
+#[derive(Deserialize)]
+pub struct LdtkIntGridValue {
+    pub value: i64,
+    pub identifier: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LdtkTileset {
+    pub uid: i64,
+    pub rel_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LdtkLevel {
+    pub identifier: String,
+    /// Pixel position of the level in the world
+    pub world_x: i64,
+    pub world_y: i64,
+    pub px_wid: i64,
+    pub px_hei: i64,
+    /// Missing when the project saves its levels in separate files
+    pub layer_instances: Option<Vec<LdtkLayer>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LdtkLayer {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+    #[serde(rename = "__cWid")]
+    pub width: usize,
+    #[serde(rename = "__cHei")]
+    pub height: usize,
+    #[serde(rename = "__gridSize")]
+    pub grid_size: i64,
+    #[serde(rename = "__tilesetDefUid")]
+    pub tileset_def_uid: Option<i64>,
+    pub layer_def_uid: i64,
+    #[serde(default)]
+    pub grid_tiles: Vec<LdtkTile>,
+    #[serde(default)]
+    pub auto_layer_tiles: Vec<LdtkTile>,
+    #[serde(default)]
+    pub int_grid_csv: Vec<i64>,
+    #[serde(default)]
+    pub entity_instances: Vec<LdtkEntity>,
+}
+
+#[derive(Deserialize)]
+pub struct LdtkTile {
+    /// Pixel position of the tile in the layer
+    pub px: [i64; 2],
+    /// Index of the tile in its tileset
+    pub t: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LdtkEntity {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+    /// Cell position of the entity in the layer
+    #[serde(rename = "__grid")]
+    pub grid: [i64; 2],
+    #[serde(default)]
+    pub field_instances: Vec<LdtkField>,
+}
+
+#[derive(Deserialize)]
+pub struct LdtkField {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+    #[serde(rename = "__value")]
+    pub value: Value,
+}
+
+impl LdtkProject {
+    pub fn load(file: &'static str) -> Result<Self, DataError> {
+        read_raws(file)
+    }
+
+    pub fn level(&self, name: &str) -> Option<&LdtkLevel> {
+        self.levels.iter().find(|level| level.identifier == name)
+    }
+
+    pub fn tileset(&self, uid: i64) -> Option<&LdtkTileset> {
+        self.defs.tilesets.iter().find(|tileset| tileset.uid == uid)
+    }
+
+    /// The identifier given to a value of an IntGrid layer, `None` for values without one
+    pub fn int_grid_name(&self, layer: &LdtkLayer, value: i64) -> Option<&str> {
+        let layer_def = self.defs.layers.iter().find(|def| def.uid == layer.layer_def_uid)?;
+        layer_def.int_grid_values.iter().find(|v| v.value == value)?.identifier.as_deref()
+    }
+}
+
+impl LdtkLevel {
+    pub fn layer(&self, identifier: &str) -> Option<&LdtkLayer> {
+        self.layer_instances.iter().flatten().find(|layer| layer.identifier == identifier)
+    }
+}
+
+impl LdtkEntity {
+    pub fn field(&self, identifier: &str) -> Option<&Value> {
+        self.field_instances.iter().find(|field| field.identifier == identifier).map(|field| &field.value)
+    }
+}
+
+/// Maps a tileset image to the font it is loaded as, only the file name of `rel_path` is looked at
+pub(crate) fn tileset_font(rel_path: &str) -> Option<usize> {
+    match rel_path.rsplit('/').next()? {
+        "terrain_forest.png" => Some(FONT_TERRAIN_FOREST),
+        "terrain_town_forest.png" => Some(FONT_TERRAIN_TOWN_FOREST),
+        "interactable_tiles.png" => Some(FONT_INTERACTABLES),
+        _ => None,
+    }
+}
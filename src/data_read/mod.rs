@@ -1,9 +1,17 @@
 mod animations;
 mod audio;
 mod beings;
+pub mod biomes;
+pub mod dungeons;
 mod items;
-mod noise;
+pub mod ldtk;
+pub mod noise;
+mod prefabs;
 mod recipes;
+pub mod spawns;
+pub mod towns;
+mod ui_layout;
+mod validate;
 mod world_objs;
 
 /// A tight bunch of important data reading stuff such as the databases and json loading
@@ -13,39 +21,147 @@ mod world_objs;
 pub mod prelude {
     pub use crate::data_read::animations::ANIMATION_DB;
     pub use crate::data_read::audio::{AUDIOMAN, AUDIO_DB};
-    pub use crate::data_read::beings::build_being;
+    pub use crate::data_read::beings::{build_being, goal_mover_ai};
+    pub use crate::data_read::biomes::BIOME_DB;
+    pub use crate::data_read::dungeons::DUNGEON_DB;
+    pub use crate::data_read::ldtk::LEVEL_DB;
     pub use crate::data_read::noise::NOISE_DB;
+    pub use crate::data_read::prefabs::PREFAB_DB;
     pub use crate::data_read::recipes::RECIPE_DB;
+    pub use crate::data_read::spawns::SPAWN_DB;
+    pub use crate::data_read::towns::TOWN_DB;
+    pub use crate::data_read::ui_layout::UI_LAYOUT;
     pub use crate::data_read::world_objs::build_world_obj;
-    pub use crate::data_read::ENTITY_DB;
+    pub use crate::data_read::{game_data, GameDataRes};
 }
 
-use itertools::Itertools;
+pub(crate) use items::ITEMS_FILE;
+
 use lazy_static::lazy_static;
-use log::{debug, error, warn};
+use log::debug;
 use noise::NOISE_DB;
-use prelude::{ANIMATION_DB, AUDIO_DB};
-use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use prelude::{ANIMATION_DB, AUDIO_DB, UI_LAYOUT};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    hash::Hash,
+    sync::{Arc, RwLock},
+};
 
 use crate::{
     droptables::{DropQty, Drops, Loot},
+    map::WorldTile,
     stats::Stats,
 };
 
 use self::{
+    animations::AnimationDatabase,
+    audio::AudioDatabase,
     beings::{BeingDatabase, RawDrops},
+    biomes::BiomeDatabase,
+    dungeons::DungeonDatabase,
     items::ItemDatabase,
-    prelude::RECIPE_DB,
+    ldtk::{LdtkProject, LEVELS_FILE},
+    noise::NoiseDatabase,
+    prefabs::PrefabDatabase,
+    prelude::{BIOME_DB, DUNGEON_DB, LEVEL_DB, PREFAB_DB, RECIPE_DB, SPAWN_DB, TOWN_DB},
+    recipes::RecipeDatabase,
+    spawns::SpawnDatabase,
+    towns::TownDatabase,
     world_objs::WorldObjectDatabase,
 };
 
+pub use self::validate::validate_raws;
+
 lazy_static! {
-    pub static ref ENTITY_DB: Mutex<GameData> = Mutex::new(GameData::new());
+    /// Swapped out as a whole when the raws are reloaded, anyone holding the old data keeps it until they let go
+    static ref ENTITY_DB: RwLock<Arc<GameData>> = RwLock::new(Arc::new(GameData::new()));
+}
+
+/// A handle to the current item, being and world object data.
+/// Systems should read `GameDataRes` instead so they never touch the global lock.
+pub fn game_data() -> Arc<GameData> {
+    ENTITY_DB.read().unwrap().clone()
+}
+
+/// The same data as `game_data()` kept in the ecs, it is replaced whenever the raws are reloaded
+pub struct GameDataRes(pub Arc<GameData>);
+
+/// Entries in the order of their file with hashed lookups by id and by name.
+/// When two entries share an id or name the first one is found, `--validate-raws` reports the duplicate.
+pub struct IndexedTable<K, T> {
+    data: Vec<T>,
+    by_id: HashMap<K, usize>,
+    by_name: HashMap<String, usize>,
+}
+
+impl<K: Hash + Eq, T> IndexedTable<K, T> {
+    fn empty() -> Self {
+        Self { data: Vec::new(), by_id: HashMap::new(), by_name: HashMap::new() }
+    }
+
+    /// `keys` gives the id and name of an entry
+    fn new(data: Vec<T>, keys: impl Fn(&T) -> (K, &str)) -> Self {
+        let mut by_id = HashMap::with_capacity(data.len());
+        let mut by_name = HashMap::with_capacity(data.len());
+        for (idx, entry) in data.iter().enumerate() {
+            let (id, name) = keys(entry);
+            by_id.entry(id).or_insert(idx);
+            by_name.entry(name.to_string()).or_insert(idx);
+        }
+        Self { data, by_id, by_name }
+    }
+
+    fn get_by_id(&self, id: &K) -> Option<&T> {
+        self.by_id.get(id).map(|idx| &self.data[*idx])
+    }
+
+    fn get_by_name(&self, name: &str) -> Option<&T> {
+        self.by_name.get(name).map(|idx| &self.data[*idx])
+    }
 }
 
-#[derive(Debug)]
-pub struct EntityBuildError;
+/// Describes what went wrong while loading the raws or building an entity out of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataError {
+    /// The file could not be read from the disk
+    Unreadable { file: &'static str, reason: String },
+    /// The file is not valid json
+    Malformed { file: &'static str, reason: String },
+    /// An entry in the file has a bad value or refers to something that is not defined
+    BadEntry { file: &'static str, entry: String, reason: String },
+    /// No entry in the file has the name that was asked for
+    NotFound { file: &'static str, entry: String },
+}
+
+impl DataError {
+    pub fn bad_entry(file: &'static str, entry: impl ToString, reason: impl ToString) -> Self {
+        Self::BadEntry { file, entry: entry.to_string(), reason: reason.to_string() }
+    }
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Unreadable { file, reason } => write!(f, "{} could not be read: {}", file, reason),
+            DataError::Malformed { file, reason } => write!(f, "{} could not be parsed: {}", file, reason),
+            DataError::BadEntry { file, entry, reason } => write!(f, "{} > {}: {}", file, entry, reason),
+            DataError::NotFound { file, entry } => write!(f, "{} has no entry named {}", file, entry),
+        }
+    }
+}
+
+/// Reads and parses a whole raws file, `.json5` files are parsed as json5 and everything else as plain json
+fn read_raws<T: DeserializeOwned>(file: &'static str) -> Result<T, DataError> {
+    let contents = fs::read_to_string(file).map_err(|e| DataError::Unreadable { file, reason: e.to_string() })?;
+    let parsed = if file.ends_with(".json5") {
+        json5::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    };
+    parsed.map_err(|reason| DataError::Malformed { file, reason })
+}
 
 pub struct GameData {
     pub items: ItemDatabase,
@@ -57,31 +173,108 @@ impl GameData {
     fn new() -> Self {
         Self { items: ItemDatabase::empty(), world_objs: WorldObjectDatabase::empty(), beings: BeingDatabase::empty() }
     }
+}
+
+/// The databases that can be reloaded while the game is running, nothing is put into the globals until every
+/// one of them has loaded so a bad file never leaves the game with half of its data changed
+struct ReloadableRaws {
+    game_db: GameData,
+    noise_db: NoiseDatabase,
+    recipe_db: RecipeDatabase,
+    prefab_db: PrefabDatabase,
+    levels: LdtkProject,
+    biome_db: BiomeDatabase,
+    dungeon_db: DungeonDatabase,
+    town_db: TownDatabase,
+    spawn_db: SpawnDatabase,
+}
+
+impl ReloadableRaws {
+    fn load() -> Result<Self, DataError> {
+        let mut game_db = GameData::new();
+
+        // the item database must be loaded first since other tables rely on looking up item names to find their ids
+        game_db.items = ItemDatabase::load()?;
 
-    fn load(&mut self, data: GameData) {
-        *self = data;
+        game_db.world_objs = WorldObjectDatabase::load(&game_db)?;
+
+        game_db.beings = BeingDatabase::load(&game_db)?;
+
+        let recipe_db = RecipeDatabase::load(&game_db)?;
+        let dungeon_db = DungeonDatabase::load(&game_db)?;
+        let town_db = TownDatabase::load(&game_db)?;
+        let biome_db = BiomeDatabase::load()?;
+        let spawn_db = SpawnDatabase::load(&game_db, &biome_db)?;
+        Ok(Self {
+            game_db,
+            noise_db: NoiseDatabase::load()?,
+            recipe_db,
+            prefab_db: PrefabDatabase::load()?,
+            levels: LdtkProject::load(LEVELS_FILE)?,
+            biome_db,
+            dungeon_db,
+            town_db,
+            spawn_db,
+        })
+    }
+
+    fn install(self) {
+        *ENTITY_DB.write().unwrap() = Arc::new(self.game_db);
+        *NOISE_DB.lock().unwrap() = self.noise_db;
+        *RECIPE_DB.lock().unwrap() = self.recipe_db;
+        *PREFAB_DB.lock().unwrap() = self.prefab_db;
+        *LEVEL_DB.lock().unwrap() = self.levels;
+        *BIOME_DB.lock().unwrap() = self.biome_db;
+        *DUNGEON_DB.lock().unwrap() = self.dungeon_db;
+        *TOWN_DB.lock().unwrap() = self.town_db;
+        *SPAWN_DB.lock().unwrap() = self.spawn_db;
     }
 }
 
 /// Creates global instances of static data present in the `raws/` folder
-pub fn initialize_game_databases() {
+pub fn initialize_game_databases() -> Result<(), DataError> {
     debug!("startup: starting to load game databases");
-    let mut game_db = GameData::new();
 
-    // the item database must be loaded first since other tables rely on looking up item names to find their ids
-    game_db.items = ItemDatabase::load();
+    ReloadableRaws::load()?.install();
+    *ANIMATION_DB.lock().unwrap() = AnimationDatabase::load()?;
+    *AUDIO_DB.lock().unwrap() = AudioDatabase::load()?;
+    UI_LAYOUT.lock().unwrap().load();
 
-    game_db.world_objs = WorldObjectDatabase::load(&game_db);
+    debug!("startup: finished loading game databases");
+    Ok(())
+}
 
-    game_db.beings = BeingDatabase::load(&game_db);
+/// Loads the items, beings, world objects, recipes, noises, prefabs, levels, biomes, dungeons and towns again from
+/// the `raws/` folder.
+/// The current data is kept if any of the files fail to load.
+pub fn reload_game_databases() -> Result<(), DataError> {
+    debug!("reloading game databases");
+    ReloadableRaws::load()?.install();
+    // the ui layout keeps working without art so it is reloaded on its own
+    UI_LAYOUT.lock().unwrap().load();
+    debug!("finished reloading game databases");
+    Ok(())
+}
 
-    ENTITY_DB.lock().unwrap().load(game_db);
-    NOISE_DB.lock().unwrap().load();
-    RECIPE_DB.lock().unwrap().load();
-    ANIMATION_DB.lock().unwrap().load();
-    AUDIO_DB.lock().unwrap().load();
+/// A tile that is always the same, given by its name and index in a font
+#[derive(Deserialize)]
+pub(crate) struct RawTile {
+    pub(crate) name: String,
+    pub(crate) atlas_idx: usize,
+}
 
-    debug!("startup: finished loading game databases");
+impl RawTile {
+    /// Blocked tiles can't be seen through either, `font` is `None` for tiles drawn with the map's font
+    fn to_world_tile(&self, is_blocked: bool, font: Option<usize>) -> WorldTile {
+        WorldTile {
+            name: self.name.clone(),
+            atlas_idx: self.atlas_idx,
+            transparent: !is_blocked,
+            is_blocked,
+            height: 0,
+            font,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -108,49 +301,39 @@ impl Stats {
 }
 
 impl Drops {
-    pub(crate) fn from_raw(raw: &RawDrops, game_db: &GameData) -> Self {
-        Self {
-            drop_chance: raw.drop_chance,
-            loot_table: raw
-                .loot_table
-                .iter()
-                .map(|raw_loot| Loot {
-                    id: game_db
-                        .items
-                        .get_by_name(&raw_loot.item)
-                        .unwrap_or_else(|| panic!("{} has no definition in items", raw_loot.item))
-                        .identifier,
-                    qty: DropQty::from_str(&raw_loot.item_qty),
-                    weight: raw_loot.weight,
-                })
-                .collect(),
+    /// The error is only the reason, the caller knows which file and entry the drops belong to
+    pub(crate) fn from_raw(raw: &RawDrops, game_db: &GameData) -> Result<Self, String> {
+        let mut loot_table = vec![];
+        for raw_loot in raw.loot_table.iter() {
+            let item = match game_db.items.get_by_name(&raw_loot.item) {
+                Some(item) => item,
+                None => return Err(format!("loot {} has no definition in items", raw_loot.item)),
+            };
+            loot_table.push(Loot {
+                id: item.identifier,
+                qty: DropQty::parse(&raw_loot.item_qty).map_err(|e| format!("loot {}: {}", raw_loot.item, e))?,
+                weight: raw_loot.weight,
+            });
         }
+        Ok(Self { drop_chance: raw.drop_chance, loot_table })
     }
 }
 
 impl DropQty {
-    fn from_str(qty: &str) -> DropQty {
-        if let Some((colon, _)) = qty.chars().find_position(|c| c.eq(&':')) {
-            let first: String = qty[..colon].chars().collect();
-            let second: String = qty[(colon + 1)..].chars().collect();
-            let min = first.parse().unwrap_or(0);
-            let max = second.parse().unwrap_or(1);
-            if min >= max {
-                warn!("Drop range defined by {} is invalid: The range is empty. Using Single(1) instead", qty);
-                DropQty::Single(1)
-            } else {
-                debug!("Creating Drop range from {} to {}", min, max);
-                DropQty::Range { min, max }
-            }
-        } else {
-            let single_qty = match qty.parse() {
-                Ok(qty) => qty,
-                Err(_) => {
-                    error!("Invalid qty provided, {} for drop qty falling back to 1", qty);
-                    1
-                }
-            };
-            DropQty::Single(single_qty)
+    /// Reads either a single amount such as "2" or a range such as "1:4"
+    pub(crate) fn parse(qty: &str) -> Result<DropQty, String> {
+        let (first, second) = match qty.split_once(':') {
+            Some(range) => range,
+            None => return qty.parse().map(DropQty::Single).map_err(|_| format!("drop qty {} is not a number", qty)),
+        };
+        let (min, max): (usize, usize) = match (first.parse(), second.parse()) {
+            (Ok(min), Ok(max)) => (min, max),
+            _ => return Err(format!("drop range {} is not made of two numbers", qty)),
+        };
+        if min >= max {
+            return Err(format!("drop range defined by {} is invalid: The range is empty", qty));
         }
+        debug!("Creating Drop range from {} to {}", min, max);
+        Ok(DropQty::Range { min, max })
     }
 }
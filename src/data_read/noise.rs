@@ -1,7 +1,6 @@
 use lazy_static::lazy_static;
-use log::{error, info, warn};
+use log::{info, warn};
 use serde::Deserialize;
-use std::fs;
 use std::sync::Mutex;
 
 use bracket_lib::{
@@ -11,17 +10,27 @@ use bracket_lib::{
 
 use crate::noise::{Noise, RawWorldTile};
 
+use super::{read_raws, DataError};
+
 lazy_static! {
     pub static ref NOISE_DB: Mutex<NoiseDatabase> = Mutex::new(NoiseDatabase::empty());
 }
 
-const NOISE_PATH: &str = "raws/noise.json5";
+pub(crate) const NOISE_PATH: &str = "raws/noise.json5";
+pub(crate) const NOISE_PRESETS_FILE: &str = "raws/noise_presets.json5";
+/// The noise the ground is generated from, its tile mapping is the ground outside of any biome
+pub(crate) const HEIGHT_NOISE: &str = "height";
+/// The noise whose tile mapping names world objects to place
+pub(crate) const RESOURCE_NOISE: &str = "resources";
 
 pub struct NoiseDatabase {
     pub noises: Vec<Noise>,
+    /// Settings of every noise as written in the raws, in the same order as `noises`
+    raw_noises: Vec<RawNoise>,
+    pub presets: Vec<NoisePreset>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 struct RawNoise {
     pub name: String,
     pub noise_type: Option<String>,
@@ -33,47 +42,75 @@ struct RawNoise {
     pub tile_mapping: Option<Vec<RawWorldTile>>,
 }
 
+/// A named set of changes to the noises a world is generated from
+#[derive(Deserialize)]
+pub struct NoisePreset {
+    pub name: String,
+    pub(crate) noises: Vec<NoiseOverride>,
+}
+
+/// Settings that replace the ones of the noise with the same name, missing settings are left as they are
+#[derive(Deserialize)]
+pub(crate) struct NoiseOverride {
+    pub(crate) name: String,
+    octaves: Option<i32>,
+    frequency: Option<f32>,
+    lacunarity: Option<f32>,
+    gain: Option<f32>,
+}
+
+/// The noise type named in the raws, `None` when no type has the name
+pub(crate) fn noise_type(name: &str) -> Option<NoiseType> {
+    match name {
+        "perlin" => Some(NoiseType::Perlin),
+        "simplex" => Some(NoiseType::Simplex),
+        "cellular" => Some(NoiseType::Cellular),
+        _ => None,
+    }
+}
+
+/// A noise set up from its raws with the preset's changes on top
+fn fast_noise(raw: &RawNoise, changes: Option<&NoiseOverride>) -> FastNoise {
+    let mut noise = FastNoise::new();
+    if let Some(noise_type) = raw.noise_type.as_deref().and_then(noise_type) {
+        noise.set_noise_type(noise_type);
+    }
+    if let Some(octaves) = changes.and_then(|c| c.octaves).or(raw.octaves) {
+        noise.set_fractal_octaves(octaves);
+    }
+    if let Some(frequency) = changes.and_then(|c| c.frequency).or(raw.frequency) {
+        noise.set_frequency(frequency);
+    }
+    if let Some(lacunarity) = changes.and_then(|c| c.lacunarity).or(raw.lacunarity) {
+        noise.set_fractal_lacunarity(lacunarity);
+    }
+    if let Some(gain) = changes.and_then(|c| c.gain).or(raw.gain) {
+        noise.set_fractal_gain(gain);
+    }
+    noise
+}
+
 impl NoiseDatabase {
     pub fn empty() -> Self {
-        NoiseDatabase { noises: Vec::new() }
+        NoiseDatabase { noises: Vec::new(), raw_noises: Vec::new(), presets: Vec::new() }
     }
 
-    pub fn load(&mut self) {
+    pub fn load() -> Result<Self, DataError> {
         let mut noise_db = Self::empty();
-        let raw_noises =
-            fs::read_to_string(NOISE_PATH).unwrap_or_else(|_| panic!("Failed to read noise file: {}", NOISE_PATH));
-        let raw_noises: Vec<RawNoise> =
-            json5::from_str(&raw_noises).unwrap_or_else(|_| panic!("Failed to parse noise file: {}", NOISE_PATH));
+        let raw_noises: Vec<RawNoise> = read_raws(NOISE_PATH)?;
 
         for noise in raw_noises {
-            let mut parsed = Noise { name: noise.name, scale: PointF::one(), noise: FastNoise::new(), mapping: vec![] };
-
-            if let Some(noise_type) = noise.noise_type {
-                match noise_type.as_str() {
-                    "perlin" => parsed.noise.set_noise_type(NoiseType::Perlin),
-                    "simplex" => parsed.noise.set_noise_type(NoiseType::Simplex),
-                    "cellular" => parsed.noise.set_noise_type(NoiseType::Cellular),
-                    _ => error!("Invalid noise type: {}", noise_type),
-                }
+            if let Some(name) = noise.noise_type.as_ref().filter(|name| noise_type(name).is_none()) {
+                return Err(DataError::bad_entry(NOISE_PATH, &noise.name, format!("{} is not a noise type", name)));
             }
-
-            if let Some(octaves) = noise.octaves {
-                parsed.noise.set_fractal_octaves(octaves);
-            }
-
-            if let Some(frequency) = noise.frequency {
-                parsed.noise.set_frequency(frequency);
-            }
-
-            if let Some(lacunarity) = noise.lacunarity {
-                parsed.noise.set_fractal_lacunarity(lacunarity);
-            }
-
-            if let Some(gain) = noise.gain {
-                parsed.noise.set_fractal_gain(gain);
-            }
-
-            if let Some(scale) = noise.scale {
+            let mut parsed = Noise {
+                name: noise.name.clone(),
+                scale: PointF::one(),
+                noise: fast_noise(&noise, None),
+                mapping: vec![],
+            };
+
+            if let Some(scale) = &noise.scale {
                 if scale.len() != 2 {
                     warn!("Noise Parse: {} Scale does not have exactly 2 values resorting to default", parsed.name);
                 } else {
@@ -81,24 +118,60 @@ impl NoiseDatabase {
                 }
             }
 
-            if let Some(tilemap) = noise.tile_mapping {
-                parsed.mapping = tilemap;
+            if let Some(tilemap) = &noise.tile_mapping {
+                parsed.mapping = tilemap.clone();
             }
 
             noise_db.noises.push(parsed);
+            noise_db.raw_noises.push(noise);
+        }
+
+        match noise_db.get_by_name(HEIGHT_NOISE) {
+            None => return Err(DataError::NotFound { file: NOISE_PATH, entry: HEIGHT_NOISE.to_string() }),
+            Some(height) if height.mapping.iter().any(|tile| tile.atlas_idx.is_none()) => {
+                return Err(DataError::bad_entry(NOISE_PATH, HEIGHT_NOISE, "every tile needs an atlas_idx"))
+            }
+            Some(_) => {}
+        }
+
+        noise_db.presets = read_raws(NOISE_PRESETS_FILE)?;
+        for preset in noise_db.presets.iter() {
+            if let Some(change) = preset.noises.iter().find(|change| noise_db.get_by_name(&change.name).is_none()) {
+                let reason = format!("{} is not a noise", change.name);
+                return Err(DataError::bad_entry(NOISE_PRESETS_FILE, &preset.name, reason));
+            }
         }
 
-        *self = noise_db;
+        Ok(noise_db)
     }
 
+    /// Sets every noise up the way `preset` changes it, or as the raws have it when there is no preset, then seeds
+    /// them for the world
+    pub fn configure(&mut self, seed: u64, preset: Option<&str>) {
+        let preset = preset.and_then(|name| self.presets.iter().find(|p| p.name == name));
+        for (noise, raw) in self.noises.iter_mut().zip(self.raw_noises.iter()) {
+            let changes = preset.and_then(|p| p.noises.iter().find(|change| change.name == noise.name));
+            noise.noise = fast_noise(raw, changes);
+        }
+        self.reseed(seed);
+    }
+
+    /// Each noise is offset from the world seed by its place in the file so layers with the same settings differ
     pub fn reseed(&mut self, seed: u64) {
         info!("seed for world set to {}", seed);
-        for noise in &mut self.noises {
-            noise.noise.set_seed(seed);
+        for (offset, noise) in self.noises.iter_mut().enumerate() {
+            noise.noise.set_seed(seed.wrapping_add(offset as u64));
         }
     }
 
     pub fn get_by_name(&self, name: &str) -> Option<&Noise> {
         self.noises.iter().find(|n| n.name == name)
     }
+
+    /// The preset picked after `current` when cycling through them, an empty name is no preset
+    pub fn preset_after(&self, current: &str) -> String {
+        let names: Vec<&str> = std::iter::once("").chain(self.presets.iter().map(|p| p.name.as_str())).collect();
+        let at = names.iter().position(|name| *name == current).unwrap_or(0);
+        names[(at + 1) % names.len()].to_string()
+    }
 }
@@ -0,0 +1,76 @@
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::sync::Mutex;
+
+use super::{
+    ldtk::{LdtkLevel, LdtkProject, PREFABS_LDTK_FILE},
+    read_raws, DataError,
+};
+
+pub(crate) const PREFABS_FILE: &str = "raws/prefabs.json5";
+
+lazy_static! {
+    pub static ref PREFAB_DB: Mutex<PrefabDatabase> = Mutex::new(PrefabDatabase::empty());
+}
+
+/// The prefab levels along with the rules for where they are placed in generated worlds
+pub struct PrefabDatabase {
+    pub project: LdtkProject,
+    pub prefabs: Vec<Prefab>,
+}
+
+pub struct Prefab {
+    pub name: String,
+    /// Index of the prefab's level in the project
+    level_idx: usize,
+    pub chance: f32,
+    pub min_spacing: usize,
+    pub max_height_diff: u8,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RawPrefab {
+    pub(crate) level: String,
+    pub(crate) name: String,
+    pub(crate) chance: f32,
+    pub(crate) min_spacing: usize,
+    pub(crate) max_height_diff: u8,
+}
+
+impl PrefabDatabase {
+    pub fn empty() -> Self {
+        Self { project: LdtkProject::default(), prefabs: Vec::new() }
+    }
+
+    pub fn load() -> Result<Self, DataError> {
+        let project = LdtkProject::load(PREFABS_LDTK_FILE)?;
+        let raw_prefabs: Vec<RawPrefab> = read_raws(PREFABS_FILE)?;
+
+        let mut prefabs = vec![];
+        for raw in raw_prefabs {
+            let level_idx = match project.levels.iter().position(|level| level.identifier == raw.level) {
+                Some(idx) => idx,
+                None => {
+                    let reason = format!("level {} is not in {}", raw.level, PREFABS_LDTK_FILE);
+                    return Err(DataError::bad_entry(PREFABS_FILE, &raw.name, reason));
+                }
+            };
+            if !(0.0..=1.0).contains(&raw.chance) {
+                let reason = format!("chance {} is not between 0 and 1", raw.chance);
+                return Err(DataError::bad_entry(PREFABS_FILE, &raw.name, reason));
+            }
+            prefabs.push(Prefab {
+                name: raw.name,
+                level_idx,
+                chance: raw.chance,
+                min_spacing: raw.min_spacing,
+                max_height_diff: raw.max_height_diff,
+            });
+        }
+        Ok(Self { project, prefabs })
+    }
+
+    pub fn level_of(&self, prefab: &Prefab) -> &LdtkLevel {
+        &self.project.levels[prefab.level_idx]
+    }
+}
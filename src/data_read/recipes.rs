@@ -4,10 +4,11 @@ use crate::{
 };
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use serde_json::from_str;
-use std::{fs, sync::Mutex};
+use std::sync::Mutex;
 
-use super::ENTITY_DB;
+use super::{read_raws, DataError, GameData};
+
+pub(crate) const RECIPES_FILE: &str = "raws/recipes.json";
 
 lazy_static! {
     pub static ref RECIPE_DB: Mutex<RecipeDatabase> = Mutex::new(RecipeDatabase::new());
@@ -22,39 +23,38 @@ impl RecipeDatabase {
         Self { use_with_recipes: Vec::new() }
     }
 
-    pub fn load(&mut self) {
-        let contents: String =
-            fs::read_to_string("raws/recipes.json").expect("Unable to find recipes.json at `raws/recipes.json`");
-        let recipes: Vec<RawRecipe> = from_str(&contents).expect("Bad JSON in recipes.json fix it");
-        let edb = &ENTITY_DB.lock().unwrap();
-        self.use_with_recipes = recipes
-            .iter()
-            .map(|r| UseWithRecipe {
+    /// Uses the item database in `game_db` to turn the item names into ids
+    pub fn load(game_db: &GameData) -> Result<Self, DataError> {
+        let recipes: Vec<RawRecipe> = read_raws(RECIPES_FILE)?;
+
+        let mut use_with_recipes = vec![];
+        for r in recipes.iter() {
+            let item_id = |name: &String| {
+                game_db.items.get_by_name(name).map(|info| info.identifier).ok_or_else(|| {
+                    DataError::bad_entry(RECIPES_FILE, &r.output, format!("{} has no definition in items", name))
+                })
+            };
+            use_with_recipes.push(UseWithRecipe {
                 ingredients: vec![
-                    Ingredient {
-                        id: edb.items.get_by_name_unchecked(&r.first.name).identifier,
-                        consume: r.first.consume.map(ItemQty),
-                    },
-                    Ingredient {
-                        id: edb.items.get_by_name_unchecked(&r.second.name).identifier,
-                        consume: r.second.consume.map(ItemQty),
-                    },
+                    Ingredient { id: item_id(&r.first.name)?, consume: r.first.consume.map(ItemQty) },
+                    Ingredient { id: item_id(&r.second.name)?, consume: r.second.consume.map(ItemQty) },
                 ],
-                output: edb.items.get_by_name_unchecked(&r.output).identifier,
-            })
-            .collect();
+                output: item_id(&r.output)?,
+            });
+        }
+        Ok(Self { use_with_recipes })
     }
 }
 
 #[derive(Deserialize, Serialize)]
-struct RawRecipe {
-    first: RawIngredient,
-    second: RawIngredient,
-    output: String,
+pub(crate) struct RawRecipe {
+    pub(crate) first: RawIngredient,
+    pub(crate) second: RawIngredient,
+    pub(crate) output: String,
 }
 
 #[derive(Deserialize, Serialize)]
-struct RawIngredient {
-    name: String,
+pub(crate) struct RawIngredient {
+    pub(crate) name: String,
     consume: Option<usize>,
 }
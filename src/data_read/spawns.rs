@@ -0,0 +1,84 @@
+//! Where the beings of generated worlds live. Every open tile a spawn fits is rolled against its density and a
+//! successful roll puts a whole group down around that tile.
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::sync::Mutex;
+
+use super::{biomes::BiomeDatabase, read_raws, DataError, GameData};
+
+pub(crate) const SPAWNS_FILE: &str = "raws/spawns.json5";
+
+lazy_static! {
+    pub static ref SPAWN_DB: Mutex<SpawnDatabase> = Mutex::new(SpawnDatabase::empty());
+}
+
+pub struct SpawnDatabase {
+    pub spawns: Vec<Spawn>,
+}
+
+#[derive(Deserialize)]
+pub struct Spawn {
+    /// The being that is spawned
+    pub name: String,
+    /// Biomes it is found in, every biome when empty
+    #[serde(default)]
+    pub biomes: Vec<String>,
+    /// Ground tiles it is found on, every open tile when empty
+    #[serde(default)]
+    pub tiles: Vec<String>,
+    /// Chance for each open tile to start a group
+    pub density: f32,
+    /// Fewest and most beings in a group
+    #[serde(default = "single")]
+    pub group: [usize; 2],
+    /// Groups never start closer than this many tiles to where the player starts
+    #[serde(default)]
+    pub min_player_distance: usize,
+}
+
+fn single() -> [usize; 2] {
+    [1, 1]
+}
+
+impl Spawn {
+    /// What is wrong with the spawn's numbers, if anything
+    pub(crate) fn number_problem(&self) -> Option<String> {
+        if !(0.0..=1.0).contains(&self.density) {
+            Some(format!("density {} is not between 0 and 1", self.density))
+        } else if self.group[0] == 0 || self.group[0] > self.group[1] {
+            Some(format!("{:?} is not a group size range starting at 1 or more", self.group))
+        } else {
+            None
+        }
+    }
+
+    /// `biome` is `None` when the world has no biomes, only spawns without biomes fit then
+    pub fn fits(&self, biome: Option<&str>, tile: &str) -> bool {
+        let in_biome = self.biomes.is_empty() || biome.is_some_and(|biome| self.biomes.iter().any(|b| b == biome));
+        in_biome && (self.tiles.is_empty() || self.tiles.iter().any(|t| t == tile))
+    }
+}
+
+impl SpawnDatabase {
+    pub fn empty() -> Self {
+        SpawnDatabase { spawns: Vec::new() }
+    }
+
+    pub fn load(game_db: &GameData, biome_db: &BiomeDatabase) -> Result<Self, DataError> {
+        let spawns: Vec<Spawn> = read_raws(SPAWNS_FILE)?;
+
+        for spawn in spawns.iter() {
+            if game_db.beings.get_by_name(&spawn.name).is_none() {
+                return Err(DataError::bad_entry(SPAWNS_FILE, &spawn.name, "spawn is not a being"));
+            }
+            if let Some(problem) = spawn.number_problem() {
+                return Err(DataError::bad_entry(SPAWNS_FILE, &spawn.name, problem));
+            }
+            if let Some(biome) = spawn.biomes.iter().find(|name| !biome_db.biomes.iter().any(|b| &b.name == *name)) {
+                return Err(DataError::bad_entry(SPAWNS_FILE, &spawn.name, format!("{} is not a biome", biome)));
+            }
+        }
+        Ok(Self { spawns })
+    }
+}
@@ -0,0 +1,147 @@
+//! The kinds of settlement built on the land of generated worlds, what they are built from and who lives in them.
+
+use bracket_lib::random::RandomNumberGenerator;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::sync::Mutex;
+
+use crate::{chunks::CHUNK_SIZE, map::WorldTile};
+
+use super::{ldtk::tileset_font, read_raws, DataError, GameData, RawTile};
+
+pub(crate) const TOWNS_FILE: &str = "raws/towns.json5";
+
+lazy_static! {
+    pub static ref TOWN_DB: Mutex<TownDatabase> = Mutex::new(TownDatabase::empty());
+}
+
+pub struct TownDatabase {
+    pub towns: Vec<Town>,
+}
+
+pub struct Town {
+    pub name: String,
+    /// Chance for each chunk of a generated world to get one
+    pub chance: f32,
+    pub width: usize,
+    pub height: usize,
+    /// The most the height of the land under the town can vary
+    pub max_height_diff: u8,
+    pub road: WorldTile,
+    pub floor: WorldTile,
+    pub wall: WorldTile,
+    pub door: WorldTile,
+    /// Smallest and largest width and height of a house, walls included
+    pub house_size: [usize; 2],
+    /// Chance for each house to have someone living in it
+    pub resident_chance: f32,
+    residents: Vec<Resident>,
+    /// World object built at both ends of the road
+    pub campfire: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Resident {
+    pub(crate) name: String,
+    pub(crate) weight: u32,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RawTown {
+    pub(crate) name: String,
+    pub(crate) chance: f32,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) max_height_diff: u8,
+    pub(crate) font: String,
+    pub(crate) road: RawTile,
+    pub(crate) floor: RawTile,
+    pub(crate) wall: RawTile,
+    pub(crate) door: RawTile,
+    pub(crate) house_size: [usize; 2],
+    pub(crate) resident_chance: f32,
+    pub(crate) residents: Vec<Resident>,
+    pub(crate) campfire: Option<String>,
+}
+
+/// Houses smaller than this have no room inside of them
+pub(crate) const MIN_HOUSE_SIZE: usize = 3;
+
+impl RawTown {
+    /// What is wrong with the town's numbers, if anything
+    pub(crate) fn size_problem(&self) -> Option<String> {
+        let [smallest, largest] = self.house_size;
+        if self.width > CHUNK_SIZE || self.height > CHUNK_SIZE {
+            Some(format!("towns can't be bigger than a chunk, {} tiles across", CHUNK_SIZE))
+        } else if smallest < MIN_HOUSE_SIZE || smallest > largest {
+            Some(format!("{:?} is not a house size range starting at {} or more", self.house_size, MIN_HOUSE_SIZE))
+        } else if self.height < largest * 2 + 4 || self.width < largest + 2 {
+            Some("the town is too small for two rows of its largest houses and a road".to_string())
+        } else if !(0.0..=1.0).contains(&self.chance) || !(0.0..=1.0).contains(&self.resident_chance) {
+            Some("chances must be between 0 and 1".to_string())
+        } else {
+            None
+        }
+    }
+}
+
+impl TownDatabase {
+    pub fn empty() -> Self {
+        TownDatabase { towns: Vec::new() }
+    }
+
+    pub fn load(game_db: &GameData) -> Result<Self, DataError> {
+        let raw_towns: Vec<RawTown> = read_raws(TOWNS_FILE)?;
+
+        let mut towns = vec![];
+        for raw in raw_towns {
+            let bad_entry = |reason: String| DataError::bad_entry(TOWNS_FILE, &raw.name, reason);
+            let font = tileset_font(&raw.font).ok_or_else(|| bad_entry(format!("{} is not a font", raw.font)))?;
+            if let Some(problem) = raw.size_problem() {
+                return Err(bad_entry(problem));
+            }
+            if let Some(resident) = raw.residents.iter().find(|r| game_db.beings.get_by_name(&r.name).is_none()) {
+                return Err(bad_entry(format!("{} is not a being", resident.name)));
+            }
+            if let Some(campfire) = &raw.campfire {
+                if game_db.world_objs.get_by_name(campfire).is_none() {
+                    return Err(bad_entry(format!("{} is not a world object", campfire)));
+                }
+            }
+            towns.push(Town {
+                chance: raw.chance,
+                width: raw.width,
+                height: raw.height,
+                max_height_diff: raw.max_height_diff,
+                road: raw.road.to_world_tile(false, Some(font)),
+                floor: raw.floor.to_world_tile(false, Some(font)),
+                wall: raw.wall.to_world_tile(true, Some(font)),
+                door: raw.door.to_world_tile(false, Some(font)),
+                house_size: raw.house_size,
+                resident_chance: raw.resident_chance,
+                residents: raw.residents,
+                campfire: raw.campfire,
+                name: raw.name,
+            });
+        }
+        Ok(Self { towns })
+    }
+}
+
+impl Town {
+    /// A weighted pick of who lives in a house, `None` when the town has no residents
+    pub fn roll_resident(&self, rng: &mut RandomNumberGenerator) -> Option<&str> {
+        let total: u32 = self.residents.iter().map(|resident| resident.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.range(0, total);
+        for resident in self.residents.iter() {
+            if roll < resident.weight {
+                return Some(&resident.name);
+            }
+            roll -= resident.weight;
+        }
+        None
+    }
+}
@@ -0,0 +1,120 @@
+use std::{collections::HashMap, fs::File, sync::Mutex};
+
+use bracket_lib::{
+    prelude::XpFile,
+    terminal::{Point, Rect, RGB},
+};
+use lazy_static::lazy_static;
+use log::error;
+use serde::Deserialize;
+
+use super::read_raws;
+
+pub(crate) const UI_LAYOUT_FILE: &str = "resources/rex/ui_layout.json5";
+
+lazy_static! {
+    pub static ref UI_LAYOUT: Mutex<UiLayout> = Mutex::new(UiLayout::new());
+}
+
+/// The ui panels that are drawn from REX Paint art, along with the art they are cut out of
+pub struct UiLayout {
+    arts: HashMap<String, XpFile>,
+    panels: HashMap<String, UiPanel>,
+}
+
+pub struct UiPanel {
+    /// Path of the .xp file the panel is cut out of
+    pub art: String,
+    /// Where the panel is in the art and on the screen
+    pub region: Rect,
+    /// Fills the panel before the art is drawn, the transparent cells of the art show it
+    pub background: Option<RGB>,
+    anchors: HashMap<String, Rect>,
+}
+
+impl UiPanel {
+    pub fn anchor(&self, name: &str) -> Option<Rect> {
+        self.anchors.get(name).copied()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawPanel {
+    name: String,
+    art: String,
+    region: [i32; 4],
+    background: Option<(u8, u8, u8)>,
+    #[serde(default)]
+    anchors: Vec<RawAnchor>,
+}
+
+#[derive(Deserialize)]
+struct RawAnchor {
+    name: String,
+    region: [i32; 4],
+}
+
+fn to_rect([x, y, width, height]: [i32; 4]) -> Rect {
+    Rect::with_size(x, y, width, height)
+}
+
+impl UiLayout {
+    pub fn new() -> Self {
+        Self { arts: HashMap::new(), panels: HashMap::new() }
+    }
+
+    pub fn panel(&self, name: &str) -> Option<&UiPanel> {
+        self.panels.get(name)
+    }
+
+    pub fn art(&self, path: &str) -> Option<&XpFile> {
+        self.arts.get(path)
+    }
+
+    /// Panels whose art can't be read are left out so the game draws them without art instead,
+    /// anchors outside of their panel are left out as well
+    pub fn load(&mut self) {
+        let mut layout = Self::new();
+        let raw_panels: Vec<RawPanel> = match read_raws(UI_LAYOUT_FILE) {
+            Ok(panels) => panels,
+            Err(e) => {
+                error!("Ui layout could not be loaded: {}", e);
+                *self = layout;
+                return;
+            }
+        };
+
+        for raw in raw_panels {
+            if !layout.arts.contains_key(&raw.art) {
+                match File::open(&raw.art).and_then(|mut file| XpFile::read(&mut file)) {
+                    Ok(art) => {
+                        layout.arts.insert(raw.art.clone(), art);
+                    }
+                    Err(e) => {
+                        error!("Ui art {} for {} could not be read: {}", raw.art, raw.name, e);
+                        continue;
+                    }
+                }
+            }
+
+            let region = to_rect(raw.region);
+            let mut anchors = HashMap::new();
+            for anchor in raw.anchors {
+                let anchor_region = to_rect(anchor.region);
+                let corner = Point::new(anchor_region.x2 - 1, anchor_region.y2 - 1);
+                if !region.point_in_rect(Point::new(anchor_region.x1, anchor_region.y1))
+                    || !region.point_in_rect(corner)
+                {
+                    error!("Ui panel {} has anchor {} outside of its region", raw.name, anchor.name);
+                    continue;
+                }
+                anchors.insert(anchor.name, anchor_region);
+            }
+
+            let background = raw.background.map(|(r, g, b)| RGB::from_u8(r, g, b));
+            layout.panels.insert(raw.name, UiPanel { art: raw.art, region, background, anchors });
+        }
+
+        *self = layout;
+    }
+}
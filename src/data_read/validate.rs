@@ -0,0 +1,428 @@
+//! Checks every file in `raws/` for problems that would otherwise show up as a panic or a silently missing
+//! sound in the middle of a game. Run it with the `--validate-raws` launch argument.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    components::{Breakable, Consumable, EquipmentSlot},
+    droptables::DropQty,
+};
+
+use super::{
+    animations::ANIMATION_FILE,
+    audio::{AUDIO_DEFINTIONS_FILE, AUDIO_DIRECTORY},
+    beings::{RawBeing, RawDrops, BEINGS_FILE},
+    biomes::{RawBiome, BIOMES_FILE},
+    dungeons::{DungeonLayout, RawDungeon, DUNGEONS_FILE, MIN_FLOOR_SIZE},
+    items::{RawItemDatabase, ITEMS_FILE},
+    ldtk::{tileset_font, LdtkProject, PREFABS_LDTK_FILE},
+    noise::{noise_type, NoisePreset, NOISE_PATH, NOISE_PRESETS_FILE, RESOURCE_NOISE},
+    prefabs::{RawPrefab, PREFABS_FILE},
+    read_raws,
+    recipes::{RawRecipe, RECIPES_FILE},
+    spawns::{Spawn, SPAWNS_FILE},
+    towns::{RawTown, TOWNS_FILE},
+    world_objs::{RawWorldObject, WORLD_OBJS_FILE},
+    DataError,
+};
+
+/// Sounds played by name from the code rather than from the raws
+const SOUNDS_USED_BY_CODE: [&str; 4] = ["pickup", "ui_inventory", "confirm", "ui_move"];
+/// Animations played by name from the code rather than from the raws
+const ANIMATIONS_USED_BY_CODE: [&str; 1] = ["main_menu_intro"];
+/// Noises the world generation looks up by name
+const NOISES_USED_BY_CODE: [&str; 4] = ["height", "resources", "temperature", "moisture"];
+/// Goals may point at the player which is not defined in the raws
+const PLAYER_NAME: &str = "Player";
+/// Every file under here has to be one the game reads
+const RAWS_DIRECTORY: &str = "raws";
+
+#[derive(Default)]
+struct RawsReport {
+    problems: Vec<DataError>,
+    /// Every file `read` was asked for, readable or not
+    read_files: Vec<&'static str>,
+}
+
+impl RawsReport {
+    fn problem(&mut self, file: &'static str, entry: impl ToString, reason: impl ToString) {
+        self.problems.push(DataError::bad_entry(file, entry, reason));
+    }
+
+    /// A file that can't be read is reported and `None` is returned
+    fn read<T: DeserializeOwned>(&mut self, file: &'static str) -> Option<T> {
+        self.read_files.push(file);
+        match read_raws(file) {
+            Ok(raws) => Some(raws),
+            Err(e) => {
+                self.problems.push(e);
+                None
+            }
+        }
+    }
+
+    /// Reports every file under `RAWS_DIRECTORY` that was never read, the game would not read it either
+    fn unread_files(&mut self) {
+        let read: HashSet<PathBuf> = self.read_files.iter().map(|file| normalized(Path::new(file))).collect();
+        let mut folders = vec![PathBuf::from(RAWS_DIRECTORY)];
+        while let Some(folder) = folders.pop() {
+            let entries = match fs::read_dir(&folder) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    self.problem(RAWS_DIRECTORY, folder.display(), format!("folder could not be read: {}", e));
+                    continue;
+                }
+            };
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.is_dir() {
+                    folders.push(path);
+                } else if !read.contains(&normalized(&path)) {
+                    self.problem(RAWS_DIRECTORY, path.display(), "file is not read by the game");
+                }
+            }
+        }
+    }
+
+    /// Reports every id and name that is used by more than one entry, the names are returned for lookups
+    fn unique<'a>(
+        &mut self,
+        file: &'static str,
+        entries: impl Iterator<Item = (usize, &'a String)>,
+    ) -> HashSet<String> {
+        let mut ids = HashSet::new();
+        let mut names = HashSet::new();
+        for (id, name) in entries {
+            if !ids.insert(id) {
+                self.problem(file, name, format!("identifier {} is already used by another entry", id));
+            }
+            if !names.insert(name.clone()) {
+                self.problem(file, name, "name is already used by another entry");
+            }
+        }
+        names
+    }
+
+    /// `items` is None when items.json could not be read, the item names are not checked then
+    fn drops(&mut self, file: &'static str, entry: &str, drops: &RawDrops, items: Option<&HashSet<String>>) {
+        if drops.drop_chance > 100 {
+            self.problem(file, entry, format!("drop chance {} is over 100", drops.drop_chance));
+        }
+        for loot in drops.loot_table.iter() {
+            if items.is_some_and(|items| !items.contains(&loot.item)) {
+                self.problem(file, entry, format!("loot {} has no definition in items", loot.item));
+            }
+            if let Err(e) = DropQty::parse(&loot.item_qty) {
+                self.problem(file, entry, format!("loot {}: {}", loot.item, e));
+            }
+        }
+    }
+}
+
+/// The path without any `./` so paths written either way can be compared
+fn normalized(path: &Path) -> PathBuf {
+    path.components().filter(|part| *part != Component::CurDir).collect()
+}
+
+#[derive(Deserialize)]
+struct RawSound {
+    name: String,
+    directory: Option<String>,
+    file_names: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawNamed {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RawNoiseMapping {
+    name: String,
+    noise_type: Option<String>,
+    tile_mapping: Option<Vec<RawNamed>>,
+}
+
+/// Loads every raws file and returns all the problems found in them, an empty list means the raws are valid
+pub fn validate_raws() -> Vec<DataError> {
+    let mut report = RawsReport::default();
+
+    let items: Option<RawItemDatabase> = report.read(ITEMS_FILE);
+    let world_objs: Option<Vec<RawWorldObject>> = report.read(WORLD_OBJS_FILE);
+    let beings: Option<Vec<RawBeing>> = report.read(BEINGS_FILE);
+    let recipes: Option<Vec<RawRecipe>> = report.read(RECIPES_FILE);
+    let sounds: Option<Vec<RawSound>> = report.read(AUDIO_DEFINTIONS_FILE);
+    let animations: Option<Vec<RawNamed>> = report.read(ANIMATION_FILE);
+    let noises: Option<Vec<RawNoiseMapping>> = report.read(NOISE_PATH);
+    let noise_presets: Option<Vec<NoisePreset>> = report.read(NOISE_PRESETS_FILE);
+    let prefabs: Option<Vec<RawPrefab>> = report.read(PREFABS_FILE);
+    let prefab_levels: Option<LdtkProject> = report.read(PREFABS_LDTK_FILE);
+    let biomes: Option<Vec<RawBiome>> = report.read(BIOMES_FILE);
+    let dungeons: Option<Vec<RawDungeon>> = report.read(DUNGEONS_FILE);
+    let towns: Option<Vec<RawTown>> = report.read(TOWNS_FILE);
+    let spawns: Option<Vec<Spawn>> = report.read(SPAWNS_FILE);
+
+    let item_names = items
+        .as_ref()
+        .map(|items| report.unique(ITEMS_FILE, items.data.iter().map(|item| (item.identifier.0 as usize, &item.name))));
+    let world_obj_names = world_objs
+        .as_ref()
+        .map(|objs| report.unique(WORLD_OBJS_FILE, objs.iter().map(|obj| (obj.identifier, &obj.name))));
+    let being_names = beings.as_ref().map(|beings| {
+        report.unique(BEINGS_FILE, beings.iter().map(|being| (being.identifier.0 as usize, &being.name)))
+    });
+    let sound_names = sounds.as_ref().map(|sounds| sounds.iter().map(|s| s.name.clone()).collect::<HashSet<_>>());
+
+    if let Some(items) = &items {
+        for item in items.data.iter() {
+            if let Some(slot) = &item.equipable {
+                if EquipmentSlot::from_name(slot).is_none() {
+                    report.problem(ITEMS_FILE, &item.name, format!("{} is not an equipment slot", slot));
+                }
+            }
+            if let Some(consumable) = &item.consumable {
+                if Consumable::from_str(&consumable.effect, 0).is_none() {
+                    let reason = format!("{} is not a consumable effect", consumable.effect);
+                    report.problem(ITEMS_FILE, &item.name, reason);
+                }
+            }
+        }
+    }
+
+    if let Some(world_objs) = &world_objs {
+        for obj in world_objs.iter() {
+            if let Some(breakable) = &obj.breakable {
+                if Breakable::from_str(breakable).is_err() {
+                    report.problem(WORLD_OBJS_FILE, &obj.name, format!("{} is not a breakable tool", breakable));
+                }
+            }
+            if let Some(sound) = obj.impact_sound.as_ref().filter(|sound| !sound.is_empty()) {
+                if sound_names.as_ref().is_some_and(|names| !names.contains(sound)) {
+                    report.problem(WORLD_OBJS_FILE, &obj.name, format!("impact sound {} is not in audio", sound));
+                }
+            }
+            if let Some(loot) = &obj.loot {
+                report.drops(WORLD_OBJS_FILE, &obj.name, loot, item_names.as_ref());
+            }
+        }
+    }
+
+    if let Some(beings) = &beings {
+        for being in beings.iter() {
+            if let Some(ai) = &being.ai {
+                match ai.start_mode.as_str() {
+                    "random_walk" => {}
+                    "goal" => {
+                        if ai.goal_range.is_none() {
+                            report.problem(BEINGS_FILE, &being.name, "goal ai has no goal_range");
+                        }
+                        for goal in ai.goals.iter().flatten() {
+                            let known = goal == PLAYER_NAME
+                                || [&item_names, &world_obj_names, &being_names]
+                                    .iter()
+                                    .any(|names| names.as_ref().is_none_or(|names| names.contains(goal)));
+                            if !known {
+                                let reason = format!("goal {} is not an item, world object or being", goal);
+                                report.problem(BEINGS_FILE, &being.name, reason);
+                            }
+                        }
+                    }
+                    mode => report.problem(BEINGS_FILE, &being.name, format!("{} is not an ai start mode", mode)),
+                }
+            }
+            if let Some(loot) = &being.loot {
+                report.drops(BEINGS_FILE, &being.name, loot, item_names.as_ref());
+            }
+        }
+    }
+
+    if let (Some(recipes), Some(item_names)) = (&recipes, &item_names) {
+        for recipe in recipes.iter() {
+            for name in [&recipe.first.name, &recipe.second.name, &recipe.output] {
+                if !item_names.contains(name) {
+                    report.problem(RECIPES_FILE, &recipe.output, format!("{} has no definition in items", name));
+                }
+            }
+        }
+    }
+
+    if let Some(sounds) = &sounds {
+        for sound in sounds.iter() {
+            let directory = match &sound.directory {
+                Some(dir) => format!("{}/{}", AUDIO_DIRECTORY, dir),
+                None => AUDIO_DIRECTORY.to_string(),
+            };
+            for file_name in sound.file_names.iter() {
+                if !Path::new(&directory).join(file_name).is_file() {
+                    report.problem(
+                        AUDIO_DEFINTIONS_FILE,
+                        &sound.name,
+                        format!("{}/{} does not exist", directory, file_name),
+                    );
+                }
+            }
+        }
+        for name in SOUNDS_USED_BY_CODE {
+            if !sounds.iter().any(|sound| sound.name == name) {
+                report.problem(AUDIO_DEFINTIONS_FILE, name, "sound is played by the game but not defined");
+            }
+        }
+    }
+
+    if let Some(animations) = &animations {
+        for name in ANIMATIONS_USED_BY_CODE {
+            if !animations.iter().any(|anim| anim.name == name) {
+                report.problem(ANIMATION_FILE, name, "animation is played by the game but not defined");
+            }
+        }
+    }
+
+    if let Some(noises) = &noises {
+        for noise in noises.iter() {
+            if let Some(type_name) = &noise.noise_type {
+                if noise_type(type_name).is_none() {
+                    report.problem(NOISE_PATH, &noise.name, format!("{} is not a noise type", type_name));
+                }
+            }
+            if noise.name != RESOURCE_NOISE {
+                continue;
+            }
+            for mapping in noise.tile_mapping.iter().flatten() {
+                if world_obj_names.as_ref().is_some_and(|names| !names.contains(&mapping.name)) {
+                    report.problem(NOISE_PATH, &noise.name, format!("{} is not a world object", mapping.name));
+                }
+            }
+        }
+        for name in NOISES_USED_BY_CODE {
+            if !noises.iter().any(|noise| noise.name == name) {
+                report.problem(NOISE_PATH, name, "noise is used by world generation but not defined");
+            }
+        }
+        for preset in noise_presets.iter().flatten() {
+            for change in preset.noises.iter() {
+                if !noises.iter().any(|noise| noise.name == change.name) {
+                    report.problem(NOISE_PRESETS_FILE, &preset.name, format!("{} is not a noise", change.name));
+                }
+            }
+        }
+    }
+
+    if let Some(prefabs) = &prefabs {
+        for prefab in prefabs.iter() {
+            if !(0.0..=1.0).contains(&prefab.chance) {
+                report.problem(PREFABS_FILE, &prefab.name, format!("chance {} is not between 0 and 1", prefab.chance));
+            }
+            if prefab_levels.as_ref().is_some_and(|project| project.level(&prefab.level).is_none()) {
+                let reason = format!("level {} is not in {}", prefab.level, PREFABS_LDTK_FILE);
+                report.problem(PREFABS_FILE, &prefab.name, reason);
+            }
+        }
+    }
+
+    if let Some(biomes) = &biomes {
+        for biome in biomes.iter() {
+            if tileset_font(&biome.font).is_none() {
+                report.problem(BIOMES_FILE, &biome.name, format!("{} is not a font", biome.font));
+            }
+            if biome.tiles.is_empty() || biome.tiles.iter().any(|tile| tile.atlas_idx.is_none()) {
+                report.problem(BIOMES_FILE, &biome.name, "every biome needs tiles with an atlas_idx");
+            }
+            for range in [biome.temperature, biome.moisture, biome.height] {
+                if range[0] > range[1] || !(0.0..=1.0).contains(&range[0]) || !(0.0..=1.0).contains(&range[1]) {
+                    report.problem(BIOMES_FILE, &biome.name, format!("{:?} is not a range between 0 and 1", range));
+                }
+            }
+            for resource in biome.resources.iter() {
+                if world_obj_names.as_ref().is_some_and(|names| !names.contains(&resource.name)) {
+                    report.problem(BIOMES_FILE, &biome.name, format!("{} is not a world object", resource.name));
+                }
+                if resource.weight.is_none() {
+                    report.problem(BIOMES_FILE, &biome.name, format!("resource {} has no weight", resource.name));
+                }
+            }
+        }
+    }
+
+    if let Some(dungeons) = &dungeons {
+        for dungeon in dungeons.iter() {
+            if DungeonLayout::from_name(&dungeon.layout).is_none() {
+                report.problem(DUNGEONS_FILE, &dungeon.name, format!("{} is not a dungeon layout", dungeon.layout));
+            }
+            if tileset_font(&dungeon.font).is_none() {
+                report.problem(DUNGEONS_FILE, &dungeon.name, format!("{} is not a font", dungeon.font));
+            }
+            if dungeon.depths == 0 {
+                report.problem(DUNGEONS_FILE, &dungeon.name, "a dungeon needs at least one depth");
+            }
+            if dungeon.width < MIN_FLOOR_SIZE || dungeon.height < MIN_FLOOR_SIZE {
+                let reason = format!("floors must be at least {} tiles across", MIN_FLOOR_SIZE);
+                report.problem(DUNGEONS_FILE, &dungeon.name, reason);
+            }
+            if !(0.0..=1.0).contains(&dungeon.entrance_chance) {
+                let reason = format!("entrance_chance {} is not between 0 and 1", dungeon.entrance_chance);
+                report.problem(DUNGEONS_FILE, &dungeon.name, reason);
+            }
+            for obj in [&dungeon.entrance, &dungeon.stairs_down, &dungeon.stairs_up] {
+                if world_obj_names.as_ref().is_some_and(|names| !names.contains(obj)) {
+                    report.problem(DUNGEONS_FILE, &dungeon.name, format!("{} is not a world object", obj));
+                }
+            }
+            for creature in dungeon.creatures.table.iter() {
+                if being_names.as_ref().is_some_and(|names| !names.contains(&creature.name)) {
+                    report.problem(DUNGEONS_FILE, &dungeon.name, format!("{} is not a being", creature.name));
+                }
+            }
+            for loot in dungeon.loot.table.iter() {
+                if item_names.as_ref().is_some_and(|names| !names.contains(&loot.name)) {
+                    report.problem(DUNGEONS_FILE, &dungeon.name, format!("{} has no definition in items", loot.name));
+                }
+            }
+        }
+    }
+
+    if let Some(towns) = &towns {
+        for town in towns.iter() {
+            if tileset_font(&town.font).is_none() {
+                report.problem(TOWNS_FILE, &town.name, format!("{} is not a font", town.font));
+            }
+            if let Some(problem) = town.size_problem() {
+                report.problem(TOWNS_FILE, &town.name, problem);
+            }
+            for resident in town.residents.iter() {
+                if being_names.as_ref().is_some_and(|names| !names.contains(&resident.name)) {
+                    report.problem(TOWNS_FILE, &town.name, format!("{} is not a being", resident.name));
+                }
+            }
+            if let Some(campfire) = &town.campfire {
+                if world_obj_names.as_ref().is_some_and(|names| !names.contains(campfire)) {
+                    report.problem(TOWNS_FILE, &town.name, format!("{} is not a world object", campfire));
+                }
+            }
+        }
+    }
+
+    if let Some(spawns) = &spawns {
+        for spawn in spawns.iter() {
+            if being_names.as_ref().is_some_and(|names| !names.contains(&spawn.name)) {
+                report.problem(SPAWNS_FILE, &spawn.name, "spawn is not a being");
+            }
+            if let Some(problem) = spawn.number_problem() {
+                report.problem(SPAWNS_FILE, &spawn.name, problem);
+            }
+            for biome in spawn.biomes.iter() {
+                if biomes.as_ref().is_some_and(|biomes| !biomes.iter().any(|b| &b.name == biome)) {
+                    report.problem(SPAWNS_FILE, &spawn.name, format!("{} is not a biome", biome));
+                }
+            }
+        }
+    }
+
+    report.unread_files();
+    report.problems
+}
@@ -1,7 +1,9 @@
-use std::{fs, str::FromStr};
+use std::str::FromStr;
 
 use crate::{
-    components::{Blocking, Breakable, Grass, HealthStats as HealthStatsComponent, Name, Position, Renderable},
+    components::{
+        Blocking, Breakable, Grass, HealthStats as HealthStatsComponent, LightSource, Name, Position, Renderable,
+    },
     droptables::Drops,
     map::{ObjectID, WorldObject},
     saveload::SerializeMe,
@@ -13,25 +15,28 @@ use specs::{
     Builder, Entity, World, WorldExt,
 };
 
-use super::{beings::RawDrops, EntityBuildError, GameData, ENTITY_DB};
+use super::{beings::RawDrops, game_data, read_raws, DataError, GameData, IndexedTable};
+
+pub(crate) const WORLD_OBJS_FILE: &str = "raws/world_objs.json5";
 
 pub struct WorldObjectDatabase {
-    data: Vec<WorldObject>,
+    data: IndexedTable<usize, WorldObject>,
 }
 
 #[derive(Deserialize)]
 pub struct RawWorldObject {
     /// Unique id to find the world object's static data
-    identifier: usize,
-    name: String,
+    pub(crate) identifier: usize,
+    pub(crate) name: String,
     atlas_index: u8,
     is_blocking: bool,
-    breakable: Option<String>,
+    pub(crate) breakable: Option<String>,
     health_stats: Option<HealthStats>,
     grass: Option<String>,
     foreground: Option<(u8, u8, u8)>,
-    loot: Option<RawDrops>,
-    impact_sound: Option<String>,
+    pub(crate) loot: Option<RawDrops>,
+    pub(crate) impact_sound: Option<String>,
+    light_radius: Option<usize>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -42,16 +47,14 @@ struct HealthStats {
 
 impl WorldObjectDatabase {
     pub(crate) fn empty() -> Self {
-        Self { data: Vec::new() }
+        Self { data: IndexedTable::empty() }
     }
 
-    pub(crate) fn load(game_data: &GameData) -> Self {
-        let contents: String = fs::read_to_string("raws/world_objs.json5")
-            .expect("Unable to find world_objs.json at `raws/world_objs.json5`");
-        let world_objs: Vec<RawWorldObject> = json5::from_str(&contents).expect("Bad JSON in world_objs.json5 fix it");
-        let data = world_objs
-            .iter()
-            .map(|raw| WorldObject {
+    pub(crate) fn load(game_data: &GameData) -> Result<Self, DataError> {
+        let world_objs: Vec<RawWorldObject> = read_raws(WORLD_OBJS_FILE)?;
+        let mut data = vec![];
+        for raw in world_objs.iter() {
+            data.push(WorldObject {
                 id: ObjectID(raw.identifier),
                 name: raw.name.clone(),
                 atlas_index: raw.atlas_index,
@@ -60,32 +63,35 @@ impl WorldObjectDatabase {
                 health_stats: raw.health_stats.clone().map(|hs| HealthStatsComponent::new(hs.max_hp, hs.defense)),
                 grass: raw.grass.clone(),
                 foreground: raw.foreground,
-                loot: raw.loot.as_ref().map(|raw| Drops::from_raw(raw, game_data)),
+                loot: raw
+                    .loot
+                    .as_ref()
+                    .map(|loot| Drops::from_raw(loot, game_data))
+                    .transpose()
+                    .map_err(|reason| DataError::bad_entry(WORLD_OBJS_FILE, &raw.name, reason))?,
                 impact_sound: raw.impact_sound.clone().unwrap_or("".to_string()),
-            })
-            .collect();
-        WorldObjectDatabase { data }
+                light_radius: raw.light_radius,
+            });
+        }
+        Ok(WorldObjectDatabase { data: IndexedTable::new(data, |obj| (obj.id.0, &obj.name)) })
     }
 
-    pub fn get_by_name(&self, name: &String) -> Option<&WorldObject> {
-        self.data.iter().find(|i| i.name.eq(name))
+    pub fn get_by_name(&self, name: &str) -> Option<&WorldObject> {
+        self.data.get_by_name(name)
     }
 
     #[allow(unused)]
     pub fn get_by_id(&self, id: usize) -> Option<&WorldObject> {
-        self.data.iter().find(|i| i.id.0 == id)
+        self.data.get_by_id(&id)
     }
 }
 
 /// Attempts to create the specified entity directly into the world
-pub fn build_world_obj(name: impl ToString, pos: Position, world: &mut World) -> Result<Entity, EntityBuildError> {
-    let edb = &ENTITY_DB.lock().unwrap();
+pub fn build_world_obj(name: impl ToString, pos: Position, world: &mut World) -> Result<Entity, DataError> {
+    let edb = game_data();
     let raw = match edb.world_objs.get_by_name(&name.to_string()) {
         Some(raw) => raw,
-        None => {
-            eprintln!("No world object found named: {}", name.to_string());
-            return Err(EntityBuildError);
-        }
+        None => return Err(DataError::NotFound { file: WORLD_OBJS_FILE, entry: name.to_string() }),
     };
     let mut builder = world.create_entity().with(Name::new(&raw.name)).with(pos).marked::<SimpleMarker<SerializeMe>>();
 
@@ -103,8 +109,8 @@ pub fn build_world_obj(name: impl ToString, pos: Position, world: &mut World) ->
                 builder = builder.with(breakable_type);
             }
             Err(_) => {
-                eprintln!("Invalid breakable string {} on world object {}", breakable, &raw.name);
-                return Err(EntityBuildError);
+                let reason = format!("{} is not a breakable tool", breakable);
+                return Err(DataError::bad_entry(WORLD_OBJS_FILE, &raw.name, reason));
             }
         }
     }
@@ -117,5 +123,9 @@ pub fn build_world_obj(name: impl ToString, pos: Position, world: &mut World) ->
         builder = builder.with(HealthStatsComponent::new(health_stats.max_hp, health_stats.defense));
     }
 
+    if let Some(radius) = raw.light_radius {
+        builder = builder.with(LightSource { radius });
+    }
+
     Ok(builder.build())
 }
@@ -0,0 +1,129 @@
+//! The world clock. Every turn moves the sun along, above ground it changes how far beings can see and how the
+//! terrain is tinted. Light sources like torches and campfires light up the tiles around them so they can still be
+//! seen in the dark.
+
+use std::collections::HashSet;
+
+use bracket_lib::{pathfinding::field_of_view_set, prelude::Point};
+use specs::{Join, Read, ReadExpect, ReadStorage, System, Write};
+
+use crate::{
+    colors::{Color, DAWNPINK, DUSKORANGE, MIDNIGHTBLUE, SUNLIGHT},
+    components::{Equipped, LightSource, Position},
+    map::MapRes,
+    TurnCounter,
+};
+
+/// Turns it takes for a whole day to go by
+pub const TURNS_PER_DAY: usize = 400;
+/// How far into the day the first turn is, so a new game starts in the morning
+const FIRST_TURN_TIME: usize = 60;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DayPhase {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl DayPhase {
+    pub fn at(turn: usize) -> Self {
+        match (turn + FIRST_TURN_TIME) % TURNS_PER_DAY {
+            0..=39 => DayPhase::Dawn,
+            40..=239 => DayPhase::Day,
+            240..=279 => DayPhase::Dusk,
+            _ => DayPhase::Night,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DayPhase::Dawn => "Dawn",
+            DayPhase::Day => "Day",
+            DayPhase::Dusk => "Dusk",
+            DayPhase::Night => "Night",
+        }
+    }
+
+    /// Color the terrain is drawn with outside of any light
+    pub fn tint(&self) -> Color {
+        match self {
+            DayPhase::Dawn => DAWNPINK,
+            DayPhase::Day => SUNLIGHT,
+            DayPhase::Dusk => DUSKORANGE,
+            DayPhase::Night => MIDNIGHTBLUE,
+        }
+    }
+
+    /// Part of a viewshed's range that can be seen without a light
+    pub fn vision_factor(&self) -> f32 {
+        match self {
+            DayPhase::Dawn | DayPhase::Dusk => 0.75,
+            DayPhase::Day => 1.0,
+            DayPhase::Night => 0.4,
+        }
+    }
+}
+
+/// The light over the current map, updated every frame by `UpdateLighting`
+pub struct WorldLight {
+    /// Always `Day` below ground, dungeons are lit the same at every hour
+    pub phase: DayPhase,
+    /// Tiles lit by a light source, only filled in while it isn't day
+    pub lit: HashSet<Point>,
+}
+
+impl Default for WorldLight {
+    fn default() -> Self {
+        Self { phase: DayPhase::Day, lit: HashSet::new() }
+    }
+}
+
+impl WorldLight {
+    /// Range left of a viewshed of `range` tiles outside of any light
+    pub fn vision_range(&self, range: usize) -> usize {
+        ((range as f32 * self.phase.vision_factor()).round() as usize).max(1)
+    }
+
+    /// Color of the terrain at `point`
+    pub fn tint_at(&self, point: Point) -> Color {
+        if self.lit.contains(&point) {
+            SUNLIGHT
+        } else {
+            self.phase.tint()
+        }
+    }
+}
+
+/// Sets the phase of the day and lights up the tiles around every light source, a light that is equipped shines
+/// from whoever is holding it
+pub struct UpdateLighting;
+
+impl<'a> System<'a> for UpdateLighting {
+    type SystemData = (
+        Write<'a, WorldLight>,
+        ReadExpect<'a, TurnCounter>,
+        Read<'a, MapRes>,
+        ReadStorage<'a, LightSource>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Equipped>,
+    );
+
+    fn run(&mut self, (mut light, turns, map, light_sources, positions, equippeds): Self::SystemData) {
+        light.phase = if map.0.dungeon.is_some() { DayPhase::Day } else { DayPhase::at(turns.0) };
+        light.lit.clear();
+        if light.phase == DayPhase::Day {
+            return;
+        }
+
+        let placed = (&light_sources, &positions).join().map(|(source, pos)| (source, *pos));
+        let held = (&light_sources, &equippeds)
+            .join()
+            .filter_map(|(source, equipped)| positions.get(equipped.on).map(|pos| (source, *pos)));
+        let shining: Vec<(usize, Position)> = placed.chain(held).map(|(source, pos)| (source.radius, pos)).collect();
+        for (radius, pos) in shining {
+            light.lit.extend(field_of_view_set(pos.to_point(), radius as i32, &map.0));
+        }
+    }
+}
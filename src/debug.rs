@@ -1,4 +1,4 @@
-use bracket_lib::terminal::{to_char, BTerm, TextAlign, VirtualKeyCode, RGB, RGBA, WHITESMOKE};
+use bracket_lib::terminal::{to_char, BTerm, TextAlign, RGB, RGBA, WHITESMOKE};
 use itertools::Itertools;
 use specs::{Join, ReadStorage, World, WorldExt};
 
@@ -8,7 +8,9 @@ use crate::{
     components::{HealthStats, InBag, Interactor, Item, Name, Position, SelectedInventoryItem, Transform},
     config::{InventoryConfig, SortMode},
     game_init::PlayerEntity,
+    hot_reload::reload_raws,
     inventory::UseMenuResult,
+    keybindings::{Action, BindingContext, Keybindings},
     map::MapRes,
     CL_INTERACTABLES, CL_TEXT, CL_WORLD,
 };
@@ -113,7 +115,7 @@ fn draw_inventory_state(ctx: &mut BTerm, ecs: &World, cfg: &InventoryConfig) {
     ctx.print_color(41, 49, WHITESMOKE, RGB::from_u8(61, 84, 107), sort_mode);
 }
 
-pub fn debug_input(ctx: &mut BTerm, ecs: &World) {
+pub fn debug_input(ctx: &mut BTerm, ecs: &mut World, binds: &Keybindings) {
     if !ctx.control {
         return;
     }
@@ -124,8 +126,10 @@ pub fn debug_input(ctx: &mut BTerm, ecs: &World) {
         print_tile_contents(ctx, ecs);
     }
 
-    if ctx.key.is_some() && ctx.key == Some(VirtualKeyCode::V) {
-        print_position(ecs);
+    match binds.pressed(BindingContext::Debug, ctx) {
+        Some(Action::DebugPrintPosition) => print_position(ecs),
+        Some(Action::DebugReloadRaws) => reload_raws(ecs),
+        _ => {}
     }
 }
 
@@ -1,14 +1,18 @@
+use std::collections::HashSet;
+
 use bracket_lib::terminal::*;
-use specs::{Join, World, WorldExt};
+use specs::{Entity, Join, World, WorldExt};
 
 use crate::{
+    being::BeingID,
     camera::get_camera_bounds,
-    components::{GlyphFlash, Renderable, SizeFlexor, Transform},
+    components::{GlyphFlash, Renderable, SizeFlexor, Transform, Viewshed},
     debug::CLEAR,
+    game_init::PlayerEntity,
     map::render_map,
     time::DeltaTime,
     z_order::PLAYER_Z,
-    Position, CL_EFFECTS, CL_INTERACTABLES, CL_WORLD,
+    Position, CL_EFFECTS, CL_INTERACTABLES, CL_WORLD, CL_WORLD_OVERLAY,
 };
 
 pub const SPRITE_SPEED: f32 = 8.0;
@@ -25,7 +29,7 @@ pub fn update_fancy_positions(ecs: &World) {
     }
 }
 
-/// Draws the CL_INTERACTABLES and CL_WORLD sprites to the screen
+/// Draws the CL_INTERACTABLES, CL_WORLD and CL_WORLD_OVERLAY sprites to the screen
 pub fn draw_sprite_layers(ecs: &World) {
     let mut draw_batch = DrawBatch::new();
 
@@ -36,23 +40,51 @@ pub fn draw_sprite_layers(ecs: &World) {
     draw_fancy_sprites(ecs, &mut draw_batch);
     draw_batch.submit(CL_INTERACTABLES).expect("Batch error??");
 
+    let mut overlay_batch = DrawBatch::new();
+    overlay_batch.target(CL_WORLD_OVERLAY);
+    overlay_batch.cls();
+
     draw_batch.target(CL_WORLD);
     draw_batch.cls();
-    render_map(ecs, &mut draw_batch);
+    render_map(ecs, &mut draw_batch, &mut overlay_batch);
     draw_batch.submit(CL_WORLD).expect("Batch error??");
+    overlay_batch.submit(CL_WORLD_OVERLAY).expect("Batch error??");
+}
+
+/// Beings standing outside of what the player can see, they aren't remembered along with the terrain
+fn hidden_beings(ecs: &World) -> HashSet<Entity> {
+    let player = match ecs.try_fetch::<PlayerEntity>() {
+        Some(player) => player.0,
+        None => return HashSet::new(),
+    };
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let player_view = match viewsheds.get(player) {
+        Some(view) => view,
+        None => return HashSet::new(),
+    };
+    let positions = ecs.read_storage::<Position>();
+    let beings = ecs.read_storage::<BeingID>();
+    (&ecs.entities(), &positions, &beings)
+        .join()
+        .filter(|(_, pos, _)| !player_view.tiles.contains(&pos.to_point()))
+        .map(|(entity, ..)| entity)
+        .collect()
 }
 
 fn draw_sprites(ecs: &World, draw_batch: &mut DrawBatch) {
+    let entities = ecs.entities();
     let positions = ecs.read_storage::<Position>();
     let renderables = ecs.read_storage::<Renderable>();
     let transforms = ecs.read_storage::<Transform>();
     let flashes = ecs.read_storage::<GlyphFlash>();
 
     let bounding_box = get_camera_bounds(ecs);
+    let hidden = hidden_beings(ecs);
 
-    let data = (&positions, &renderables, !&transforms, !&flashes)
+    let data = (&entities, &positions, &renderables, !&transforms, !&flashes)
         .join()
-        .map(|(p, r, ..)| (p, r))
+        .filter(|(entity, ..)| !hidden.contains(entity))
+        .map(|(_, p, r, ..)| (p, r))
         .filter(|(pos, _)| bounding_box.point_in_rect(pos.to_point()));
     for (pos, render) in data {
         draw_batch.set_with_z(
@@ -82,8 +114,11 @@ fn draw_fancy_sprites(ecs: &World, draw_batch: &mut DrawBatch) {
     let transforms = ecs.read_storage::<Transform>();
 
     let bounding_box = get_camera_bounds(ecs);
-    for (ftrans, render) in
-        (&transforms, &renderables).join().filter(|(pos, _)| bounding_box.point_in_rect(pos.sprite_pos.into()))
+    let hidden = hidden_beings(ecs);
+    for (_, ftrans, render) in (&ecs.entities(), &transforms, &renderables)
+        .join()
+        .filter(|(entity, ..)| !hidden.contains(entity))
+        .filter(|(_, pos, _)| bounding_box.point_in_rect(pos.sprite_pos.into()))
     {
         let fx = ftrans.sprite_pos.x - bounding_box.x1 as f32;
         let fy = ftrans.sprite_pos.y - bounding_box.y1 as f32 + 1.0;
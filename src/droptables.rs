@@ -1,12 +1,12 @@
 use bracket_lib::random::RandomNumberGenerator;
 use log::{debug, error};
-use specs::{Join, ReadStorage, System, Write};
-use weighted_rand::builder::{NewBuilder, WalkerTableBuilder};
+use specs::{Join, ReadExpect, ReadStorage, System, Write, WriteExpect};
 
 use crate::{
     components::{HealthStats, Name, Position},
-    data_read::ENTITY_DB,
+    data_read::GameDataRes,
     items::{ItemID, ItemQty, ItemSpawner, SpawnType},
+    rng::GameRng,
 };
 
 pub struct Drops {
@@ -27,16 +27,17 @@ pub enum DropQty {
 
 const MAX_ITEM_DROPS: u32 = 10;
 
-fn generate_drops(drop_table: &Drops) -> Vec<(ItemID, ItemQty)> {
+fn generate_drops(drop_table: &Drops, rng: &mut RandomNumberGenerator) -> Vec<(ItemID, ItemQty)> {
     let mut drops: Vec<(ItemID, ItemQty)> = vec![];
-    let mut rng = RandomNumberGenerator::new();
     let mut total_drops: u32 = 0;
-    let weights: Vec<u32> = drop_table.loot_table.iter().map(|loot| loot.weight).collect();
-    let w = WalkerTableBuilder::new(&weights).build();
+    let total_weight: u32 = drop_table.loot_table.iter().map(|loot| loot.weight).sum();
+    if total_weight == 0 {
+        return drops;
+    }
 
     let mut roll = rng.range(0, 100);
     while roll < drop_table.drop_chance / 2u32.pow(total_drops) && total_drops < MAX_ITEM_DROPS {
-        let idx = w.next();
+        let idx = pick_weighted(&drop_table.loot_table, rng.range(0, total_weight));
         let drop = match drop_table.loot_table.get(idx) {
             Some(drop) => drop,
             None => {
@@ -55,14 +56,31 @@ fn generate_drops(drop_table: &Drops) -> Vec<(ItemID, ItemQty)> {
     drops
 }
 
+/// Finds the idx of the loot that `weight_roll` lands on, `weight_roll` must be less than the total weight
+fn pick_weighted(loot_table: &[Loot], mut weight_roll: u32) -> usize {
+    for (idx, loot) in loot_table.iter().enumerate() {
+        if weight_roll < loot.weight {
+            return idx;
+        }
+        weight_roll -= loot.weight;
+    }
+    loot_table.len()
+}
+
 pub struct DeathLootDrop;
 
 impl<'a> System<'a> for DeathLootDrop {
-    type SystemData =
-        (ReadStorage<'a, HealthStats>, ReadStorage<'a, Position>, ReadStorage<'a, Name>, Write<'a, ItemSpawner>);
+    type SystemData = (
+        ReadStorage<'a, HealthStats>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        Write<'a, ItemSpawner>,
+        WriteExpect<'a, GameRng>,
+        ReadExpect<'a, GameDataRes>,
+    );
 
-    fn run(&mut self, (healths, positions, names, mut item_spawner): Self::SystemData) {
-        let edb = &ENTITY_DB.lock().unwrap();
+    fn run(&mut self, (healths, positions, names, mut item_spawner, mut rng, game_data): Self::SystemData) {
+        let edb = &game_data.0;
         for (pos, _, name) in (&positions, &healths, &names).join().filter(|(_, health, _)| health.hp == 0) {
             debug!("{} in deathloopdrop", name);
             let drop_table = match edb.beings.get_by_name(&name.0) {
@@ -88,7 +106,7 @@ impl<'a> System<'a> for DeathLootDrop {
                 },
             };
             debug!("{} generating drops", name);
-            let drops = generate_drops(drop_table);
+            let drops = generate_drops(drop_table, &mut rng);
             debug!("{:?}", drops);
             for drop in drops {
                 item_spawner.request_amt(drop.0, SpawnType::OnGround(*pos), drop.1);
@@ -0,0 +1,52 @@
+//! Caves grown with a cellular automaton. The floor starts out as noise and is smoothed until the walls clump
+//! together, pockets that can't be reached from the middle of the floor are filled back in.
+
+use bracket_lib::random::RandomNumberGenerator;
+
+use super::FloorPlan;
+
+/// Percent of the tiles that start out as wall
+const START_WALL_CHANCE: i32 = 45;
+const SMOOTHING_STEPS: usize = 5;
+/// A tile with more walls than this around it becomes a wall
+const CROWDED: usize = 4;
+
+/// Returns the plan along with the open tile closest to the middle of the floor
+pub(crate) fn grow_caves(width: usize, height: usize, rng: &mut RandomNumberGenerator) -> (FloorPlan, (usize, usize)) {
+    let mut plan = FloorPlan::new(width, height);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            if rng.range(0, 100) >= START_WALL_CHANCE {
+                plan.set_open(x, y);
+            }
+        }
+    }
+
+    for _ in 0..SMOOTHING_STEPS {
+        let mut next = plan.clone();
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let walls = plan.walls_around(x, y);
+                // walls are also put in the middle of wide open spaces so they don't turn into one big room
+                if walls > CROWDED || walls == 0 {
+                    next.set_closed(x, y);
+                } else {
+                    next.set_open(x, y);
+                }
+            }
+        }
+        plan = next;
+    }
+
+    let middle = (width / 2, height / 2);
+    let start = plan.open_tiles().min_by_key(|&(x, y)| x.abs_diff(middle.0).pow(2) + y.abs_diff(middle.1).pow(2));
+    let start = match start {
+        Some(start) => start,
+        None => {
+            plan.set_open(middle.0, middle.1);
+            middle
+        }
+    };
+    plan.keep_reachable(start);
+    (plan, start)
+}
@@ -0,0 +1,419 @@
+//! Dungeons under generated worlds. A floor is generated from the world seed, its entrance and its depth so a
+//! dungeon always has the same floors, and a floor the player leaves is put away with its entities the same way as
+//! a chunk so it is found again as it was left. Taking the stairs swaps out the whole map like changing levels.
+
+mod caves;
+mod rooms;
+
+use std::{
+    collections::{HashSet, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use bracket_lib::{random::RandomNumberGenerator, terminal::Rect};
+use caves::grow_caves;
+use log::{debug, warn};
+use rooms::rooms_and_corridors;
+use serde::{Deserialize, Serialize};
+use specs::{
+    saveload::{MarkedBuilder, SimpleMarker},
+    Builder, Entity, Join, World, WorldExt,
+};
+
+use crate::{
+    chunks::{load_area, load_world_around, unload_area, unload_world},
+    components::{Blocking, Position, Stairs},
+    data_read::{
+        dungeons::{DungeonLayout, DUNGEONS_FILE},
+        prelude::{build_being, build_world_obj, DUNGEON_DB},
+        DataError,
+    },
+    game_init::PlayerEntity,
+    indexing::SpatialIndex,
+    items::{ItemSpawner, SpawnType},
+    map::{Map, MapRes, WorldCoords},
+    map_gen::WorldConfig,
+    saveload::SerializeMe,
+    AppState,
+};
+
+/// Nothing is spawned this many steps or closer to the stairs the player arrives by
+const SAFE_STEPS: usize = 5;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DungeonFloor {
+    /// The kind of dungeon in the raws
+    pub dungeon: String,
+    pub depth: u32,
+    /// World tile of the way in, tells apart dungeons of the same kind
+    pub entrance: WorldCoords,
+    /// The world the dungeon is under
+    pub world: WorldConfig,
+}
+
+impl DungeonFloor {
+    fn file_name(&self) -> String {
+        format!("{}_{}_{}_{}.json", self.dungeon, self.entrance.x, self.entrance.y, self.depth)
+    }
+}
+
+/// Which tiles of a floor are open before it is turned into a map
+#[derive(Clone)]
+pub(crate) struct FloorPlan {
+    width: usize,
+    height: usize,
+    open: Vec<bool>,
+}
+
+impl FloorPlan {
+    fn new(width: usize, height: usize) -> Self {
+        Self { width, height, open: vec![false; width * height] }
+    }
+
+    fn is_open(&self, x: usize, y: usize) -> bool {
+        self.open[x + y * self.width]
+    }
+
+    fn set_open(&mut self, x: usize, y: usize) {
+        self.open[x + y * self.width] = true;
+    }
+
+    fn set_closed(&mut self, x: usize, y: usize) {
+        self.open[x + y * self.width] = false;
+    }
+
+    fn open_tiles(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.open.len()).filter(|idx| self.open[*idx]).map(|idx| (idx % self.width, idx / self.width))
+    }
+
+    /// The walls in the 8 tiles around a tile, the edge of the floor counts as wall
+    fn walls_around(&self, x: usize, y: usize) -> usize {
+        let mut walls = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let off_floor = nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32;
+                if off_floor || !self.is_open(nx as usize, ny as usize) {
+                    walls += 1;
+                }
+            }
+        }
+        walls
+    }
+
+    /// Steps needed to walk from `start` to every tile, `None` for walls and tiles that can't be reached
+    fn steps_from(&self, start: (usize, usize)) -> Vec<Option<usize>> {
+        let mut steps = vec![None; self.open.len()];
+        let mut frontier = VecDeque::from([start]);
+        steps[start.0 + start.1 * self.width] = Some(0);
+
+        while let Some((x, y)) = frontier.pop_front() {
+            let next_step = steps[x + y * self.width].map(|step| step + 1);
+            let neighbours = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+            for (nx, ny) in neighbours {
+                if nx >= self.width || ny >= self.height || !self.is_open(nx, ny) {
+                    continue;
+                }
+                let idx = nx + ny * self.width;
+                if steps[idx].is_none() {
+                    steps[idx] = next_step;
+                    frontier.push_back((nx, ny));
+                }
+            }
+        }
+        steps
+    }
+
+    /// Closes every open tile that can't be walked to from `start`
+    fn keep_reachable(&mut self, start: (usize, usize)) {
+        let steps = self.steps_from(start);
+        for (open, step) in self.open.iter_mut().zip(steps) {
+            *open &= step.is_some();
+        }
+    }
+}
+
+/// The dungeon change for the player standing on stairs, or on an entrance in a generated world
+pub fn stairs_transition(ecs: &World) -> Option<AppState> {
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    let positions = ecs.read_storage::<Position>();
+    let stairs = ecs.read_storage::<Stairs>();
+    let map_res = ecs.read_resource::<MapRes>();
+    let map = &map_res.0;
+    let player_pos = positions.get(player_entity.0)?;
+
+    let (stairs, _) = (&stairs, &positions).join().find(|(_, pos)| *pos == player_pos)?;
+    let entrance = match &map.dungeon {
+        Some(floor) => Position::new(floor.entrance.x, floor.entrance.y),
+        None => Position::new(map.chunk_coords.x + player_pos.x, map.chunk_coords.y + player_pos.y),
+    };
+    Some(AppState::DepthChange { dungeon: stairs.dungeon.clone(), entrance, depth: stairs.depth })
+}
+
+/// Replaces the map with `depth` of the dungeon whose way in is at `entrance`, a world tile, and puts the player
+/// beside the stairs that lead back. Depth 0 is the world above the dungeon.
+pub fn change_depth(ecs: &mut World, dungeon: &str, entrance: &Position, depth: u32) -> Result<(), DataError> {
+    let old_map = ecs.read_resource::<MapRes>().0.clone();
+    let wc = match old_map.world_config() {
+        Some(wc) => wc.clone(),
+        None => {
+            return Err(DataError::bad_entry(DUNGEONS_FILE, dungeon, "dungeons are only found in generated worlds"))
+        }
+    };
+    let from_depth = old_map.dungeon.as_ref().map_or(0, |floor| floor.depth);
+
+    // looked up before the old map is put away so a missing dungeon leaves the player where they are
+    let floor_map = if depth > 0 {
+        let floor = DungeonFloor {
+            dungeon: dungeon.to_string(),
+            depth,
+            entrance: (entrance.x, entrance.y).into(),
+            world: wc.clone(),
+        };
+        Some(empty_floor_map(floor)?)
+    } else {
+        None
+    };
+
+    match &old_map.dungeon {
+        Some(floor) => unload_area(ecs, &wc.world_name, &floor.file_name(), &old_map, whole_map(&old_map)),
+        None => unload_world(ecs, &old_map),
+    }
+
+    let new_map = match floor_map {
+        Some(mut map) => {
+            let file_name = map.dungeon.as_ref().map(|floor| floor.file_name()).unwrap_or_default();
+            let area = whole_map(&map);
+            if !load_area(ecs, &wc.world_name, &file_name, &mut map, area) {
+                gen_floor(ecs, &mut map);
+            }
+            map
+        }
+        None => load_world_around(ecs, &wc, entrance),
+    };
+
+    let entrance_pos = Position::new(
+        entrance.x.saturating_sub(new_map.chunk_coords.x).min(new_map.width - 1),
+        entrance.y.saturating_sub(new_map.chunk_coords.y).min(new_map.height - 1),
+    );
+    let arrival = {
+        let stairs = ecs.read_storage::<Stairs>();
+        let positions = ecs.read_storage::<Position>();
+        (&stairs, &positions)
+            .join()
+            .find(|(stairs, pos)| {
+                stairs.dungeon == dungeon && stairs.depth == from_depth && (depth > 0 || **pos == entrance_pos)
+            })
+            .map_or(entrance_pos, |(_, pos)| *pos)
+    };
+    let player_pos = beside(ecs, &new_map, arrival);
+    debug!("{} depth {} entered at {:?}", dungeon, depth, (player_pos.x, player_pos.y));
+
+    ecs.insert(MapRes(new_map));
+    ecs.write_resource::<SpatialIndex>().request_rebuild();
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let _ = ecs.write_storage::<Position>().insert(player_entity, player_pos);
+    Ok(())
+}
+
+fn whole_map(map: &Map) -> Rect {
+    Rect::with_size(0, 0, map.width as i32, map.height as i32)
+}
+
+/// A map the size of the dungeon's floors with nothing on it yet
+fn empty_floor_map(floor: DungeonFloor) -> Result<Map, DataError> {
+    let dungeon_db = DUNGEON_DB.lock().unwrap();
+    let dungeon = match dungeon_db.get_by_name(&floor.dungeon) {
+        Some(dungeon) => dungeon,
+        None => return Err(DataError::NotFound { file: DUNGEONS_FILE, entry: floor.dungeon }),
+    };
+    let mut map = Map::new(dungeon.width, dungeon.height, (0, 0));
+    map.tile_atlas_index = dungeon.font;
+    map.dungeon = Some(floor);
+    Ok(map)
+}
+
+/// An open tile next to `pos` for the player to stand on so they don't take the stairs again right away.
+/// `pos` itself when there is none.
+fn beside(ecs: &World, map: &Map, pos: Position) -> Position {
+    let blocked: HashSet<Position> = {
+        let blockings = ecs.read_storage::<Blocking>();
+        let positions = ecs.read_storage::<Position>();
+        (&blockings, &positions).join().map(|(_, pos)| *pos).collect()
+    };
+    let neighbours =
+        [(pos.x + 1, pos.y), (pos.x.wrapping_sub(1), pos.y), (pos.x, pos.y + 1), (pos.x, pos.y.wrapping_sub(1))];
+    neighbours
+        .into_iter()
+        .filter(|&(x, y)| x < map.width && y < map.height)
+        .map(|(x, y)| Position::new(x, y))
+        .find(|next| {
+            let tile = &map.tiles[map.xy_to_idx(next.x, next.y)];
+            !tile.is_blocked && !tile.is_water() && !blocked.contains(next)
+        })
+        .unwrap_or(pos)
+}
+
+/// Puts a stairs world object at `pos` that leads to `depth` of `dungeon`
+pub(crate) fn build_stairs(
+    ecs: &mut World,
+    obj_name: &str,
+    dungeon: &str,
+    depth: u32,
+    pos: Position,
+) -> Result<Entity, DataError> {
+    let entity = build_world_obj(obj_name, pos, ecs)?;
+    let _ = ecs.write_storage::<Stairs>().insert(entity, Stairs { dungeon: dungeon.to_string(), depth });
+    Ok(entity)
+}
+
+/// Lays out the floor `map` is for and spawns its stairs, beings and loot.
+/// The rng is seeded from the floor so the layout is the same every time it is generated.
+fn gen_floor(ecs: &mut World, map: &mut Map) {
+    let floor = match &map.dungeon {
+        Some(floor) => floor.clone(),
+        None => return,
+    };
+    let dungeon_db = DUNGEON_DB.lock().unwrap();
+    let dungeon = match dungeon_db.get_by_name(&floor.dungeon) {
+        Some(dungeon) => dungeon,
+        None => return,
+    };
+    let mut hasher = DefaultHasher::new();
+    (floor.world.seed, &floor.dungeon, floor.entrance.x, floor.entrance.y, floor.depth).hash(&mut hasher);
+    let mut rng = RandomNumberGenerator::seeded(hasher.finish());
+
+    let (plan, up) = match dungeon.layout {
+        DungeonLayout::Rooms => rooms_and_corridors(map.width, map.height, &mut rng),
+        DungeonLayout::Caves => grow_caves(map.width, map.height, &mut rng),
+    };
+    for y in 0..map.height {
+        for x in 0..map.width {
+            if plan.is_open(x, y) {
+                map.set_tile(&dungeon.floor, x, y);
+                continue;
+            }
+            map.set_tile(&dungeon.wall, x, y);
+            // walls that can't be walked up to never need to block anything
+            if plan.walls_around(x, y) < 8 {
+                ecs.create_entity()
+                    .with(Position::new(x, y))
+                    .with(Blocking {})
+                    .marked::<SimpleMarker<SerializeMe>>()
+                    .build();
+            }
+        }
+    }
+
+    let steps = plan.steps_from(up);
+    let mut spots: Vec<(usize, usize)> = plan.open_tiles().collect();
+    spots.retain(|&(x, y)| steps[x + y * map.width].is_some_and(|step| step > SAFE_STEPS));
+
+    if let Err(e) = build_stairs(ecs, &dungeon.stairs_up, &dungeon.name, floor.depth - 1, up.into()) {
+        warn!("{} has no way up from depth {}: {}", dungeon.name, floor.depth, e);
+    }
+    if floor.depth < dungeon.depths {
+        // the way down is as far from the way up as the floor allows
+        let down = (0..steps.len()).max_by_key(|idx| steps[*idx]).map(|idx| (idx % map.width, idx / map.width));
+        if let Some(down) = down.filter(|down| *down != up) {
+            spots.retain(|spot| *spot != down);
+            if let Err(e) = build_stairs(ecs, &dungeon.stairs_down, &dungeon.name, floor.depth + 1, down.into()) {
+                warn!("{} has no way down from depth {}: {}", dungeon.name, floor.depth, e);
+            }
+        }
+    }
+
+    for _ in 0..dungeon.creatures.count_at(floor.depth) {
+        let (name, spot) = match (dungeon.creatures.roll(floor.depth, &mut rng), take_spot(&mut spots, &mut rng)) {
+            (Some(name), Some(spot)) => (name, spot),
+            _ => break,
+        };
+        if let Err(e) = build_being(name, spot.into(), ecs) {
+            warn!("{} could not spawn in {}: {}", name, dungeon.name, e);
+        }
+    }
+    let mut item_spawner = ecs.write_resource::<ItemSpawner>();
+    for _ in 0..dungeon.loot.count_at(floor.depth) {
+        let (name, spot) = match (dungeon.loot.roll(floor.depth, &mut rng), take_spot(&mut spots, &mut rng)) {
+            (Some(name), Some(spot)) => (name, spot),
+            _ => break,
+        };
+        if let Err(e) = item_spawner.request_named(name, SpawnType::OnGround(spot.into())) {
+            warn!("{} could not be placed in {}: {}", name, dungeon.name, e);
+        }
+    }
+    debug!("{} depth {} generated", dungeon.name, floor.depth);
+}
+
+/// Removes a random spot so nothing else is put there
+fn take_spot(spots: &mut Vec<(usize, usize)>, rng: &mut RandomNumberGenerator) -> Option<(usize, usize)> {
+    if spots.is_empty() {
+        return None;
+    }
+    let idx = rng.range(0, spots.len());
+    Some(spots.swap_remove(idx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::generate_in;
+
+    /// Generates `depth` of the dungeon under an entrance in the middle of a default world
+    fn generate_floor(dungeon: &str, depth: u32) -> (World, Map) {
+        let floor = DungeonFloor {
+            dungeon: dungeon.to_string(),
+            depth,
+            entrance: (64, 64).into(),
+            world: WorldConfig { world_name: "dungeon_gen_test".to_string(), seed: 5, ..Default::default() },
+        };
+        let (ecs, map) = generate_in(|ecs| {
+            let mut map = empty_floor_map(floor).unwrap();
+            gen_floor(ecs, &mut map);
+            map
+        })
+        .unwrap();
+        (ecs, map)
+    }
+
+    #[test]
+    fn floors_are_placed_inside_their_bounds() {
+        let (_, dungeons) = generate_in(|_| {
+            let dungeon_db = DUNGEON_DB.lock().unwrap();
+            dungeon_db.dungeons.iter().map(|dungeon| (dungeon.name.clone(), dungeon.depths)).collect::<Vec<_>>()
+        })
+        .unwrap();
+        assert!(!dungeons.is_empty());
+
+        for (name, depths) in dungeons {
+            for depth in 1..=depths {
+                let (ecs, map) = generate_floor(&name, depth);
+                let positions = ecs.read_storage::<Position>();
+                let stairs = ecs.read_storage::<Stairs>();
+                for pos in positions.join() {
+                    assert!(
+                        pos.x < map.width && pos.y < map.height,
+                        "{} depth {} placed {:?} outside",
+                        name,
+                        depth,
+                        pos
+                    );
+                }
+
+                let mut leads_to: Vec<u32> = (&stairs, &positions)
+                    .join()
+                    .map(|(stairs, pos)| {
+                        assert!(!map.tiles[map.xy_to_idx(pos.x, pos.y)].is_blocked, "stairs on a wall in {}", name);
+                        stairs.depth
+                    })
+                    .collect();
+                leads_to.sort();
+                let expected = if depth < depths { vec![depth - 1, depth + 1] } else { vec![depth - 1] };
+                assert_eq!(leads_to, expected, "{} depth {} has the wrong stairs", name, depth);
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Rectangular rooms joined by corridors. Every room is joined to the room made before it so all of them can be
+//! reached from the first one.
+
+use bracket_lib::{random::RandomNumberGenerator, terminal::Rect};
+
+use super::FloorPlan;
+
+const MAX_ROOMS: usize = 30;
+const MIN_ROOM_SIZE: i32 = 5;
+const MAX_ROOM_SIZE: i32 = 12;
+
+/// Returns the plan along with the middle of the first room
+pub(crate) fn rooms_and_corridors(
+    width: usize,
+    height: usize,
+    rng: &mut RandomNumberGenerator,
+) -> (FloorPlan, (usize, usize)) {
+    let mut plan = FloorPlan::new(width, height);
+    let mut rooms: Vec<Rect> = vec![];
+
+    for _ in 0..MAX_ROOMS {
+        let (w, h) = (rng.range(MIN_ROOM_SIZE, MAX_ROOM_SIZE + 1), rng.range(MIN_ROOM_SIZE, MAX_ROOM_SIZE + 1));
+        // the edge of the floor is always left as wall
+        let x = rng.range(1, width as i32 - w - 1);
+        let y = rng.range(1, height as i32 - h - 1);
+        let room = Rect::with_size(x, y, w, h);
+        if rooms.iter().any(|other| room.intersect(other)) {
+            continue;
+        }
+
+        for ry in room.y1..room.y2 {
+            for rx in room.x1..room.x2 {
+                plan.set_open(rx as usize, ry as usize);
+            }
+        }
+        if let Some(last) = rooms.last() {
+            let (from, to) = (last.center(), room.center());
+            if rng.range(0, 2) == 0 {
+                carve_horizontal(&mut plan, from.x, to.x, from.y);
+                carve_vertical(&mut plan, from.y, to.y, to.x);
+            } else {
+                carve_vertical(&mut plan, from.y, to.y, from.x);
+                carve_horizontal(&mut plan, from.x, to.x, to.y);
+            }
+        }
+        rooms.push(room);
+    }
+
+    // the first room is never turned down since there is nothing for it to overlap
+    let start = rooms[0].center();
+    (plan, (start.x as usize, start.y as usize))
+}
+
+fn carve_horizontal(plan: &mut FloorPlan, x1: i32, x2: i32, y: i32) {
+    for x in x1.min(x2)..=x1.max(x2) {
+        plan.set_open(x as usize, y as usize);
+    }
+}
+
+fn carve_vertical(plan: &mut FloorPlan, y1: i32, y2: i32, x: i32) {
+    for y in y1.min(y2)..=y1.max(y2) {
+        plan.set_open(x as usize, y as usize);
+    }
+}
@@ -0,0 +1,260 @@
+//! Gameplay systems describe what happened by sending a `GameEvent` instead of writing to the message log or
+//! playing sounds themselves. Anything interested in what happened during a frame (the presenter, quests,
+//! statistics) reads the events from `GameEvents` before they are cleared at the end of the frame.
+
+use std::time::Duration;
+
+use bracket_lib::color::WHITE;
+use specs::{Entity, Read, ReadExpect, ReadStorage, System, Write};
+
+use crate::{
+    audio::play_sound_effect,
+    char_c::CH_STRIKE,
+    components::{Breakable, HealthStats, Name, Position, Renderable, SizeFlexor, Viewshed},
+    data_read::GameDataRes,
+    game_init::PlayerEntity,
+    items::{ItemID, ItemQty},
+    tile_animation::{AnimationRequest, TileAnimationBuilder},
+    ui::message_log::MessageLog,
+    z_order::EFFECT_Z,
+};
+
+pub enum GameEvent {
+    DamageDealt {
+        attacker: Entity,
+        target: Entity,
+        amount: usize,
+    },
+    /// The target's defense was greater than the attacker's strength
+    AttackBlocked {
+        attacker: Entity,
+        target: Entity,
+    },
+    /// The breaker did not own the tool needed to break the target
+    MissingTool {
+        breaker: Entity,
+        target: Entity,
+    },
+    /// `first_of_kind` is true when the picker did not already have a stack of the item
+    ItemPickedUp {
+        picker: Entity,
+        item: ItemID,
+        qty: ItemQty,
+        first_of_kind: bool,
+    },
+    /// The name is kept since the entity will be deleted by the time most readers see the event
+    EntityDied {
+        entity: Entity,
+        name: Name,
+    },
+    FishHooked {
+        fisher: Entity,
+        attempts_left: usize,
+    },
+    FishingReel {
+        fisher: Entity,
+        hit: bool,
+    },
+    FishEscaped {
+        fisher: Entity,
+    },
+    FishCaught {
+        fisher: Entity,
+        item: ItemID,
+    },
+    FishingAttemptsExhausted {
+        fisher: Entity,
+    },
+    CraftSucceeded {
+        crafter: Entity,
+        output: ItemID,
+    },
+    CraftFailed {
+        crafter: Entity,
+    },
+    QuipSpoken {
+        speaker: Entity,
+        quip: String,
+    },
+}
+
+/// Every event sent during the current frame, in the order they were sent
+#[derive(Default)]
+pub struct GameEvents {
+    events: Vec<GameEvent>,
+}
+
+impl GameEvents {
+    pub fn send(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &GameEvent> {
+        self.events.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+/// Turns the frame's events into log messages, sounds and animations for the player
+pub struct GameEventPresenter;
+
+impl<'a> System<'a> for GameEventPresenter {
+    type SystemData = (
+        Read<'a, GameEvents>,
+        Write<'a, MessageLog>,
+        Write<'a, TileAnimationBuilder>,
+        Read<'a, PlayerEntity>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Breakable>,
+        ReadStorage<'a, HealthStats>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Viewshed>,
+        ReadExpect<'a, GameDataRes>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            events,
+            mut log,
+            mut anim_builder,
+            player_e,
+            names,
+            breakables,
+            healths,
+            positions,
+            viewsheds,
+            game_data,
+        ): Self::SystemData,
+    ) {
+        let edb = &game_data.0;
+        let name_of = |e: Entity| names.get(e).cloned().unwrap_or_else(Name::missing_item_name);
+
+        for event in events.iter() {
+            match event {
+                GameEvent::DamageDealt { attacker, target, amount } => {
+                    log.log(format!("{} dealt {} damage to {}", name_of(*attacker), amount, name_of(*target)));
+                    if !breakables.contains(*target) {
+                        continue;
+                    }
+
+                    let target_name = name_of(*target);
+                    if *attacker == player_e.0 {
+                        if let Some(info) = edb.world_objs.get_by_name(&target_name.0) {
+                            play_sound_effect(&info.impact_sound);
+                        }
+                    }
+                    // a broken tile is deleted this frame so there is nothing left to animate
+                    if healths.get(*target).is_none_or(|health| health.hp == 0) {
+                        continue;
+                    }
+                    anim_builder.request(AnimationRequest::StretchShrink(
+                        *target,
+                        SizeFlexor::new(&[(0.75, 1.25), (1.0, 1.0)], 25.0),
+                    ));
+                    anim_builder.request(AnimationRequest::GlyphFlash(
+                        *target,
+                        Duration::from_secs_f32(0.15),
+                        Renderable::clear_bg(CH_STRIKE, WHITE, EFFECT_Z),
+                    ));
+                }
+                GameEvent::AttackBlocked { attacker, target } => {
+                    if *attacker == player_e.0 || *target == player_e.0 {
+                        log.log(format!(
+                            "{} took no damage from {} because defense is greater",
+                            name_of(*target),
+                            name_of(*attacker)
+                        ))
+                    }
+                }
+                GameEvent::MissingTool { breaker, target } => {
+                    if *breaker == player_e.0 {
+                        log.log(format!("You do not own the correct tool for this {}.", name_of(*target)))
+                    }
+                }
+                GameEvent::ItemPickedUp { picker, item, qty, first_of_kind } => {
+                    let info = edb.items.get_by_id(*item);
+                    if *first_of_kind {
+                        if let Some(text) = info.and_then(|info| info.pickup_text.as_ref()) {
+                            log.enhance(text);
+                        }
+                    }
+                    let item_name = info.map_or(Name::missing_item_name().0, |info| info.name.to_lowercase());
+                    if qty.0 == 1 {
+                        log.log(format!("{} picked up a {}", name_of(*picker), item_name));
+                    } else {
+                        log.log(format!("{} picked up {} {}", name_of(*picker), qty, item_name));
+                    }
+                    if *picker == player_e.0 {
+                        play_sound_effect("pickup");
+                    }
+                }
+                GameEvent::EntityDied { entity, name } => {
+                    if *entity == player_e.0 {
+                        log.log("#[red]You died.#[]");
+                    } else {
+                        log.log(format!("{} was destroyed.", name));
+                    }
+                }
+                GameEvent::FishHooked { fisher, attempts_left } => {
+                    // the player is put into the fishing minigame instead
+                    if *fisher != player_e.0 {
+                        log.log(format!(
+                            "{} caught a fish wow with {} attempts remaining",
+                            name_of(*fisher),
+                            attempts_left
+                        ));
+                    }
+                }
+                // only the player plays the reeling minigame
+                GameEvent::FishingReel { fisher, hit } if *fisher == player_e.0 => {
+                    if *hit {
+                        log.log("#[bright_green]Success!#[]");
+                    } else {
+                        log.log("#[orange]Missed#[] the fish zone.");
+                    }
+                }
+                GameEvent::FishingReel { .. } => {}
+                GameEvent::FishEscaped { fisher } => {
+                    if *fisher == player_e.0 {
+                        log.log("#[red]Ahhh, the fish got away.#[]");
+                    }
+                }
+                GameEvent::FishCaught { fisher, item } => {
+                    if *fisher == player_e.0 {
+                        log.log("#[bright_green]Success!#[]");
+                    }
+                    let fish_name = edb.items.get_by_id(*item).map_or("really big fish", |info| &info.name);
+                    log.enhance(format!("{} caught a {}!", name_of(*fisher), fish_name.to_lowercase()));
+                }
+                GameEvent::FishingAttemptsExhausted { fisher } => {
+                    log.enhance(format!("{} ran out of attempts to catch a fish", name_of(*fisher)))
+                }
+                GameEvent::CraftSucceeded { crafter, output } => {
+                    if *crafter == player_e.0 {
+                        let output_name = edb.items.get_by_id(*output).map_or("something", |info| &info.name);
+                        log.log(format!("Crafted {}.", output_name.to_lowercase()));
+                    }
+                }
+                GameEvent::CraftFailed { crafter } => {
+                    if *crafter == player_e.0 {
+                        log.log("These things had no effect on each other.");
+                    }
+                }
+                GameEvent::QuipSpoken { speaker, quip } => {
+                    // the player only notices quips from beings they can see
+                    let seen = positions
+                        .get(*speaker)
+                        .zip(viewsheds.get(player_e.0))
+                        .is_some_and(|(pos, view)| view.tiles.contains(&pos.to_point()));
+                    if seen {
+                        log.enhance(quip);
+                    }
+                }
+            }
+        }
+    }
+}
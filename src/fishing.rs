@@ -5,29 +5,34 @@ use crate::{
         DeleteCondition, FinishedActivity, FishAction, FishOnTheLine, Fishable, FishingMinigame, GameAction, Name,
         Renderable, WaitingForFish, Water,
     },
+    events::{GameEvent, GameEvents},
     game_init::PlayerEntity,
     items::{ItemID, ItemSpawner, SpawnType},
+    rng::GameRng,
     tile_animation::{AnimationRequest, TileAnimationBuilder},
     time::DeltaTime,
-    ui::message_log::MessageLog,
     z_order::EFFECT_Z,
 };
 
 pub const WHITE: (u8, u8, u8) = (255, 255, 255);
 
-use bracket_lib::{color::ColorPair, random::*, terminal::BLACK};
-use log::info;
+use bracket_lib::{color::ColorPair, terminal::BLACK};
+use log::{debug, error, info};
 use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteExpect, WriteStorage};
 
 pub struct SetupFishingActions;
 
 impl<'a> System<'a> for SetupFishingActions {
-    type SystemData =
-        (Entities<'a>, WriteStorage<'a, FishAction>, WriteStorage<'a, WaitingForFish>, Write<'a, TileAnimationBuilder>);
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, FishAction>,
+        WriteStorage<'a, WaitingForFish>,
+        Write<'a, TileAnimationBuilder>,
+        WriteExpect<'a, GameRng>,
+    );
 
-    fn run(&mut self, (entities, mut fish_actions, mut fish_waiters, mut anim_builder): Self::SystemData) {
+    fn run(&mut self, (entities, mut fish_actions, mut fish_waiters, mut anim_builder, mut rng): Self::SystemData) {
         for (fisher, fish_action) in (&entities, &mut fish_actions).join() {
-            let mut rng = RandomNumberGenerator::new();
             anim_builder.request(AnimationRequest::StaticTile(
                 112,
                 fish_action.target,
@@ -65,7 +70,8 @@ impl<'a> System<'a> for WaitingForFishSystem {
         WriteStorage<'a, FinishedActivity>,
         Read<'a, PlayerEntity>,
         Read<'a, DeltaTime>,
-        WriteExpect<'a, MessageLog>,
+        Write<'a, GameEvents>,
+        WriteExpect<'a, GameRng>,
         ReadStorage<'a, Name>,
     );
 
@@ -79,17 +85,17 @@ impl<'a> System<'a> for WaitingForFishSystem {
             mut finished_activities,
             p_entity,
             dt,
-            mut log,
+            mut events,
+            mut rng,
             names,
         ): Self::SystemData,
     ) {
-        let mut rng = RandomNumberGenerator::new();
         let mut finished_fishers = Vec::new();
 
         for (e, waiter, name) in (&entities, &mut waiters, &names).join() {
             if waiter.attempts == 0 {
                 finished_fishers.push(e);
-                log.enhance(format!("{} ran out of attempts to catch a fish", name));
+                events.send(GameEvent::FishingAttemptsExhausted { fisher: e });
                 continue;
             }
 
@@ -102,7 +108,7 @@ impl<'a> System<'a> for WaitingForFishSystem {
             waiter.attempts -= 1;
 
             let roll = rng.range(1, 100);
-            log.debug(format!("Attempts left: {} | Rolled: {} ", waiter.attempts, roll));
+            debug!("Attempts left: {} | Rolled: {} ", waiter.attempts, roll);
 
             // if roll < 1 {
             // for testing fix with below
@@ -112,6 +118,7 @@ impl<'a> System<'a> for WaitingForFishSystem {
 
             // Bite on the line, succeeding in fishing attempt
             finished_fishers.push(e);
+            events.send(GameEvent::FishHooked { fisher: e, attempts_left: waiter.attempts });
             if e == p_entity.0 {
                 let _ = minigames.insert(
                     e,
@@ -125,18 +132,21 @@ impl<'a> System<'a> for WaitingForFishSystem {
                 );
             } else {
                 info!("{} caught a fish, o cool", name);
-                log.log(format!("{} caught a fish wow with {} attempts remaining", name, waiter.attempts));
             }
 
             match fishing_lines.insert(e, FishOnTheLine {}) {
                 Ok(existing_fish) => {
                     if let Some(fish) = existing_fish {
-                        log.debug(format!("ERROR: entity {} {} already had a fish on their line, cannot add a second fish ABORTING fish", name, e.id()));
+                        error!(
+                            "entity {} {} already had a fish on their line, cannot add a second fish ABORTING fish",
+                            name,
+                            e.id()
+                        );
                         let _ = fishing_lines.insert(e, fish);
                     }
                 }
                 Err(err) => {
-                    log.debug(format!("ERROR: entity: {} {} failed to add fish on the line: {}", name, e.id(), err));
+                    error!("entity: {} {} failed to add fish on the line: {}", name, e.id(), err);
                 }
             }
         }
@@ -204,12 +214,12 @@ impl<'a> System<'a> for FishingMinigameUpdate {
         WriteStorage<'a, FishingMinigame>,
         WriteStorage<'a, FinishedActivity>,
         WriteStorage<'a, FishOnTheLine>,
-        Write<'a, MessageLog>,
+        Write<'a, GameEvents>,
         Entities<'a>,
         Read<'a, DeltaTime>,
     );
 
-    fn run(&mut self, (mut minigames, mut finished_activities, mut hooks, mut log, entities, dt): Self::SystemData) {
+    fn run(&mut self, (mut minigames, mut finished_activities, mut hooks, mut events, entities, dt): Self::SystemData) {
         let mut remove_mes = vec![];
         for (fisher, minigame) in (&entities, &mut minigames).join() {
             let seconds_past = dt.0.as_millis() as f32 / 1000.0;
@@ -236,14 +246,13 @@ impl<'a> System<'a> for FishingMinigameUpdate {
 
             if minigame.reel.catch_percent <= 0.0 {
                 let _ = finished_activities.insert(fisher, FinishedActivity {});
-                log.log("#[bright_green]Success!#[]");
                 continue;
             }
 
             minigame.reel.catch_percent += minigame.reel.runaway_speed * seconds_past;
 
             if minigame.reel.catch_percent >= 100.0 || minigame.attempts_left == 0 {
-                log.log("#[red]Ahhh, the fish got away.#[]");
+                events.send(GameEvent::FishEscaped { fisher });
                 hooks.remove(fisher);
                 remove_mes.push(fisher);
                 let _ = finished_activities.insert(fisher, FinishedActivity {});
@@ -261,7 +270,7 @@ impl<'a> System<'a> for FishingMinigameCheck {
     type SystemData = (
         WriteStorage<'a, GameAction>,
         WriteStorage<'a, FishingMinigame>,
-        Write<'a, MessageLog>,
+        Write<'a, GameEvents>,
         ReadStorage<'a, FinishedActivity>,
         ReadStorage<'a, FishOnTheLine>,
         Read<'a, PlayerEntity>,
@@ -270,20 +279,21 @@ impl<'a> System<'a> for FishingMinigameCheck {
 
     fn run(
         &mut self,
-        (mut game_actions, mut minigames, mut log, finished_activities, hooks, p_entity, entities): Self::SystemData,
+        (mut game_actions, mut minigames, mut events, finished_activities, hooks, p_entity, entities): Self::SystemData,
     ) {
-        if let Some((_, _, _, game, ())) = (&entities, &game_actions, &hooks, &mut minigames, !&finished_activities)
-            .join()
-            .find(|(e, _, _, _, _)| *e == p_entity.0)
+        if let Some((fisher, _, _, game, ())) =
+            (&entities, &game_actions, &hooks, &mut minigames, !&finished_activities)
+                .join()
+                .find(|(e, _, _, _, _)| *e == p_entity.0)
         {
             info!("Game action read, checking if in_pos");
             let hit_idx = game.cursor.bar_position();
             let start_idx = game.goal_bar.goal;
             if hit_idx <= start_idx + game.goal_bar.goal_width && hit_idx >= start_idx {
-                log.log("#[bright_green]Success!#[]");
+                events.send(GameEvent::FishingReel { fisher, hit: true });
                 game.reel.catch_percent -= 15.0;
             } else {
-                log.log("#[orange]Missed#[] the fish zone.");
+                events.send(GameEvent::FishingReel { fisher, hit: false });
                 game.attempts_left = game.attempts_left.saturating_sub(1);
                 game.reel.catch_percent += 5.0;
             }
@@ -301,20 +311,20 @@ impl<'a> System<'a> for CatchFishSystem {
         WriteStorage<'a, FishOnTheLine>,
         WriteStorage<'a, FishingMinigame>,
         WriteExpect<'a, ItemSpawner>,
-        WriteExpect<'a, MessageLog>,
+        Write<'a, GameEvents>,
         ReadStorage<'a, FinishedActivity>,
         ReadStorage<'a, Name>,
     );
 
     fn run(
         &mut self,
-        (entities, mut hooks, mut minigames, mut item_spawner, mut log, finished_activities, names): Self::SystemData,
+        (entities, mut hooks, mut minigames, mut item_spawner, mut events, finished_activities, names): Self::SystemData,
     ) {
         let mut remove_mes = Vec::new();
         for (e, _, name, _) in (&entities, &hooks, &names, &finished_activities).join() {
             remove_mes.push((e, name));
-            log.enhance(format!("{} caught a really big fish!", name));
             item_spawner.request(ItemID(3), SpawnType::InBag(e));
+            events.send(GameEvent::FishCaught { fisher: e, item: ItemID(3) });
         }
         for (entity, _) in remove_mes.iter() {
             hooks.remove(*entity);
@@ -328,10 +338,15 @@ pub struct CreateFishingBubbles;
 pub const BUBBLE_SPAWN_RATE: usize = 1000;
 pub const BUBBLE_LIFETIME_SECS: u64 = 10;
 impl<'a> System<'a> for CreateFishingBubbles {
-    type SystemData = (WriteStorage<'a, Fishable>, WriteStorage<'a, Renderable>, ReadStorage<'a, Water>, Entities<'a>);
+    type SystemData = (
+        WriteStorage<'a, Fishable>,
+        WriteStorage<'a, Renderable>,
+        ReadStorage<'a, Water>,
+        WriteExpect<'a, GameRng>,
+        Entities<'a>,
+    );
 
-    fn run(&mut self, (mut fishables, mut renderables, waters, entities): Self::SystemData) {
-        let mut rng = RandomNumberGenerator::new();
+    fn run(&mut self, (mut fishables, mut renderables, waters, mut rng, entities): Self::SystemData) {
         let mut new_bubbles = Vec::new();
         for (_, _, entity) in (!(&fishables), &waters, &entities).join() {
             if rng.range(0, BUBBLE_SPAWN_RATE) < 3 {
@@ -1,32 +1,64 @@
 use bracket_lib::{
     pathfinding::{field_of_view_set, Algorithm2D, BaseMap},
     prelude::{Point, SmallVec},
-    terminal::{to_char, ColorPair, DistanceAlg, DrawBatch},
+    terminal::{to_char, ColorPair, DistanceAlg, DrawBatch, BLACK, RGBA},
 };
-use specs::{Join, Read, ReadStorage, System, World, WorldExt, WriteStorage};
+use specs::{Join, LendJoin, Read, ReadStorage, System, World, WorldExt, Write, WriteStorage};
 
 use crate::{
     camera::get_camera_bounds,
-    colors::{DARKBLUE, WHITE},
+    colors::DARKBLUE,
     components::{Position, Viewshed},
+    daylight::WorldLight,
     game_init::PlayerEntity,
     indexing::idx_to_point,
     map::{Map, MapRes},
+    player::Player,
     CL_EFFECTS,
 };
 
+/// Alpha of the shade over tiles the player remembers but can't see right now
+const REMEMBERED_SHADE: u8 = 170;
+
+/// Beings see less outside of lit tiles when it isn't day. Also reveals every tile the player sees on the map
 pub struct UpdateViewsheds;
 
 impl<'a> System<'a> for UpdateViewsheds {
-    type SystemData = (WriteStorage<'a, Viewshed>, ReadStorage<'a, Position>, Read<'a, MapRes>);
-
-    fn run(&mut self, (mut viewsheds, positions, map): Self::SystemData) {
-        for (view, pos) in (&mut viewsheds, &positions).join() {
-            view.tiles = field_of_view_set(pos.to_point(), view.range as i32, &map.0);
+    type SystemData = (
+        WriteStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        Write<'a, MapRes>,
+        Read<'a, WorldLight>,
+    );
+
+    fn run(&mut self, (mut viewsheds, positions, players, mut map, light): Self::SystemData) {
+        for (view, pos, player) in (&mut viewsheds, &positions, players.maybe()).join() {
+            let origin = pos.to_point();
+            view.tiles = field_of_view_set(origin, view.range as i32, &map.0);
+            let dark_range = light.vision_range(view.range) as f32;
+            if dark_range < view.range as f32 {
+                view.tiles.retain(|tile| {
+                    light.lit.contains(tile) || DistanceAlg::Pythagoras.distance2d(origin, *tile) <= dark_range
+                });
+            }
+            if player.is_none() {
+                continue;
+            }
+            let seen: Vec<usize> = view
+                .tiles
+                .iter()
+                .filter(|tile| map.0.in_bounds(**tile))
+                .map(|tile| map.0.point2d_to_index(*tile))
+                .collect();
+            for idx in seen {
+                map.0.revealed.set(idx, true);
+            }
         }
     }
 }
 
+/// Tiles the player remembers are shaded, the ones never seen are covered up completely
 pub fn draw_unseen_area(draw_batch: &mut DrawBatch, ecs: &World) {
     let player_e = ecs.read_resource::<PlayerEntity>();
     let viewsheds = ecs.read_storage::<Viewshed>();
@@ -34,18 +66,25 @@ pub fn draw_unseen_area(draw_batch: &mut DrawBatch, ecs: &World) {
         Some(v) => v,
         None => return,
     };
+    let map = ecs.read_resource::<MapRes>();
+    let (r, g, b) = DARKBLUE;
+    let remembered = ColorPair::new(RGBA::from_u8(0, 0, 0, 0), RGBA::from_u8(r, g, b, REMEMBERED_SHADE));
+    let unknown = ColorPair::new(BLACK, BLACK);
 
     draw_batch.target(CL_EFFECTS);
     let bounding_box = get_camera_bounds(ecs);
     for x in bounding_box.x1..bounding_box.x2 {
         for y in bounding_box.y1..bounding_box.y2 {
-            if player_view.tiles.contains(&Point { x, y }) {
+            let point = Point { x, y };
+            if player_view.tiles.contains(&point) {
                 continue;
             }
 
+            let is_remembered = map.0.in_bounds(point) && map.0.revealed.get(map.0.point2d_to_index(point));
+            let colors = if is_remembered { remembered } else { unknown };
             let screen_x = x - bounding_box.x1;
             let screen_y = y - bounding_box.y1;
-            draw_batch.set(Point::new(screen_x, screen_y), ColorPair::new(WHITE, DARKBLUE), to_char(34));
+            draw_batch.set(Point::new(screen_x, screen_y), colors, to_char(34));
         }
     }
 }
@@ -1,52 +1,197 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt, time::Duration};
 
 use bracket_lib::terminal::{BTerm, VirtualKeyCode};
-use log::debug;
+use log::{debug, warn};
 use specs::{
-    saveload::{MarkedBuilder, SimpleMarker},
+    saveload::{MarkedBuilder, SimpleMarker, SimpleMarkerAllocator},
     Builder, Entity, World, WorldExt,
 };
 
 pub const WHITE: (u8, u8, u8) = (255, 255, 255);
 
 use crate::{
+    being::BeingID,
+    chunks::{unload_world, CHUNK_SIZE},
     components::{
-        EquipmentSlots, Interactor, InteractorMode, LevelPersistent, Name, Position, Renderable, Transform, Viewshed,
+        AttackAction, AttackBonus, Blocking, BreakAction, Breakable, Consumable, ConsumeAction, CraftAction,
+        DeleteCondition, Doorway, EntityStats, EquipAction, Equipable, EquipmentSlots, Equipped, FinishedActivity,
+        FishAction, FishOnTheLine, Fishable, FishingMinigame, GameAction, GlyphFlash, GoalMoverAI, Grass, HealAction,
+        HealthStats, InBag, Interactor, InteractorMode, Item, LevelPersistent, LightSource, MoveAction, Name,
+        PickupAction, Position, RandomWalkerAI, Renderable, River, SelectedInventoryItem, SizeFlexor, Stairs,
+        SufferDamage, Transform, Viewshed, WaitingForFish, Water,
     },
-    data_read::prelude::build_being,
+    data_read::{
+        prelude::{game_data, GameDataRes, AUDIOMAN},
+        DataError,
+    },
+    daylight::WorldLight,
+    events::GameEvents,
+    frame_animation::AnimationRenderer,
     get_text,
+    indexing::{index_map, SpatialIndex},
     items::{ItemID, ItemSpawner, SpawnType},
-    map::MapRes,
-    map_gen::{gen_world, WorldConfig},
+    keybindings::{Action, BindingContext, Keybindings},
+    levels::{load_level, LoadedLevel},
+    map::{Map, MapRes},
+    map_gen::{find_player_spawn, gen_world, WorldConfig, WorldPreview},
     player::Player,
-    saveload::{SerializeMe, SAVE_EXTENSION},
-    saveload_menu::LoadedWorld,
+    rng::GameRng,
+    saveload::{SerializationHelper, SerializeChunk, SerializeMe, SAVE_EXTENSION},
+    saveload_menu::{GameSaves, LoadedWorld},
     stats::get_random_stats,
+    tile_animation::TileAnimationBuilder,
+    time::DeltaTime,
+    ui::message_log::MessageLog,
     z_order::PLAYER_Z,
-    CL_WORLD,
+    TurnCounter, CL_WORLD, CL_WORLD_OVERLAY,
 };
 
+/// Times a generated world is made again from the next seed when it has nowhere for the player to start
+const START_REGEN_TRIES: u64 = 3;
+
 /// A convenient resource to access the entity associated with the player
 pub struct PlayerEntity(pub Entity);
 
+/// Describes why a new game could not be started
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NewWorldError {
+    /// The raws the world is built from are broken
+    Data(DataError),
+    /// A generated world had no open land to start on for any of the seeds that were tried
+    NoStart { world: String, first_seed: u64, last_seed: u64 },
+}
+
+impl From<DataError> for NewWorldError {
+    fn from(error: DataError) -> Self {
+        Self::Data(error)
+    }
+}
+
+impl fmt::Display for NewWorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NewWorldError::Data(error) => error.fmt(f),
+            NewWorldError::NoStart { world, first_seed, last_seed } => {
+                write!(f, "{} has no open land to start on with seeds {} to {}", world, first_seed, last_seed)
+            }
+        }
+    }
+}
+
 impl Default for PlayerEntity {
     fn default() -> Self {
         panic!("Dont call default on player_entity")
     }
 }
 
-pub fn initialize_new_game_world(ecs: &mut World, world_config: &WorldConfig) {
+/// Creates the ECS world with every component registered and every resource given a basic definition.
+/// The `AppState` is left to the caller since it depends on how the game is being ran.
+pub fn initialize_ecs() -> World {
+    let mut world = World::new();
+
+    // Component Registration, the ECS needs to have every type of component registered
+    world.register::<Position>();
+    world.register::<Player>();
+    world.register::<BeingID>();
+    world.register::<Renderable>();
+    world.register::<Blocking>();
+    world.register::<HealthStats>();
+    world.register::<BreakAction>();
+    world.register::<AttackAction>();
+    world.register::<PickupAction>();
+    world.register::<FishAction>();
+    world.register::<Breakable>();
+    world.register::<SufferDamage>();
+    world.register::<Fishable>();
+    world.register::<WaitingForFish>();
+    world.register::<FishOnTheLine>();
+    world.register::<DeleteCondition>();
+    world.register::<FinishedActivity>();
+    world.register::<Name>();
+    world.register::<RandomWalkerAI>();
+    world.register::<GoalMoverAI>();
+    world.register::<Item>();
+    world.register::<Water>();
+    world.register::<River>();
+    world.register::<Stairs>();
+    world.register::<Grass>();
+    world.register::<InBag>();
+    world.register::<MoveAction>();
+    world.register::<CraftAction>();
+    world.register::<EquipAction>();
+    world.register::<Transform>();
+    world.register::<Interactor>();
+    world.register::<EntityStats>();
+    world.register::<SelectedInventoryItem>();
+    world.register::<EquipmentSlots>();
+    world.register::<Equipable>();
+    world.register::<Equipped>();
+    world.register::<AttackBonus>();
+    world.register::<Consumable>();
+    world.register::<ConsumeAction>();
+    world.register::<HealAction>();
+    world.register::<GameAction>();
+    world.register::<FishingMinigame>();
+    world.register::<LevelPersistent>();
+    world.register::<Doorway>();
+    world.register::<SizeFlexor>();
+    world.register::<GlyphFlash>();
+    world.register::<Viewshed>();
+    world.register::<LightSource>();
+
+    // Still components but used for saving the data in the ecs
+    world.register::<SimpleMarker<SerializeMe>>();
+    world.register::<SimpleMarker<SerializeChunk>>();
+    world.register::<SerializationHelper>();
+    world.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    world.insert(SimpleMarkerAllocator::<SerializeChunk>::new());
+
+    // Resource Initialization, the ECS needs a basic definition of every resource that will be in the game
+    world.insert(DeltaTime(Duration::ZERO));
+    world.insert(TileAnimationBuilder::new());
+    world.insert(AnimationRenderer::new());
+    world.insert(ItemSpawner::new());
+    world.insert(MessageLog::new());
+    world.insert(GameEvents::default());
+    world.insert(MapRes(Map::empty(0, 0)));
+    world.insert(TurnCounter::zero());
+    world.insert(WorldLight::default());
+    world.insert(GameSaves::default());
+    world.insert(LoadedWorld::default());
+    world.insert(GameRng::default());
+    world.insert(WorldPreview::default());
+    world.insert(GameDataRes(game_data()));
+    let spatial_index = SpatialIndex::new(&mut world);
+    world.insert(spatial_index);
+
+    world
+}
+
+/// Fails when the starting level can't be loaded from the raws or a generated world has nowhere to start
+pub fn initialize_new_game_world(ecs: &mut World, world_config: &WorldConfig) -> Result<(), NewWorldError> {
     debug!("startup: map loading");
-    let new_chunk = gen_world(ecs, world_config);
+    let mut world_config = world_config.clone();
+    let (new_chunk, player_spawn) = match world_config.start_level.clone() {
+        Some(level_name) => {
+            let LoadedLevel { map, player_spawn } = load_level(ecs, &level_name)?;
+            let spawn = player_spawn.unwrap_or_else(|| Position::new(map.width / 2, map.height / 2));
+            (map, spawn)
+        }
+        None => gen_starting_world(ecs, &mut world_config)?,
+    };
     ecs.insert(MapRes(new_chunk));
+    ecs.write_resource::<SpatialIndex>().request_rebuild();
     debug!("startup: map loaded");
 
-    let mut player_stats = get_random_stats();
+    ecs.insert(GameRng::from_world_seed(world_config.seed));
+    AUDIOMAN.lock().unwrap().reseed(world_config.seed);
+
+    let mut player_stats = get_random_stats(&mut ecs.write_resource::<GameRng>());
     player_stats.set.vitality = 25;
     player_stats.set.strength = 2;
     let player_entity = ecs
         .create_entity()
-        .with(Position::new(67, 30))
+        .with(player_spawn)
         .with(Interactor::new(InteractorMode::Reactive))
         .with(Player {})
         .with(Viewshed { tiles: HashSet::new(), range: 16 })
@@ -66,19 +211,49 @@ pub fn initialize_new_game_world(ecs: &mut World, world_config: &WorldConfig) {
         item_spawner.request(ItemID(201), SpawnType::InBag(player_entity));
     }
 
-    build_being("Bahhhby", Position::new(5, 15), ecs).ok();
-    let greg = build_being("Greg Goat", Position::new(12, 19), ecs).unwrap();
-    let mut transforms = ecs.write_storage::<Transform>();
-    let _ = transforms.insert(greg, Transform::new(12.0, 19.0, 0.0, 1.0, 1.0));
-    debug!("startup: sample beings loaded");
-
     let mut lw = ecs.write_resource::<LoadedWorld>();
     lw.file_name = Some(format!("{}.{}", world_config.world_name.clone(), SAVE_EXTENSION));
+    Ok(())
 }
 
-/// Updates the CL_WORLD layer's font to match the active map's tile atlas
+/// Generates the world around its center and finds the player somewhere to start near it. A world with nowhere
+/// to start is generated again from the next seed, `world_config` is left with the seed that was used and the
+/// player is told about the change in the message log.
+fn gen_starting_world(ecs: &mut World, world_config: &mut WorldConfig) -> Result<(Map, Position), NewWorldError> {
+    let first_seed = world_config.seed;
+    for attempt in 0..=START_REGEN_TRIES {
+        let center = Position::new(world_config.width / 2, world_config.height / 2);
+        world_config.player_start = (center.x, center.y);
+        let mut map = gen_world(ecs, world_config, &center);
+        index_map(&mut map, ecs);
+
+        let local_center = Position::new(center.x - map.chunk_coords.x, center.y - map.chunk_coords.y);
+        if let Some(spawn) = find_player_spawn(&map, &local_center) {
+            if world_config.seed != first_seed {
+                ecs.write_resource::<MessageLog>().log(format!(
+                    "Seed {} had nowhere to start, {} was generated from seed {} instead.",
+                    first_seed, world_config.world_name, world_config.seed
+                ));
+            }
+            return Ok((map, spawn));
+        }
+        unload_world(ecs, &map);
+        if attempt < START_REGEN_TRIES {
+            warn!(
+                "{} with seed {} has nowhere to start, generating it again",
+                world_config.world_name, world_config.seed
+            );
+            world_config.seed = world_config.seed.wrapping_add(1);
+        }
+    }
+    Err(NewWorldError::NoStart { world: world_config.world_name.clone(), first_seed, last_seed: world_config.seed })
+}
+
+/// Updates the CL_WORLD and CL_WORLD_OVERLAY layers' fonts to match the active map's tile atlas
 pub fn set_level_font(ecs: &World, ctx: &mut BTerm) {
     let map = ecs.read_resource::<MapRes>();
+    ctx.set_active_console(CL_WORLD_OVERLAY);
+    ctx.set_active_font(map.0.overlay_font(), false);
     ctx.set_active_console(CL_WORLD);
     ctx.set_active_font(map.0.tile_atlas_index, false);
     debug!("Level font changed to index {}", map.0.tile_atlas_index);
@@ -89,7 +264,10 @@ pub enum NewGameMenuSelection {
     WorldName,
     Width,
     Height,
+    SeaLevel,
     Seed,
+    ResourceDensity,
+    NoisePreset,
     Finalize,
 }
 
@@ -98,8 +276,11 @@ impl NewGameMenuSelection {
         match self {
             NewGameMenuSelection::WorldName => NewGameMenuSelection::Width,
             NewGameMenuSelection::Width => NewGameMenuSelection::Height,
-            NewGameMenuSelection::Height => NewGameMenuSelection::Seed,
-            NewGameMenuSelection::Seed => NewGameMenuSelection::Finalize,
+            NewGameMenuSelection::Height => NewGameMenuSelection::SeaLevel,
+            NewGameMenuSelection::SeaLevel => NewGameMenuSelection::Seed,
+            NewGameMenuSelection::Seed => NewGameMenuSelection::ResourceDensity,
+            NewGameMenuSelection::ResourceDensity => NewGameMenuSelection::NoisePreset,
+            NewGameMenuSelection::NoisePreset => NewGameMenuSelection::Finalize,
             NewGameMenuSelection::Finalize => NewGameMenuSelection::WorldName,
         }
     }
@@ -109,10 +290,24 @@ impl NewGameMenuSelection {
             NewGameMenuSelection::WorldName => NewGameMenuSelection::Finalize,
             NewGameMenuSelection::Width => NewGameMenuSelection::WorldName,
             NewGameMenuSelection::Height => NewGameMenuSelection::Width,
-            NewGameMenuSelection::Seed => NewGameMenuSelection::Height,
-            NewGameMenuSelection::Finalize => NewGameMenuSelection::Seed,
+            NewGameMenuSelection::SeaLevel => NewGameMenuSelection::Height,
+            NewGameMenuSelection::Seed => NewGameMenuSelection::SeaLevel,
+            NewGameMenuSelection::ResourceDensity => NewGameMenuSelection::Seed,
+            NewGameMenuSelection::NoisePreset => NewGameMenuSelection::ResourceDensity,
+            NewGameMenuSelection::Finalize => NewGameMenuSelection::NoisePreset,
         }
     }
+
+    /// Fields that only take digits
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            NewGameMenuSelection::Width
+                | NewGameMenuSelection::Height
+                | NewGameMenuSelection::SeaLevel
+                | NewGameMenuSelection::ResourceDensity
+        )
+    }
 }
 
 pub enum NewGameMenuAction {
@@ -132,21 +327,43 @@ pub struct InputWorldConfig {
     pub height: String,
     pub sea_level: String,
     pub seed: String,
+    /// Percent of the usual amount of resources
+    pub resource_density: String,
+    /// Name of the noise preset, empty for none
+    pub noise_preset: String,
 }
 
 impl Default for InputWorldConfig {
     fn default() -> Self {
         Self {
             world_name: String::new(),
-            width: "100".to_string(),
-            height: "100".to_string(),
+            width: (2 * CHUNK_SIZE).to_string(),
+            height: (2 * CHUNK_SIZE).to_string(),
             sea_level: "33".to_string(),
             seed: String::new(),
+            resource_density: "100".to_string(),
+            noise_preset: String::new(),
+        }
+    }
+}
+
+impl InputWorldConfig {
+    /// The text of a field that is typed into, `None` for the ones that aren't
+    pub fn text_field(&mut self, field: &NewGameMenuSelection) -> Option<&mut String> {
+        match field {
+            NewGameMenuSelection::WorldName => Some(&mut self.world_name),
+            NewGameMenuSelection::Width => Some(&mut self.width),
+            NewGameMenuSelection::Height => Some(&mut self.height),
+            NewGameMenuSelection::SeaLevel => Some(&mut self.sea_level),
+            NewGameMenuSelection::Seed => Some(&mut self.seed),
+            NewGameMenuSelection::ResourceDensity => Some(&mut self.resource_density),
+            NewGameMenuSelection::NoisePreset | NewGameMenuSelection::Finalize => None,
         }
     }
 }
 
-pub fn p_input_new_game_menu(ctx: &mut BTerm) -> NewGameMenuAction {
+/// Letters and numbers always type into the form, so only the other keys bound to menu actions are used
+pub fn p_input_new_game_menu(ctx: &mut BTerm, binds: &Keybindings) -> NewGameMenuAction {
     if let Some(key) = ctx.key {
         if let Some(letter) = get_text(key) {
             if ctx.shift {
@@ -156,13 +373,15 @@ pub fn p_input_new_game_menu(ctx: &mut BTerm) -> NewGameMenuAction {
         }
 
         return match key {
-            VirtualKeyCode::Return => NewGameMenuAction::Select,
-            VirtualKeyCode::Down => NewGameMenuAction::Down,
-            VirtualKeyCode::Up => NewGameMenuAction::Up,
             VirtualKeyCode::Back => NewGameMenuAction::DelChar,
-            VirtualKeyCode::Escape => NewGameMenuAction::Leave,
             VirtualKeyCode::Tab => NewGameMenuAction::Down,
-            _ => NewGameMenuAction::Waiting,
+            _ => match binds.action(BindingContext::Menu, key) {
+                Some(Action::MenuSelect) => NewGameMenuAction::Select,
+                Some(Action::MenuDown) => NewGameMenuAction::Down,
+                Some(Action::MenuUp) => NewGameMenuAction::Up,
+                Some(Action::MenuBack) => NewGameMenuAction::Leave,
+                _ => NewGameMenuAction::Waiting,
+            },
         };
     }
 
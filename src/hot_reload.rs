@@ -0,0 +1,145 @@
+//! Reloads the raws while the game is running so data can be tuned without restarting.
+//! Entities already in the world are refreshed from the new data where they can be matched to it,
+//! items and beings by their id and world objects by their name.
+
+use std::{
+    fs,
+    time::{Duration, Instant, SystemTime},
+};
+
+use bracket_lib::terminal::ColorPair;
+use log::{error, info};
+use specs::{Entity, Join, World, WorldExt};
+
+use crate::{
+    being::BeingID,
+    components::{AttackBonus, Consumable, Equipable, Fishable, Item, Name, Renderable},
+    data_read::{game_data, reload_game_databases, GameDataRes},
+    storage_utils::MaybeInsert,
+    ui::message_log::MessageLog,
+    z_order::WORLD_OBJECT_Z,
+};
+
+const RAWS_FOLDER: &str = "raws/";
+/// How often the raws folder is checked for changes when watching
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reloads the raws and refreshes the entities using them, errors are shown in the message log
+pub fn reload_raws(ecs: &mut World) {
+    if let Err(e) = reload_game_databases() {
+        error!("Raws reload failed: {}", e);
+        ecs.write_resource::<MessageLog>().log(format!("#[red]Raws reload failed:#[] {}", e));
+        return;
+    }
+
+    ecs.insert(GameDataRes(game_data()));
+    let refreshed = refresh_entities(ecs);
+    info!("Raws reloaded, {} entities were refreshed", refreshed);
+    ecs.write_resource::<MessageLog>().debug(format!("Raws reloaded, {} entities were refreshed.", refreshed));
+}
+
+/// Applies the static data to every entity made from it, returns how many entities were refreshed
+fn refresh_entities(ecs: &mut World) -> usize {
+    let edb = game_data();
+    let entities = ecs.entities();
+    let items = ecs.read_storage::<Item>();
+    let beings = ecs.read_storage::<BeingID>();
+    let fishables = ecs.read_storage::<Fishable>();
+    let mut names = ecs.write_storage::<Name>();
+    let mut renderables = ecs.write_storage::<Renderable>();
+    let mut consumables = ecs.write_storage::<Consumable>();
+    let mut equipables = ecs.write_storage::<Equipable>();
+    let mut attack_bonuses = ecs.write_storage::<AttackBonus>();
+    let mut refreshed = 0;
+
+    for (entity, item) in (&entities, &items).join() {
+        if let Some(info) = edb.items.get_by_id(item.id) {
+            let _ = names.insert(entity, Name(info.name.clone()));
+            refresh_renderable(&mut renderables, entity, info.atlas_index, info.fg);
+            consumables.insert_or_remove(entity, info.consumable.clone());
+            equipables.insert_or_remove(entity, info.equipable.clone());
+            attack_bonuses.insert_or_remove(entity, info.attack_bonus.clone());
+            refreshed += 1;
+        }
+    }
+
+    for (entity, being_id) in (&entities, &beings).join() {
+        if let Some(being) = edb.beings.get_by_id(being_id.0) {
+            let _ = names.insert(entity, Name(being.name.clone()));
+            refresh_renderable(&mut renderables, entity, being.atlas_index, being.fg);
+            refreshed += 1;
+        }
+    }
+
+    // fishing bubbles are drawn with the water's renderable so the water is left alone
+    for (entity, name, (), (), ()) in (&entities, &names, !&items, !&beings, !&fishables).join() {
+        if let Some(world_obj) = edb.world_objs.get_by_name(&name.0) {
+            if let Some(foreground) = world_obj.foreground {
+                match renderables.get_mut(entity) {
+                    Some(renderable) => {
+                        renderable.atlas_index = world_obj.atlas_index;
+                        renderable.color_pair = ColorPair::new(foreground, renderable.color_pair.bg);
+                    }
+                    None => {
+                        let _ = renderables
+                            .insert(entity, Renderable::clear_bg(world_obj.atlas_index, foreground, WORLD_OBJECT_Z));
+                    }
+                }
+            }
+            refreshed += 1;
+        }
+    }
+
+    refreshed
+}
+
+/// Keeps the z order and background of the existing renderable since those are not part of the raws
+fn refresh_renderable(
+    renderables: &mut specs::WriteStorage<Renderable>,
+    entity: Entity,
+    atlas_index: u8,
+    fg: (u8, u8, u8),
+) {
+    if let Some(renderable) = renderables.get_mut(entity) {
+        renderable.atlas_index = atlas_index;
+        renderable.color_pair = ColorPair::new(fg, renderable.color_pair.bg);
+    }
+}
+
+/// Polls the raws folder for changes, enabled with the `--watch-raws` launch argument
+pub struct RawsWatcher {
+    last_modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl RawsWatcher {
+    pub fn new() -> Self {
+        Self { last_modified: latest_raws_change(), last_poll: Instant::now() }
+    }
+
+    /// Returns true when a raws file was changed since the last time this returned true
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < WATCH_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        let latest = latest_raws_change();
+        if latest > self.last_modified {
+            self.last_modified = latest;
+            return true;
+        }
+        false
+    }
+}
+
+fn latest_raws_change() -> Option<SystemTime> {
+    let entries = match fs::read_dir(RAWS_FOLDER) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Could not watch {}: {}", RAWS_FOLDER, e);
+            return None;
+        }
+    };
+    entries.filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok()).max()
+}
@@ -1,100 +1,191 @@
 /* Indexing.rs
- *   Contains the many systems that index entities or tiles in order to update the index when
- *   needed.
+ *   Keeps the contents of every tile in the map up to date. Only the entities whose Position, Blocking,
+ *   Breakable, Fishable or Item changed since the last frame are moved in the index, the whole index is
+ *   only rebuilt when a new map is put in place.
+ *   Building with `--features check_spatial_index` checks the index against a full rebuild every frame.
  * */
 
+use std::collections::{HashMap, HashSet};
+
 use bracket_lib::terminal::Point;
 use log::warn;
-use specs::{Entities, Join, ReadStorage, System, WriteExpect};
+use specs::{
+    storage::{ComponentEvent, Tracked},
+    world::Index,
+    Component, Entities, Entity, Join, ReadStorage, ReaderId, System, World, WorldExt, WriteExpect,
+};
 
 use crate::{
     components::{Blocking, Breakable, Fishable, Item, Position},
-    map::{MapRes, TileEntity},
+    map::{Map, MapRes, TileEntity},
 };
 
-/// Clears the entity contents of every tile in the map
-pub struct IndexReset;
-
-impl<'a> System<'a> for IndexReset {
-    type SystemData = (WriteExpect<'a, MapRes>,);
+/// Tracks what changed in the flagged storages since the index was last updated
+pub struct SpatialIndex {
+    position_reader: ReaderId<ComponentEvent>,
+    blocking_reader: ReaderId<ComponentEvent>,
+    breakable_reader: ReaderId<ComponentEvent>,
+    fishable_reader: ReaderId<ComponentEvent>,
+    item_reader: ReaderId<ComponentEvent>,
+    /// The tile each indexed entity was placed on
+    placed: HashMap<Index, usize>,
+    needs_rebuild: bool,
+}
 
-    fn run(&mut self, (mut map,): Self::SystemData) {
-        for content in map.0.tile_entities.iter_mut() {
-            content.clear();
+impl SpatialIndex {
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            position_reader: world.write_storage::<Position>().register_reader(),
+            blocking_reader: world.write_storage::<Blocking>().register_reader(),
+            breakable_reader: world.write_storage::<Breakable>().register_reader(),
+            fishable_reader: world.write_storage::<Fishable>().register_reader(),
+            item_reader: world.write_storage::<Item>().register_reader(),
+            placed: HashMap::new(),
+            needs_rebuild: true,
         }
     }
+
+    /// Rebuilds the whole index on the next update, needed whenever the map is replaced
+    pub fn request_rebuild(&mut self) {
+        self.needs_rebuild = true;
+    }
+}
+
+/// Indexes every entity on `map` right away, for looking at what is on a map before it is put in place
+pub fn index_map(map: &mut Map, ecs: &World) {
+    let storages = (
+        ecs.read_storage::<Position>(),
+        ecs.read_storage::<Blocking>(),
+        ecs.read_storage::<Breakable>(),
+        ecs.read_storage::<Fishable>(),
+        ecs.read_storage::<Item>(),
+    );
+    rebuild(map, &ecs.entities(), &storages);
 }
 
-pub struct IndexBlockedTiles;
+type IndexedStorages<'a> = (
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Blocking>,
+    ReadStorage<'a, Breakable>,
+    ReadStorage<'a, Fishable>,
+    ReadStorage<'a, Item>,
+);
+
+/// Moves the entities that changed since the last frame to the tiles they are on now
+pub struct UpdateSpatialIndex;
+
+impl<'a> System<'a> for UpdateSpatialIndex {
+    type SystemData = (WriteExpect<'a, MapRes>, WriteExpect<'a, SpatialIndex>, Entities<'a>, IndexedStorages<'a>);
+
+    fn run(&mut self, (mut map, mut index, entities, storages): Self::SystemData) {
+        let (positions, blocking, breakable, fishable, items) = &storages;
+        let index = &mut *index;
+
+        let mut dirty = HashSet::new();
+        collect_changes(positions, &mut index.position_reader, true, &mut dirty);
+        collect_changes(blocking, &mut index.blocking_reader, false, &mut dirty);
+        collect_changes(breakable, &mut index.breakable_reader, false, &mut dirty);
+        collect_changes(fishable, &mut index.fishable_reader, false, &mut dirty);
+        collect_changes(items, &mut index.item_reader, false, &mut dirty);
+
+        if index.needs_rebuild {
+            index.needs_rebuild = false;
+            index.placed = rebuild(&mut map.0, &entities, &storages);
+            return;
+        }
 
-impl<'a> System<'a> for IndexBlockedTiles {
-    type SystemData = (WriteExpect<'a, MapRes>, ReadStorage<'a, Position>, ReadStorage<'a, Blocking>, Entities<'a>);
+        for id in dirty {
+            let entity = entities.entity(id);
+            let current_idx = positions.get(entity).map(|pos| map.0.xy_to_idx(pos.x, pos.y));
+            // the mover system moves blockers itself so an entity's entries can be on either tile
+            for idx in index.placed.remove(&id).into_iter().chain(current_idx) {
+                if let Some(contents) = map.0.tile_entities.get_mut(idx) {
+                    contents.retain(|te| te.entity().id() != id);
+                }
+            }
 
-    fn run(&mut self, (mut map, pos, blocking, entities): Self::SystemData) {
-        for (pos, _, e) in (&pos, &blocking, &entities).join() {
-            let idx = map.0.xy_to_idx(pos.x, pos.y);
-            match map.0.tile_entities.get_mut(idx) {
-                Some(entities) => {
-                    entities.push(TileEntity::Blocking(e));
+            if !entities.is_alive(entity) {
+                continue;
+            }
+            if let Some(idx) = current_idx {
+                if place_entity(&mut map.0, idx, entity, &storages) {
+                    index.placed.insert(id, idx);
                 }
-                None => warn!("Idx: {} was out of bounds, {:?}", idx, pos),
             }
         }
+
+        if cfg!(feature = "check_spatial_index") {
+            let mut expected = map.0.clone();
+            rebuild(&mut expected, &entities, &storages);
+            assert!(
+                expected.tile_entities == map.0.tile_entities,
+                "The spatial index no longer matches a full rebuild"
+            );
+        }
     }
 }
 
-pub struct IndexBreakableTiles;
-
-impl<'a> System<'a> for IndexBreakableTiles {
-    type SystemData = (WriteExpect<'a, MapRes>, ReadStorage<'a, Position>, ReadStorage<'a, Breakable>, Entities<'a>);
-
-    fn run(&mut self, (mut map, pos, breakable, entities): Self::SystemData) {
-        for (id, pos, _) in (&entities, &pos, &breakable).join() {
-            let idx = map.0.xy_to_idx(pos.x, pos.y);
-            match map.0.tile_entities.get_mut(idx) {
-                Some(entities) => {
-                    entities.push(TileEntity::Breakable(id));
-                }
-                None => warn!("Idx: {} was out of bounds, {:?}", idx, pos),
+/// Only the position of an entity decides where it is indexed, every other storage is only indexed by whether the
+/// entity has the component so changes to its value can be skipped with `track_modified`. Fishing bubbles count down
+/// every frame for example.
+fn collect_changes<T: Component>(
+    storage: &ReadStorage<T>,
+    reader: &mut ReaderId<ComponentEvent>,
+    track_modified: bool,
+    dirty: &mut HashSet<Index>,
+) where
+    T::Storage: Tracked,
+{
+    for event in storage.channel().read(reader) {
+        match event {
+            ComponentEvent::Inserted(id) | ComponentEvent::Removed(id) => {
+                dirty.insert(*id);
+            }
+            ComponentEvent::Modified(id) if track_modified => {
+                dirty.insert(*id);
             }
+            ComponentEvent::Modified(_) => {}
         }
     }
 }
 
-pub struct IndexFishableTiles;
-
-impl<'a> System<'a> for IndexFishableTiles {
-    type SystemData = (WriteExpect<'a, MapRes>, ReadStorage<'a, Position>, ReadStorage<'a, Fishable>, Entities<'a>);
+/// Clears every tile and indexes every entity again, returns the tile each entity was placed on
+fn rebuild(map: &mut Map, entities: &Entities, storages: &IndexedStorages) -> HashMap<Index, usize> {
+    for content in map.tile_entities.iter_mut() {
+        content.clear();
+    }
 
-    fn run(&mut self, (mut map, pos, fishable, entities): Self::SystemData) {
-        for (entity, pos, _) in (&entities, &pos, &fishable).join() {
-            let idx = map.0.xy_to_idx(pos.x, pos.y);
-            match map.0.tile_entities.get_mut(idx) {
-                Some(entities) => {
-                    entities.push(TileEntity::Fishable(entity));
-                }
-                None => warn!("Idx: {} was out of bounds, {:?}", idx, pos),
-            }
+    let mut placed = HashMap::new();
+    for (entity, pos) in (entities, &storages.0).join() {
+        let idx = map.xy_to_idx(pos.x, pos.y);
+        if place_entity(map, idx, entity, storages) {
+            placed.insert(entity.id(), idx);
         }
     }
+    placed
 }
 
-pub struct IndexItemTiles;
-
-impl<'a> System<'a> for IndexItemTiles {
-    type SystemData = (WriteExpect<'a, MapRes>, ReadStorage<'a, Position>, ReadStorage<'a, Item>, Entities<'a>);
+/// Returns false when the entity has nothing that belongs in the index or the tile is off the map
+fn place_entity(map: &mut Map, idx: usize, entity: Entity, storages: &IndexedStorages) -> bool {
+    let (_, blocking, breakable, fishable, items) = storages;
+    let tile_entities = [
+        blocking.contains(entity).then_some(TileEntity::Blocking(entity)),
+        breakable.contains(entity).then_some(TileEntity::Breakable(entity)),
+        fishable.contains(entity).then_some(TileEntity::Fishable(entity)),
+        items.contains(entity).then_some(TileEntity::Item(entity)),
+    ];
+    if tile_entities.iter().all(Option::is_none) {
+        return false;
+    }
+    if idx >= map.tile_entities.len() {
+        warn!("Idx: {} was out of bounds for {:?}", idx, entity);
+        return false;
+    }
 
-    fn run(&mut self, (mut map, pos, items, entities): Self::SystemData) {
-        for (entity, pos, _) in (&entities, &pos, &items).join() {
-            let idx = map.0.xy_to_idx(pos.x, pos.y);
-            match map.0.tile_entities.get_mut(idx) {
-                Some(entities) => {
-                    entities.push(TileEntity::Item(entity));
-                }
-                None => warn!("Idx: {} was out of bounds, {:?}", idx, pos),
-            }
-        }
+    for tile_entity in tile_entities.into_iter().flatten() {
+        map.place_tile_entity(idx, tile_entity);
     }
+    true
 }
 
 pub fn idx_to_point(idx: usize, width: usize) -> Point {
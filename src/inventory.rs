@@ -1,13 +1,14 @@
-use bracket_lib::terminal::{BTerm, VirtualKeyCode as VKC};
+use bracket_lib::terminal::BTerm;
 use itertools::Itertools;
 use specs::{Entity, Join, World, WorldExt};
 use specs::{LendJoin, ReadStorage};
 
 use crate::components::{ConsumeAction, Equipped, Position};
 use crate::config::{InventoryConfig, SortMode};
-use crate::data_read::ENTITY_DB;
+use crate::data_read::game_data;
+use crate::keybindings::{Action, BindingContext, Keybindings};
 use crate::{
-    components::{CraftAction, EquipAction, InBag, Item, Name, SelectedInventoryItem},
+    components::{CraftAction, EquipAction, InBag, Item, LevelPersistent, Name, SelectedInventoryItem},
     game_init::PlayerEntity,
     ui::message_log::MessageLog,
     AppState,
@@ -30,7 +31,12 @@ pub enum InventoryResponse {
     StateChange(AppState),
 }
 
-pub fn p_input_inventory(ecs: &mut World, ctx: &BTerm, cfg: &mut InventoryConfig) -> InventoryResponse {
+pub fn p_input_inventory(
+    ecs: &mut World,
+    ctx: &BTerm,
+    cfg: &mut InventoryConfig,
+    binds: &Keybindings,
+) -> InventoryResponse {
     let player_entity: Entity;
     {
         // dirty borrow checker hack to take the value of player entity
@@ -41,29 +47,29 @@ pub fn p_input_inventory(ecs: &mut World, ctx: &BTerm, cfg: &mut InventoryConfig
         Some(key) if check_inventory_selection(ecs) == SelectionStatus::SelectionWithoutAction => {
             let mut selected_idxs = ecs.write_storage::<SelectedInventoryItem>();
             if let Some(selection) = selected_idxs.get_mut(player_entity) {
-                match key {
-                    VKC::U => {
+                match binds.action(BindingContext::ItemMenu, key) {
+                    Some(Action::UseWith) => {
                         // using an item with something else translates to crafting most of the time (99.9%)
                         selection.intended_action = Some(UseMenuResult::Craft);
                         InventoryResponse::Waiting
                     }
-                    VKC::E => {
+                    Some(Action::Examine) => {
                         selection.intended_action = Some(UseMenuResult::Examine);
                         InventoryResponse::ActionReady
                     }
-                    VKC::D => {
+                    Some(Action::Drop) => {
                         selection.intended_action = Some(UseMenuResult::Drop);
                         InventoryResponse::ActionReady
                     }
-                    VKC::Q => {
+                    Some(Action::Equip) => {
                         selection.intended_action = Some(UseMenuResult::Equip);
                         InventoryResponse::ActionReady
                     }
-                    VKC::C => {
+                    Some(Action::Consume) => {
                         selection.intended_action = Some(UseMenuResult::Consume);
                         InventoryResponse::ActionReady
                     }
-                    VKC::Escape => {
+                    Some(Action::CancelItemMenu) => {
                         selection.intended_action = Some(UseMenuResult::Cancel);
                         InventoryResponse::ActionReady
                     }
@@ -73,38 +79,22 @@ pub fn p_input_inventory(ecs: &mut World, ctx: &BTerm, cfg: &mut InventoryConfig
                 InventoryResponse::Waiting
             }
         }
-        Some(key) => {
-            match key {
-                VKC::Key1 => select_item(&player_entity, 0, ecs, cfg),
-                VKC::Key2 => select_item(&player_entity, 1, ecs, cfg),
-                VKC::Key3 => select_item(&player_entity, 2, ecs, cfg),
-                VKC::Key4 => select_item(&player_entity, 3, ecs, cfg),
-                VKC::Key5 => select_item(&player_entity, 4, ecs, cfg),
-                VKC::Key6 => select_item(&player_entity, 5, ecs, cfg),
-                VKC::Key7 => select_item(&player_entity, 6, ecs, cfg),
-                VKC::Key8 => select_item(&player_entity, 7, ecs, cfg),
-                VKC::Key9 => select_item(&player_entity, 8, ecs, cfg),
-                VKC::A => select_item(&player_entity, 9, ecs, cfg),
-                VKC::B => select_item(&player_entity, 10, ecs, cfg),
-                VKC::C => select_item(&player_entity, 11, ecs, cfg),
-                VKC::D => select_item(&player_entity, 12, ecs, cfg),
-                VKC::E => select_item(&player_entity, 13, ecs, cfg),
-                VKC::F => select_item(&player_entity, 14, ecs, cfg),
-                VKC::G => select_item(&player_entity, 15, ecs, cfg),
-                VKC::H => select_item(&player_entity, 16, ecs, cfg),
-                VKC::S => {
-                    cfg.rotate_sort_mode();
-                    InventoryResponse::Waiting
-                }
-                VKC::Escape | VKC::I => clean_and_exit_inventory(&player_entity, ecs),
-                _ => InventoryResponse::Waiting, // Unbound keypress so just ignore it
+        Some(key) => match binds.action(BindingContext::Inventory, key) {
+            Some(Action::SortInventory) => {
+                cfg.rotate_sort_mode();
+                InventoryResponse::Waiting
             }
-        }
+            Some(Action::CloseInventory) => clean_and_exit_inventory(&player_entity, ecs),
+            _ => match binds.inventory_slot(key) {
+                Some(idx) => select_item(&player_entity, idx, ecs, cfg),
+                None => InventoryResponse::Waiting, // Unbound keypress so just ignore it
+            },
+        },
     }
 }
 
 // This function depends on the ui code of getting the iterator
-fn select_item(
+pub(crate) fn select_item(
     player_entity: &Entity,
     idx_selected: usize,
     ecs: &mut World,
@@ -177,6 +167,8 @@ pub fn handle_one_item_actions(ecs: &mut World) {
             // remove item from bag
             if let Some((item_entity, dropped_item, _)) = items_in_player_bag {
                 in_bags.remove(item_entity);
+                // left behind on the ground when the player changes levels
+                ecs.write_storage::<LevelPersistent>().remove(item_entity);
                 log.log("Dropped it");
 
                 let mut positions = ecs.write_storage::<Position>();
@@ -196,7 +188,7 @@ pub fn handle_one_item_actions(ecs: &mut World) {
         UseMenuResult::Examine => {
             //log flavor text
             if let Some((_, item, _)) = items_in_player_bag {
-                let examine_text = match &ENTITY_DB.lock().unwrap().items.get_by_id(item.id) {
+                let examine_text = match game_data().items.get_by_id(item.id) {
                     Some(info) => info.examine_text.clone(),
                     None => format!("Could not find item with id: {}", item.id),
                 };
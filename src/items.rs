@@ -14,20 +14,19 @@ use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 use specs::{
     saveload::{MarkerAllocator, SimpleMarker, SimpleMarkerAllocator},
-    Entities, Entity, Join, Read, ReadStorage, System, World, WorldExt, Write, WriteStorage,
+    Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, World, WorldExt, Write, WriteStorage,
 };
 
 use crate::{
-    audio::play_sound_effect,
     components::{
-        AttackBonus, Consumable, ConsumeAction, Equipable, HealAction, InBag, Item, LevelPersistent, Name,
+        AttackBonus, Consumable, ConsumeAction, Equipable, HealAction, InBag, Item, LevelPersistent, LightSource, Name,
         PickupAction, Position, Renderable,
     },
-    data_read::prelude::*,
+    data_read::{prelude::*, DataError, ITEMS_FILE},
+    events::{GameEvent, GameEvents},
     game_init::PlayerEntity,
     saveload::SerializeMe,
     storage_utils::MaybeInsert,
-    ui::message_log::MessageLog,
     z_order::ITEM_Z,
 };
 
@@ -66,11 +65,13 @@ impl ItemSpawner {
         Self { requests: Vec::new() }
     }
 
-    #[allow(unused)]
-    pub fn request_named(&mut self, name: &str, spawn_type: SpawnType) {
-        let edb = &ENTITY_DB.lock().unwrap();
-        let id = edb.items.get_by_name(name).unwrap().identifier;
+    pub fn request_named(&mut self, name: &str, spawn_type: SpawnType) -> Result<(), DataError> {
+        let id = match game_data().items.get_by_name(name) {
+            Some(info) => info.identifier,
+            None => return Err(DataError::NotFound { file: ITEMS_FILE, entry: name.to_string() }),
+        };
         self.requests.push(ItemSpawnRequest { id, qty: ItemQty(1), spawn_type });
+        Ok(())
     }
 
     pub fn request(&mut self, id: ItemID, spawn_type: SpawnType) {
@@ -108,10 +109,12 @@ impl<'a> System<'a> for ItemSpawnerSystem {
         WriteStorage<'a, Equipable>,
         WriteStorage<'a, AttackBonus>,
         WriteStorage<'a, Consumable>,
+        WriteStorage<'a, LightSource>,
         WriteStorage<'a, LevelPersistent>,
         WriteStorage<'a, SimpleMarker<SerializeMe>>,
         Write<'a, SimpleMarkerAllocator<SerializeMe>>,
         Read<'a, PlayerEntity>,
+        ReadExpect<'a, GameDataRes>,
     );
 
     fn run(
@@ -127,13 +130,15 @@ impl<'a> System<'a> for ItemSpawnerSystem {
             mut equipables,
             mut attack_bonus,
             mut consumables,
+            mut lights,
             mut persistents,
             mut serializables,
             mut mark_allocator,
             player_entity,
+            game_data,
         ): Self::SystemData,
     ) {
-        let edb = &ENTITY_DB.lock().unwrap();
+        let edb = &game_data.0;
 
         for spawn in spawn_requests.requests.iter() {
             let static_item = match edb.items.get_by_id(spawn.id) {
@@ -183,6 +188,7 @@ impl<'a> System<'a> for ItemSpawnerSystem {
             equipables.maybe_insert(new_item, static_item.equipable.clone());
             consumables.maybe_insert(new_item, static_item.consumable.clone());
             attack_bonus.maybe_insert(new_item, static_item.attack_bonus.clone());
+            lights.maybe_insert(new_item, static_item.light.clone());
 
             let _ = renderables.insert(new_item, Renderable::clear_bg(static_item.atlas_index, static_item.fg, ITEM_Z));
             let _ = names.insert(new_item, Name(static_item.name.clone()));
@@ -201,7 +207,7 @@ impl<'a> System<'a> for ItemPickupHandler {
         WriteStorage<'a, InBag>,
         WriteStorage<'a, Item>,
         WriteStorage<'a, LevelPersistent>,
-        Write<'a, MessageLog>,
+        Write<'a, GameEvents>,
         Read<'a, PlayerEntity>,
         ReadStorage<'a, Name>,
         Entities<'a>,
@@ -215,13 +221,13 @@ impl<'a> System<'a> for ItemPickupHandler {
             mut inbags,
             mut items,
             mut persistents,
-            mut log,
+            mut events,
             player_entity,
             names,
             entities,
         ): Self::SystemData,
     ) {
-        for (picker, pickup, picker_name) in (&entities, &pickup_actions, &names).join() {
+        for (picker, pickup, _) in (&entities, &pickup_actions, &names).join() {
             let ground_entity = pickup.item;
             let item_name = match names.get(ground_entity) {
                 Some(name) => name.clone(),
@@ -235,15 +241,16 @@ impl<'a> System<'a> for ItemPickupHandler {
                 }
             };
 
-            let edb = &ENTITY_DB.lock().unwrap();
+            let picked_up = ground_item.clone();
             // TODO: check inventory capacity
-            match (&entities, &items, &inbags)
+            let first_of_kind = match (&entities, &items, &inbags)
                 .join()
-                .find(|(_, item, bag)| bag.owner == picker && item.id == ground_item.id)
+                .find(|(_, item, bag)| bag.owner == picker && item.id == picked_up.id)
             {
                 Some((bagged_entity, bagged_item, _)) => {
-                    let _ = items.insert(bagged_entity, Item::new(bagged_item.id, bagged_item.qty + ground_item.qty));
+                    let _ = items.insert(bagged_entity, Item::new(bagged_item.id, bagged_item.qty + picked_up.qty));
                     let _ = entities.delete(ground_entity);
+                    false
                 }
                 None => {
                     let _ = inbags.insert(ground_entity, InBag { owner: picker });
@@ -251,15 +258,10 @@ impl<'a> System<'a> for ItemPickupHandler {
                         let _ = persistents.insert(ground_entity, LevelPersistent {});
                     }
                     positions.remove(ground_entity);
-                    if let Some(text) = &edb.items.get_by_name_unchecked(&item_name.0).pickup_text {
-                        log.enhance(text);
-                    }
+                    true
                 }
-            }
-            log.log(format!("{} picked up a {}", picker_name, item_name.0.to_lowercase()));
-            if player_entity.0 == picker {
-                play_sound_effect("pickup");
-            }
+            };
+            events.send(GameEvent::ItemPickedUp { picker, item: picked_up.id, qty: picked_up.qty, first_of_kind });
         }
 
         pickup_actions.clear();
@@ -302,6 +304,8 @@ pub struct ItemInfo {
     pub equipable: Option<Equipable>,
     pub attack_bonus: Option<AttackBonus>,
     pub consumable: Option<Consumable>,
+    /// Lights up the tiles around the item, or around whoever has it equipped
+    pub light: Option<LightSource>,
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, Hash, Eq, PartialEq, Default, PartialOrd, Ord)]
@@ -309,7 +313,7 @@ pub struct ItemID(pub u32);
 
 impl Display for ItemID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let edb = &ENTITY_DB.lock().unwrap();
+        let edb = game_data();
         let missing = format!("Missing name {}", self.0);
         let name = match edb.items.get_by_id(*self) {
             Some(info) => &info.name,
@@ -0,0 +1,472 @@
+//! Keys are looked up through the `Keybindings` instead of being matched on directly so players can change them.
+//! Each context is a screen (or part of one) where a key only means one thing, so the same key can be reused
+//! across contexts without colliding. The bindings are saved to `KEYBINDINGS_PATH` next to `config.json`.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use bracket_lib::terminal::{BTerm, VirtualKeyCode as VKC};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::keys::{key_from_name, key_name};
+
+const KEYBINDINGS_PATH: &str = "./keybindings.json";
+
+/// Keys that select the 1st, 2nd, ... item in the inventory by default
+const DEFAULT_INVENTORY_SLOTS: [&str; 17] =
+    ["1", "2", "3", "4", "5", "6", "7", "8", "9", "A", "B", "C", "D", "E", "F", "G", "H"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Action {
+    // Game
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Pickup,
+    ToggleInteractMode,
+    OpenInventory,
+    Wait,
+    OpenSaveMenu,
+    // Activity
+    #[serde(alias = "ActivityAction")]
+    ActivityInput,
+    // Inventory
+    SortInventory,
+    CloseInventory,
+    // Item Menu
+    UseWith,
+    Examine,
+    Drop,
+    Equip,
+    Consume,
+    #[serde(alias = "CancelItemAction")]
+    CancelItemMenu,
+    // Menu
+    MenuUp,
+    MenuDown,
+    MenuSelect,
+    MenuBack,
+    // Save Menu
+    Save,
+    QuickSave,
+    QuitWithoutSaving,
+    CancelSave,
+    // Debug, these also require CTRL to be held
+    DebugPrintPosition,
+    DebugReloadRaws,
+}
+
+impl Action {
+    /// Constructs a readable name for the action such as "Move Up"
+    pub fn display_name(&self) -> String {
+        let raw = format!("{:?}", self);
+        let mut name = String::new();
+        for (idx, ch) in raw.chars().enumerate() {
+            if idx != 0 && ch.is_ascii_uppercase() {
+                name.push(' ');
+            }
+            name.push(ch);
+        }
+        name
+    }
+}
+
+/// Where the key was pressed, a key can only be bound to one action in each context
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BindingContext {
+    Game,
+    Activity,
+    Inventory,
+    ItemMenu,
+    Menu,
+    SaveMenu,
+    Debug,
+}
+
+impl BindingContext {
+    pub const ALL: [BindingContext; 7] = [
+        BindingContext::Game,
+        BindingContext::Activity,
+        BindingContext::Inventory,
+        BindingContext::ItemMenu,
+        BindingContext::Menu,
+        BindingContext::SaveMenu,
+        BindingContext::Debug,
+    ];
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            BindingContext::Game => "Game",
+            BindingContext::Activity => "Activity",
+            BindingContext::Inventory => "Inventory",
+            BindingContext::ItemMenu => "Item Menu",
+            BindingContext::Menu => "Menus",
+            BindingContext::SaveMenu => "Save Menu",
+            BindingContext::Debug => "Debug",
+        }
+    }
+}
+
+/// Every bindable thing, one line on the rebind screen
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BindingRow {
+    Action(BindingContext, Action),
+    InventorySlot(usize),
+}
+
+impl BindingRow {
+    fn context(&self) -> BindingContext {
+        match self {
+            BindingRow::Action(context, _) => *context,
+            BindingRow::InventorySlot(_) => BindingContext::Inventory,
+        }
+    }
+}
+
+type ContextBindings = BTreeMap<Action, Vec<String>>;
+
+/// The keys bound to each action, keys are stored by their name from `keys.rs` so the file is easy to edit
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Keybindings {
+    pub game: ContextBindings,
+    pub activity: ContextBindings,
+    pub inventory: ContextBindings,
+    /// The nth key selects the nth item in the inventory, checked after the inventory actions
+    pub inventory_slots: Vec<String>,
+    pub item_menu: ContextBindings,
+    pub menu: ContextBindings,
+    pub save_menu: ContextBindings,
+    pub debug: ContextBindings,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            game: bindings(&[
+                (Action::MoveUp, &["W", "Up"]),
+                (Action::MoveDown, &["S", "Down"]),
+                (Action::MoveLeft, &["A", "Left"]),
+                (Action::MoveRight, &["D", "Right"]),
+                (Action::Pickup, &["P"]),
+                (Action::ToggleInteractMode, &["M"]),
+                (Action::OpenInventory, &["I"]),
+                (Action::Wait, &["Space"]),
+                (Action::OpenSaveMenu, &["Escape"]),
+            ]),
+            activity: bindings(&[(Action::ActivityInput, &["Space"])]),
+            inventory: bindings(&[(Action::SortInventory, &["S"]), (Action::CloseInventory, &["Escape", "I"])]),
+            inventory_slots: DEFAULT_INVENTORY_SLOTS.iter().map(|key| key.to_string()).collect(),
+            item_menu: bindings(&[
+                (Action::UseWith, &["U"]),
+                (Action::Examine, &["E"]),
+                (Action::Drop, &["D"]),
+                (Action::Equip, &["Q"]),
+                (Action::Consume, &["C"]),
+                (Action::CancelItemMenu, &["Escape"]),
+            ]),
+            menu: bindings(&[
+                (Action::MenuUp, &["Up", "W"]),
+                (Action::MenuDown, &["Down", "S"]),
+                (Action::MenuSelect, &["Enter"]),
+                (Action::MenuBack, &["Escape"]),
+            ]),
+            save_menu: bindings(&[
+                (Action::Save, &["Enter", "S"]),
+                (Action::QuickSave, &["Space"]),
+                (Action::QuitWithoutSaving, &["D"]),
+                (Action::CancelSave, &["Escape"]),
+            ]),
+            debug: bindings(&[(Action::DebugPrintPosition, &["V"]), (Action::DebugReloadRaws, &["R"])]),
+        }
+    }
+}
+
+fn bindings(actions: &[(Action, &[&str])]) -> ContextBindings {
+    actions.iter().map(|(action, keys)| (*action, keys.iter().map(|key| key.to_string()).collect())).collect()
+}
+
+impl Keybindings {
+    pub fn save(&self) {
+        self.save_to(Path::new(KEYBINDINGS_PATH));
+    }
+
+    fn save_to(&self, path: &Path) {
+        let data = serde_json::to_string_pretty(self).unwrap();
+        if let Err(e) = fs::write(path, data) {
+            error!("Keybindings Saving Error: {}", e);
+        }
+    }
+
+    /// Loads the bindings located at `KEYBINDINGS_PATH` or creates the file with the defaults.
+    /// Actions missing from the file are given their default keys so new actions work with old files.
+    pub fn load() -> Self {
+        Self::load_from(Path::new(KEYBINDINGS_PATH))
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let mut binds = match fs::read_to_string(path) {
+            Ok(raw_binds) => match serde_json::from_str::<Keybindings>(&raw_binds) {
+                Ok(binds) => binds,
+                Err(e) => {
+                    error!("Keybindings Reading Error: {}", e);
+                    warn!("Keybindings at {} could not be read. Falling back to default.", path.display());
+                    return Keybindings::default();
+                }
+            },
+            Err(_) => {
+                info!("No keybindings found at {}, creating them from the defaults.", path.display());
+                let binds = Keybindings::default();
+                binds.save_to(path);
+                return binds;
+            }
+        };
+
+        let defaults = Keybindings::default();
+        for context in BindingContext::ALL {
+            for (action, keys) in defaults.context(context) {
+                binds.context_mut(context).entry(*action).or_insert_with(|| keys.clone());
+            }
+        }
+        for keys in binds.all_bindings() {
+            for name in keys.iter().filter(|name| key_from_name(name).is_none()) {
+                warn!("{} is not a known key name, it will never be pressed.", name);
+            }
+        }
+        binds
+    }
+
+    fn context(&self, context: BindingContext) -> &ContextBindings {
+        match context {
+            BindingContext::Game => &self.game,
+            BindingContext::Activity => &self.activity,
+            BindingContext::Inventory => &self.inventory,
+            BindingContext::ItemMenu => &self.item_menu,
+            BindingContext::Menu => &self.menu,
+            BindingContext::SaveMenu => &self.save_menu,
+            BindingContext::Debug => &self.debug,
+        }
+    }
+
+    fn context_mut(&mut self, context: BindingContext) -> &mut ContextBindings {
+        match context {
+            BindingContext::Game => &mut self.game,
+            BindingContext::Activity => &mut self.activity,
+            BindingContext::Inventory => &mut self.inventory,
+            BindingContext::ItemMenu => &mut self.item_menu,
+            BindingContext::Menu => &mut self.menu,
+            BindingContext::SaveMenu => &mut self.save_menu,
+            BindingContext::Debug => &mut self.debug,
+        }
+    }
+
+    fn all_bindings(&self) -> impl Iterator<Item = &Vec<String>> {
+        BindingContext::ALL
+            .iter()
+            .flat_map(|context| self.context(*context).values())
+            .chain(std::iter::once(&self.inventory_slots))
+    }
+
+    /// The action `key` is bound to in the `context`
+    pub fn action(&self, context: BindingContext, key: VKC) -> Option<Action> {
+        let name = key_name(key)?;
+        self.context(context)
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|k| k.eq_ignore_ascii_case(name)))
+            .map(|(action, _)| *action)
+    }
+
+    /// The action the key pressed this frame is bound to in the `context`
+    pub fn pressed(&self, context: BindingContext, ctx: &BTerm) -> Option<Action> {
+        ctx.key.and_then(|key| self.action(context, key))
+    }
+
+    /// The inventory slot `key` selects
+    pub fn inventory_slot(&self, key: VKC) -> Option<usize> {
+        let name = key_name(key)?;
+        self.inventory_slots.iter().position(|k| k.eq_ignore_ascii_case(name))
+    }
+
+    /// The first key bound to the action in the `context`, the one shown in key hints
+    pub fn first_key(&self, context: BindingContext, action: Action) -> Option<&str> {
+        self.context(context).get(&action)?.iter().find(|key| !key.is_empty()).map(String::as_str)
+    }
+
+    /// The first key bound to the action written as a hint such as `<SPACE>`
+    pub fn hint(&self, context: BindingContext, action: Action) -> String {
+        match self.first_key(context, action) {
+            Some(key) => format!("<{}>", key.to_uppercase()),
+            None => "<UNBOUND>".to_string(),
+        }
+    }
+
+    /// Every row shown on the rebind screen in the order they are shown
+    pub fn rows(&self) -> Vec<BindingRow> {
+        let mut rows = vec![];
+        for context in BindingContext::ALL {
+            rows.extend(self.context(context).keys().map(|action| BindingRow::Action(context, *action)));
+            if context == BindingContext::Inventory {
+                rows.extend((0..self.inventory_slots.len()).map(BindingRow::InventorySlot));
+            }
+        }
+        rows
+    }
+
+    /// The names of the keys bound to the `row`
+    pub fn keys_of(&self, row: &BindingRow) -> Vec<String> {
+        match row {
+            BindingRow::Action(context, action) => self.context(*context).get(action).cloned().unwrap_or_default(),
+            BindingRow::InventorySlot(idx) => self.inventory_slots.get(*idx).cloned().into_iter().collect(),
+        }
+    }
+
+    /// Adds `key` to the row, taking it away from anything else in the same context so it stays unambiguous.
+    /// An inventory slot only ever has one key so its old key is replaced.
+    pub fn bind(&mut self, row: &BindingRow, key: VKC) {
+        let name = match key_name(key) {
+            Some(name) => name.to_string(),
+            None => {
+                warn!("{:?} cannot be saved to the keybindings.", key);
+                return;
+            }
+        };
+
+        let context = row.context();
+        for (action, keys) in self.context_mut(context).iter_mut() {
+            if *row != BindingRow::Action(context, *action) {
+                keys.retain(|k| !k.eq_ignore_ascii_case(&name));
+            }
+        }
+        if context == BindingContext::Inventory {
+            for (idx, slot_key) in self.inventory_slots.iter_mut().enumerate() {
+                if *row != BindingRow::InventorySlot(idx) && slot_key.eq_ignore_ascii_case(&name) {
+                    slot_key.clear();
+                }
+            }
+        }
+
+        match row {
+            BindingRow::Action(context, action) => {
+                let keys = self.context_mut(*context).entry(*action).or_default();
+                if !keys.iter().any(|k| k.eq_ignore_ascii_case(&name)) {
+                    keys.push(name);
+                }
+            }
+            BindingRow::InventorySlot(idx) => {
+                if let Some(slot_key) = self.inventory_slots.get_mut(*idx) {
+                    *slot_key = name;
+                }
+            }
+        }
+    }
+
+    /// Removes every key bound to the row
+    pub fn clear(&mut self, row: &BindingRow) {
+        match row {
+            BindingRow::Action(context, action) => {
+                if let Some(keys) = self.context_mut(*context).get_mut(action) {
+                    keys.clear();
+                }
+            }
+            BindingRow::InventorySlot(idx) => {
+                if let Some(slot_key) = self.inventory_slots.get_mut(*idx) {
+                    slot_key.clear();
+                }
+            }
+        }
+    }
+}
+
+pub enum RebindAction {
+    MoveUp,
+    MoveDown,
+    Listen,
+    Bind(VKC),
+    Clear,
+    Back,
+    Waiting,
+}
+
+/// The rebind screen's own keys are fixed so a bad binding can always be undone from inside the game
+pub fn p_input_rebind_menu(ctx: &BTerm, listening: bool) -> RebindAction {
+    match ctx.key {
+        None => RebindAction::Waiting,
+        Some(key) if listening => RebindAction::Bind(key),
+        Some(key) => match key {
+            VKC::Up => RebindAction::MoveUp,
+            VKC::Down => RebindAction::MoveDown,
+            VKC::Return => RebindAction::Listen,
+            VKC::Back | VKC::Delete => RebindAction::Clear,
+            VKC::Escape => RebindAction::Back,
+            _ => RebindAction::Waiting,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn game_row(action: Action) -> BindingRow {
+        BindingRow::Action(BindingContext::Game, action)
+    }
+
+    #[test]
+    fn saved_bindings_load_back_the_same() {
+        let path = env::temp_dir().join("edo_keybindings_round_trip.json");
+        let mut binds = Keybindings::default();
+        binds.bind(&game_row(Action::Pickup), VKC::G);
+        binds.clear(&game_row(Action::Wait));
+        binds.bind(&BindingRow::InventorySlot(0), VKC::Z);
+        binds.save_to(&path);
+
+        let loaded = Keybindings::load_from(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.rows(), binds.rows());
+        for row in binds.rows() {
+            assert_eq!(loaded.keys_of(&row), binds.keys_of(&row), "{:?} changed after loading", row);
+        }
+        assert_eq!(loaded.action(BindingContext::Game, VKC::G), Some(Action::Pickup));
+        assert_eq!(loaded.action(BindingContext::Game, VKC::Space), None);
+        assert_eq!(loaded.inventory_slot(VKC::Z), Some(0));
+    }
+
+    #[test]
+    fn loading_fills_in_missing_and_renamed_actions() {
+        let path = env::temp_dir().join("edo_keybindings_old_file.json");
+        fs::write(&path, r#"{ "activity": { "ActivityAction": ["Enter"] } }"#).unwrap();
+
+        let loaded = Keybindings::load_from(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.action(BindingContext::Activity, VKC::Return), Some(Action::ActivityInput));
+        assert_eq!(loaded.action(BindingContext::Game, VKC::W), Some(Action::MoveUp));
+        assert_eq!(loaded.action(BindingContext::ItemMenu, VKC::Escape), Some(Action::CancelItemMenu));
+    }
+
+    #[test]
+    fn binding_a_taken_key_takes_it_from_the_other_action() {
+        let mut binds = Keybindings::default();
+        binds.bind(&game_row(Action::Pickup), VKC::W);
+
+        assert_eq!(binds.action(BindingContext::Game, VKC::W), Some(Action::Pickup));
+        assert_eq!(binds.keys_of(&game_row(Action::MoveUp)), vec!["Up".to_string()]);
+        assert_eq!(binds.keys_of(&game_row(Action::Pickup)), vec!["P".to_string(), "W".to_string()]);
+        // the same key can still mean something else in another context
+        assert_eq!(binds.action(BindingContext::Menu, VKC::W), Some(Action::MenuUp));
+    }
+
+    #[test]
+    fn inventory_slots_and_actions_do_not_share_keys() {
+        let mut binds = Keybindings::default();
+        binds.bind(&BindingRow::Action(BindingContext::Inventory, Action::SortInventory), VKC::Key1);
+        assert_eq!(binds.inventory_slot(VKC::Key1), None);
+        assert_eq!(binds.action(BindingContext::Inventory, VKC::Key1), Some(Action::SortInventory));
+
+        binds.bind(&BindingRow::InventorySlot(1), VKC::S);
+        assert_eq!(binds.inventory_slot(VKC::S), Some(1));
+        assert_eq!(binds.action(BindingContext::Inventory, VKC::S), None);
+    }
+}
@@ -0,0 +1,80 @@
+//! Conversions between keys and the names they are written as in files
+
+use bracket_lib::terminal::VirtualKeyCode as VKC;
+
+/// Every key that can be written to a file, keys missing from here are ignored when saving
+const KEY_NAMES: &[(VKC, &str)] = &[
+    (VKC::A, "A"),
+    (VKC::B, "B"),
+    (VKC::C, "C"),
+    (VKC::D, "D"),
+    (VKC::E, "E"),
+    (VKC::F, "F"),
+    (VKC::G, "G"),
+    (VKC::H, "H"),
+    (VKC::I, "I"),
+    (VKC::J, "J"),
+    (VKC::K, "K"),
+    (VKC::L, "L"),
+    (VKC::M, "M"),
+    (VKC::N, "N"),
+    (VKC::O, "O"),
+    (VKC::P, "P"),
+    (VKC::Q, "Q"),
+    (VKC::R, "R"),
+    (VKC::S, "S"),
+    (VKC::T, "T"),
+    (VKC::U, "U"),
+    (VKC::V, "V"),
+    (VKC::W, "W"),
+    (VKC::X, "X"),
+    (VKC::Y, "Y"),
+    (VKC::Z, "Z"),
+    (VKC::Key0, "0"),
+    (VKC::Key1, "1"),
+    (VKC::Key2, "2"),
+    (VKC::Key3, "3"),
+    (VKC::Key4, "4"),
+    (VKC::Key5, "5"),
+    (VKC::Key6, "6"),
+    (VKC::Key7, "7"),
+    (VKC::Key8, "8"),
+    (VKC::Key9, "9"),
+    (VKC::Up, "Up"),
+    (VKC::Down, "Down"),
+    (VKC::Left, "Left"),
+    (VKC::Right, "Right"),
+    (VKC::Space, "Space"),
+    (VKC::Return, "Enter"),
+    (VKC::Escape, "Escape"),
+    (VKC::Back, "Backspace"),
+    (VKC::Delete, "Delete"),
+    (VKC::Tab, "Tab"),
+    (VKC::Minus, "Minus"),
+    (VKC::Equals, "Equals"),
+    (VKC::Comma, "Comma"),
+    (VKC::Period, "Period"),
+    (VKC::Slash, "Slash"),
+    (VKC::Grave, "Grave"),
+    (VKC::F1, "F1"),
+    (VKC::F2, "F2"),
+    (VKC::F3, "F3"),
+    (VKC::F4, "F4"),
+    (VKC::F5, "F5"),
+    (VKC::F6, "F6"),
+    (VKC::F7, "F7"),
+    (VKC::F8, "F8"),
+    (VKC::F9, "F9"),
+    (VKC::F10, "F10"),
+    (VKC::F11, "F11"),
+    (VKC::F12, "F12"),
+];
+
+pub fn key_name(key: VKC) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|(k, _)| *k == key).map(|(_, name)| *name)
+}
+
+/// Finds the key with the `name`, ignoring case
+pub fn key_from_name(name: &str) -> Option<VKC> {
+    KEY_NAMES.iter().find(|(_, n)| n.eq_ignore_ascii_case(name)).map(|(k, _)| *k)
+}
@@ -0,0 +1,306 @@
+//! Hand made levels from the LDtk world. A level's ground layer becomes the `Map` and everything placed in its
+//! entity layer is spawned by name. Walking off the edge of a level or onto a doorway changes to another level,
+//! only `LevelPersistent` entities such as the player and their bag are carried over.
+
+use std::collections::HashSet;
+
+use bracket_lib::terminal::Point;
+use log::{debug, warn};
+use serde_json::Value;
+use specs::{
+    saveload::{MarkedBuilder, SimpleMarker},
+    Builder, Entity, Join, World, WorldExt,
+};
+
+use crate::{
+    components::{Blocking, Doorway, LevelPersistent, Position, Water},
+    data_read::{
+        game_data,
+        ldtk::{tileset_font, LdtkEntity, LdtkLevel, LdtkProject, LEVELS_FILE},
+        prelude::{build_being, build_world_obj, LEVEL_DB},
+        DataError,
+    },
+    game_init::PlayerEntity,
+    indexing::SpatialIndex,
+    items::{ItemSpawner, SpawnType},
+    map::{Map, MapRes},
+    saveload::SerializeMe,
+    AppState,
+};
+
+const GROUND_LAYER: &str = "Ground";
+const VALUES_LAYER: &str = "Values";
+const ENTITIES_LAYER: &str = "Entities";
+/// Marks where the player starts when a level is entered without coming from another level
+const PLAYER_SPAWN: &str = "Player";
+/// Has a `level` string field and a `destination` point field in the cells of that level
+const DOORWAY: &str = "Doorway";
+
+/// A freshly built level, its entities are already in the ecs
+pub struct LoadedLevel {
+    pub map: Map,
+    pub player_spawn: Option<Position>,
+}
+
+/// Builds the map of a level and spawns everything placed in it.
+/// Fails when the level does not exist or something placed in it is not defined in the raws, everything is
+/// checked before the first entity is spawned.
+pub fn load_level(ecs: &mut World, level_name: &str) -> Result<LoadedLevel, DataError> {
+    let project = LEVEL_DB.lock().unwrap();
+    let level = match project.level(level_name) {
+        Some(level) => level,
+        None => return Err(DataError::NotFound { file: LEVELS_FILE, entry: level_name.to_string() }),
+    };
+    let ground = match level.layer(GROUND_LAYER) {
+        Some(ground) => ground,
+        None => return Err(DataError::bad_entry(LEVELS_FILE, level_name, "level has no Ground layer")),
+    };
+    let grid = ground.grid_size;
+
+    let origin = ((level.world_x / grid) as usize, (level.world_y / grid) as usize);
+    let mut map = Map::new(ground.width, ground.height, origin);
+    map.level_name = Some(level_name.to_string());
+    map.tile_atlas_index = match ground_font(&project, level) {
+        Some(font) => font,
+        None => return Err(DataError::bad_entry(LEVELS_FILE, level_name, "ground tileset is not a game font")),
+    };
+    let mut doorways = vec![];
+    for placed in placed_entities(level) {
+        match placed.identifier.as_str() {
+            PLAYER_SPAWN => {}
+            DOORWAY => doorways.push(doorway_from(&project, level, placed)?),
+            _ => check_placed(LEVELS_FILE, level_name, placed)?,
+        }
+    }
+
+    stamp_ground(ecs, &project, level, &mut map, (0, 0));
+    let mut player_spawn = None;
+    let mut doorways = doorways.into_iter();
+    for placed in placed_entities(level) {
+        let pos = Position::new(placed.grid[0] as usize, placed.grid[1] as usize);
+        match placed.identifier.as_str() {
+            PLAYER_SPAWN => player_spawn = Some(pos),
+            DOORWAY => {
+                if let Some(doorway) = doorways.next() {
+                    ecs.create_entity().with(pos).with(doorway).marked::<SimpleMarker<SerializeMe>>().build();
+                }
+            }
+            _ => spawn_placed(ecs, LEVELS_FILE, level_name, placed, pos)?,
+        }
+    }
+    debug!("Level {} loaded", level_name);
+
+    Ok(LoadedLevel { map, player_spawn })
+}
+
+/// Copies the ground tiles and the blocked and water cells of `level` onto `map`, shifted by `offset` cells.
+/// Anything that lands outside of the map is left out.
+pub(crate) fn stamp_ground(
+    ecs: &mut World,
+    project: &LdtkProject,
+    level: &LdtkLevel,
+    map: &mut Map,
+    offset: (usize, usize),
+) {
+    if let Some(ground) = level.layer(GROUND_LAYER) {
+        let grid = ground.grid_size;
+        for tile in ground.grid_tiles.iter().chain(ground.auto_layer_tiles.iter()) {
+            let (x, y) = (offset.0 + (tile.px[0] / grid) as usize, offset.1 + (tile.px[1] / grid) as usize);
+            if x < map.width && y < map.height {
+                let idx = map.xy_to_idx(x, y);
+                map.tiles[idx].atlas_idx = tile.t;
+                map.tiles[idx].font = None;
+            }
+        }
+    }
+
+    let values = match level.layer(VALUES_LAYER) {
+        Some(values) => values,
+        None => return,
+    };
+    for (cell, value) in values.int_grid_csv.iter().enumerate() {
+        let (x, y) = (offset.0 + cell % values.width, offset.1 + cell / values.width);
+        if x >= map.width || y >= map.height {
+            continue;
+        }
+        let idx = map.xy_to_idx(x, y);
+        match project.int_grid_name(values, *value) {
+            Some("blocked") => {
+                map.tiles[idx].is_blocked = true;
+                ecs.create_entity()
+                    .with(Position::new(x, y))
+                    .with(Blocking {})
+                    .marked::<SimpleMarker<SerializeMe>>()
+                    .build();
+            }
+            Some("water") => {
+                map.tiles[idx].name = "Water".to_string();
+                ecs.create_entity()
+                    .with(Water {})
+                    .with(Position::new(x, y))
+                    .with(Blocking {})
+                    .marked::<SimpleMarker<SerializeMe>>()
+                    .build();
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn placed_entities(level: &LdtkLevel) -> impl Iterator<Item = &LdtkEntity> {
+    level.layer(ENTITIES_LAYER).into_iter().flat_map(|layer| layer.entity_instances.iter())
+}
+
+/// The font of a level's ground tileset, `None` when the tileset is not loaded as a font
+pub(crate) fn ground_font(project: &LdtkProject, level: &LdtkLevel) -> Option<usize> {
+    let tileset = project.tileset(level.layer(GROUND_LAYER)?.tileset_def_uid?)?;
+    tileset_font(tileset.rel_path.as_deref()?)
+}
+
+/// Fails when an LDtk entity is not named after a being, world object or item
+fn check_placed(file: &'static str, level_name: &str, placed: &LdtkEntity) -> Result<(), DataError> {
+    let name = placed.identifier.replace('_', " ");
+    let edb = game_data();
+    if edb.beings.get_by_name(&name).is_none()
+        && edb.world_objs.get_by_name(&name).is_none()
+        && edb.items.get_by_name(&name).is_none()
+    {
+        let reason = format!("{} is not a being, world object or item", name);
+        return Err(DataError::bad_entry(file, level_name, reason));
+    }
+    Ok(())
+}
+
+/// Spawns the being, world object or item an LDtk entity is named after.
+/// LDtk identifiers can't have spaces so they are written with underscores instead.
+pub(crate) fn spawn_placed(
+    ecs: &mut World,
+    file: &'static str,
+    level_name: &str,
+    placed: &LdtkEntity,
+    pos: Position,
+) -> Result<(), DataError> {
+    let name = placed.identifier.replace('_', " ");
+    let edb = game_data();
+    if edb.beings.get_by_name(&name).is_some() {
+        build_being(&name, pos, ecs)?;
+    } else if edb.world_objs.get_by_name(&name).is_some() {
+        build_world_obj(&name, pos, ecs)?;
+    } else if edb.items.get_by_name(&name).is_some() {
+        ecs.write_resource::<ItemSpawner>().request_named(&name, SpawnType::OnGround(pos))?;
+    } else {
+        let reason = format!("{} is not a being, world object or item", name);
+        return Err(DataError::bad_entry(file, level_name, reason));
+    }
+    Ok(())
+}
+
+fn doorway_from(project: &LdtkProject, level: &LdtkLevel, placed: &LdtkEntity) -> Result<Doorway, DataError> {
+    let bad_doorway = |reason: &str| DataError::bad_entry(LEVELS_FILE, &level.identifier, reason);
+
+    let level_name = match placed.field("level") {
+        Some(Value::String(name)) => name.clone(),
+        _ => return Err(bad_doorway("doorway has no level")),
+    };
+    let to_level =
+        project.level(&level_name).ok_or_else(|| bad_doorway("doorway leads to a level that does not exist"))?;
+    let cell = |axis: &str| placed.field("destination").and_then(|point| point.get(axis)).and_then(Value::as_i64);
+    let (cx, cy) = match (cell("cx"), cell("cy")) {
+        (Some(cx), Some(cy)) => (cx, cy),
+        _ => return Err(bad_doorway("doorway has no destination")),
+    };
+
+    let (origin_x, origin_y) = level_origin(to_level);
+    Ok(Doorway { level_name, destination: ((origin_x + cx) as usize, (origin_y + cy) as usize).into() })
+}
+
+/// The cell position of a level's top left corner in the world
+fn level_origin(level: &LdtkLevel) -> (i64, i64) {
+    let grid = level_grid_size(level);
+    (level.world_x / grid, level.world_y / grid)
+}
+
+fn level_grid_size(level: &LdtkLevel) -> i64 {
+    level.layer(GROUND_LAYER).map_or(8, |ground| ground.grid_size)
+}
+
+/// The width and height of a level in cells
+pub(crate) fn level_size(level: &LdtkLevel) -> (usize, usize) {
+    let grid = level_grid_size(level);
+    ((level.px_wid / grid) as usize, (level.px_hei / grid) as usize)
+}
+
+/// The level change for the player trying to step off the edge of the map to `target`.
+/// `None` when the map is not an LDtk level or no level is on the other side.
+pub fn edge_transition(map: &Map, target: Point) -> Option<AppState> {
+    map.level_name.as_ref()?;
+    let world_x = map.chunk_coords.x as i64 + target.x as i64;
+    let world_y = map.chunk_coords.y as i64 + target.y as i64;
+
+    let project = LEVEL_DB.lock().unwrap();
+    let next_level = project.levels.iter().find(|level| {
+        let grid = level_grid_size(level);
+        let (origin_x, origin_y) = level_origin(level);
+        (origin_x..origin_x + level.px_wid / grid).contains(&world_x)
+            && (origin_y..origin_y + level.px_hei / grid).contains(&world_y)
+    })?;
+
+    Some(AppState::MapChange {
+        level_name: next_level.identifier.clone(),
+        player_world_pos: Position::new(world_x as usize, world_y as usize),
+    })
+}
+
+/// The level change for the player standing on a doorway
+pub fn doorway_transition(ecs: &World) -> Option<AppState> {
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    let positions = ecs.read_storage::<Position>();
+    let doorways = ecs.read_storage::<Doorway>();
+    let player_pos = positions.get(player_entity.0)?;
+
+    (&doorways, &positions).join().find(|(_, pos)| *pos == player_pos).map(|(doorway, _)| AppState::MapChange {
+        level_name: doorway.level_name.clone(),
+        player_world_pos: Position::new(doorway.destination.x, doorway.destination.y),
+    })
+}
+
+/// Replaces the current level with `level_name` and puts the player at `player_world_pos` inside of it.
+/// The new level is loaded before the old one is cleaned up so a failed load leaves the player where they are.
+pub fn change_level(ecs: &mut World, level_name: &str, player_world_pos: &Position) -> Result<(), DataError> {
+    let old_entities = level_entities(ecs);
+    let LoadedLevel { map, .. } = match load_level(ecs, level_name) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            // anything the level spawned before failing is removed again
+            let spawned: HashSet<Entity> = level_entities(ecs).difference(&old_entities).copied().collect();
+            delete_level_entities(ecs, &spawned);
+            return Err(e);
+        }
+    };
+    delete_level_entities(ecs, &old_entities);
+
+    let local_pos = Position::new(
+        player_world_pos.x.saturating_sub(map.chunk_coords.x).min(map.width.saturating_sub(1)),
+        player_world_pos.y.saturating_sub(map.chunk_coords.y).min(map.height.saturating_sub(1)),
+    );
+    ecs.insert(MapRes(map));
+    ecs.write_resource::<SpatialIndex>().request_rebuild();
+
+    let player_entity = ecs.read_resource::<PlayerEntity>().0;
+    let _ = ecs.write_storage::<Position>().insert(player_entity, local_pos);
+    Ok(())
+}
+
+/// Every entity that is not `LevelPersistent`
+fn level_entities(ecs: &World) -> HashSet<Entity> {
+    let entities = ecs.entities();
+    let persistents = ecs.read_storage::<LevelPersistent>();
+    (&entities, !&persistents).join().map(|(entity, ())| entity).collect()
+}
+
+fn delete_level_entities(ecs: &mut World, doomed: &HashSet<Entity>) {
+    let doomed: Vec<Entity> = doomed.iter().copied().collect();
+    if let Err(e) = ecs.delete_entities(&doomed) {
+        warn!("Level was not fully cleaned up: {:?}", e);
+    }
+}
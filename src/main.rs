@@ -1,64 +1,77 @@
-use crate::being::BeingID;
 use crate::colors::initialize_printer_palette;
 use crate::frame_animation::AnimationRenderer;
 use crate::game_init::set_level_font;
 use crate::logger::create_logger;
-use crate::map::MapRes;
-use crate::saveload::{SerializationHelper, SerializeMe};
 use crate::ui::draw_ui;
 use crate::ui::message_log::MessageLog;
+use std::env;
 use std::mem::discriminant;
 use std::process::exit;
 use std::time::Duration;
 
 use audio::play_sound_effect;
-use being::{GoalFindEntities, GoalMoveToEntities, HandleMoveActions, RandomMonsterMovementSystem};
 use bracket_lib::geometry::Point;
 use bracket_lib::prelude::VirtualKeyCode;
 use bracket_lib::terminal::{main_loop, render_draw_buffer, BError, BTerm, BTermBuilder, GameState};
-use combat::{AttackActionHandler, HealActionHandler};
+use chunks::stream_chunks;
 use config::ConfigMaster;
-use crafting::HandleCraftingSystem;
+use data_read::prelude::NOISE_DB;
 use debug::{debug_info, debug_input};
 use draw_sprites::{draw_sprite_layers, update_fancy_positions};
-use droptables::DeathLootDrop;
-use equipment::EquipActionHandler;
-use fov::UpdateViewsheds;
+use dungeon_gen::{change_depth, stairs_transition};
 use frame_animation::{AnimationPlay, UpdateAnimationTimers};
 use game_init::{
-    initialize_new_game_world, p_input_new_game_menu, InputWorldConfig, NewGameMenuAction, NewGameMenuSelection,
+    initialize_ecs, initialize_new_game_world, p_input_new_game_menu, InputWorldConfig, NewGameMenuAction,
+    NewGameMenuSelection,
 };
-use items::{ConsumeHandler, ItemPickupHandler, ItemSpawnerSystem, ZeroQtyItemCleanup};
+use hot_reload::{reload_raws, RawsWatcher};
+use items::ItemSpawnerSystem;
+use keybindings::{p_input_rebind_menu, RebindAction};
+use levels::{change_level, doorway_transition};
 use log::{debug, error, info, warn};
-use map_gen::WorldConfig;
-use mining::{DamageSystem, RemoveDeadTiles, TileDestructionSystem};
+use map_gen::{WorldConfig, WorldPreview};
+use replay::{InputSource, Replay};
 use saveload::{cleanup_game, load_game, save_game, SaveAction};
-use saveload_menu::{get_save_games, p_input_load_game_menu, GameSaves, LoadMenuAction, LoadedWorld};
+use saveload_menu::{get_save_games, p_input_load_game_menu, GameSaves, LoadMenuAction};
 use settings::{handle_setting_selected, SettingsAction, SettingsSelection};
 use specs::prelude::*;
+use world_export::export_world;
 
 mod audio;
 mod camera;
 mod char_c;
+mod chunks;
 mod colors;
 mod combat;
 mod config;
 mod data_read;
+mod daylight;
 mod debug;
 mod draw_sprites;
 mod droptables;
+mod dungeon_gen;
 mod equipment;
+mod events;
 mod fov;
 mod frame_animation;
 mod game_init;
+mod hot_reload;
 mod indexing;
 mod inventory;
+mod keybindings;
+mod keys;
+mod levels;
 mod logger;
+mod replay;
 mod saveload;
 mod saveload_menu;
 mod settings;
+#[cfg(test)]
+mod simulation;
 mod storage_utils;
+mod systems;
 mod ui;
+mod world_export;
 use inventory::{handle_one_item_actions, handle_two_item_actions, p_input_inventory, InventoryResponse};
 mod being;
 mod items;
@@ -66,46 +79,24 @@ mod map_gen;
 mod mining;
 mod noise;
 mod player;
+mod rng;
 mod stats;
 mod tile_animation;
-mod z_order;
-use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
-use tile_animation::{TileAnimationCleanUpSystem, TileAnimationUpdater};
 mod time;
+mod z_order;
 use player::{
-    check_player_finished, p_input_activity, p_input_game, p_input_main_menu, p_input_save_game, p_input_settings,
-    MenuAction, MenuSelection, PlayerResponse,
+    check_player_finished, p_input_activity, p_input_data_error, p_input_game, p_input_main_menu, p_input_save_game,
+    p_input_settings, MenuAction, MenuSelection, PlayerResponse,
 };
-mod map;
-use map::Map;
 mod components;
+mod map;
 use components::Position;
 mod crafting;
 mod fishing;
-use fishing::{
-    CatchFishSystem, CreateFishingBubbles, FishingMinigameCheck, FishingMinigameUpdate, PollFishingTiles,
-    SetupFishingActions, WaitingForFishSystem,
-};
-use indexing::{IndexBlockedTiles, IndexBreakableTiles, IndexFishableTiles, IndexItemTiles, IndexReset};
-use tile_animation::TileAnimationSpawner;
+use systems::{run_eof_systems, run_presentation_systems, GameDispatchers};
 use time::delta_time_update;
 
-use crate::components::{
-    AttackBonus, Consumable, ConsumeAction, CraftAction, EntityStats, EquipAction, Equipable, EquipmentSlots, Equipped,
-    FishingMinigame, GameAction, GlyphFlash, HealAction, InBag, LevelPersistent, SizeFlexor, Viewshed,
-};
-use crate::{
-    components::{
-        AttackAction, Blocking, BreakAction, Breakable, DeleteCondition, FinishedActivity, FishAction, FishOnTheLine,
-        Fishable, GoalMoverAI, Grass, HealthStats, Interactor, Item, MoveAction, Name, PickupAction, RandomWalkerAI,
-        Renderable, SelectedInventoryItem, SufferDamage, Transform, WaitingForFish, Water,
-    },
-    data_read::initialize_game_databases,
-    items::ItemSpawner,
-    player::Player,
-    tile_animation::TileAnimationBuilder,
-    time::DeltaTime,
-};
+use crate::data_read::{initialize_game_databases, validate_raws};
 
 // Size of the terminal window
 pub const DISPLAY_WIDTH: usize = 40;
@@ -115,99 +106,9 @@ pub const DISPLAY_HEIGHT: usize = 30;
 pub struct State {
     ecs: World,
     cfg: ConfigMaster,
-}
-
-impl State {
-    fn run_response_systems(&mut self) {
-        let mut randomwalker = RandomMonsterMovementSystem;
-        randomwalker.run_now(&self.ecs);
-        let mut find_goals = GoalFindEntities;
-        find_goals.run_now(&self.ecs);
-        let mut goalmover = GoalMoveToEntities;
-        goalmover.run_now(&self.ecs);
-        let mut handle_moves = HandleMoveActions;
-        handle_moves.run_now(&self.ecs);
-        let mut handle_attack_actions = AttackActionHandler;
-        handle_attack_actions.run_now(&self.ecs);
-
-        let mut update_fishing_tiles = CreateFishingBubbles;
-        update_fishing_tiles.run_now(&self.ecs);
-    }
-
-    fn run_activity_bound_systems(&mut self) {
-        // Fishing Minigame Systems ====================>
-        let mut waiting_for_fish = WaitingForFishSystem;
-        waiting_for_fish.run_now(&self.ecs);
-        let mut fish_mini_update = FishingMinigameUpdate;
-        fish_mini_update.run_now(&self.ecs);
-        let mut fish_mini_check = FishingMinigameCheck;
-        fish_mini_check.run_now(&self.ecs);
-        let mut catch_fish = CatchFishSystem;
-        catch_fish.run_now(&self.ecs);
-    }
-
-    fn run_ingame_systems(&mut self) {
-        // Indexing Systems ===============================>
-        let mut index_reset = IndexReset;
-        index_reset.run_now(&self.ecs);
-        let mut index_blocking = IndexBlockedTiles;
-        index_blocking.run_now(&self.ecs);
-        let mut index_breaking = IndexBreakableTiles;
-        index_breaking.run_now(&self.ecs);
-        let mut index_fishing = IndexFishableTiles;
-        index_fishing.run_now(&self.ecs);
-        let mut index_items = IndexItemTiles;
-        index_items.run_now(&self.ecs);
-
-        // Fishing Systems ==================================>
-        let mut setup_fishing_actions = SetupFishingActions;
-        setup_fishing_actions.run_now(&self.ecs);
-        let mut waiting_for_fish = WaitingForFishSystem;
-        waiting_for_fish.run_now(&self.ecs);
-        let mut catch_fish = CatchFishSystem;
-        catch_fish.run_now(&self.ecs);
-        let mut poll_fishing_tiles = PollFishingTiles;
-        poll_fishing_tiles.run_now(&self.ecs);
-
-        // Action Systems =================================>
-        let mut heal_handler = HealActionHandler;
-        heal_handler.run_now(&self.ecs);
-        let mut destruction_sys = TileDestructionSystem;
-        destruction_sys.run_now(&self.ecs);
-        let mut damage_sys = DamageSystem;
-        damage_sys.run_now(&self.ecs);
-        let mut item_pickup_handler = ItemPickupHandler;
-        item_pickup_handler.run_now(&self.ecs);
-
-        // Misc Systems ==================================>
-        let mut death_loot_spawn = DeathLootDrop;
-        death_loot_spawn.run_now(&self.ecs);
-        let mut viewshed_update = UpdateViewsheds;
-        viewshed_update.run_now(&self.ecs);
-
-        // Request Based Systems ================================>
-        let mut item_spawner = ItemSpawnerSystem;
-        item_spawner.run_now(&self.ecs);
-
-        // Animation Systems =========================================>
-        let mut tile_anim_spawner = TileAnimationSpawner;
-        tile_anim_spawner.run_now(&self.ecs);
-        let mut tile_anim_updater = TileAnimationUpdater;
-        tile_anim_updater.run_now(&self.ecs);
-        let mut tile_anim_cleanup_system = TileAnimationCleanUpSystem;
-        tile_anim_cleanup_system.run_now(&self.ecs);
-
-        // Cleanup Systems =======================================>
-        let mut zero_qty_item_cleanup = ZeroQtyItemCleanup;
-        zero_qty_item_cleanup.run_now(&self.ecs);
-        let mut remove_dead_tiles = RemoveDeadTiles;
-        remove_dead_tiles.run_now(&self.ecs);
-    }
-
-    /// Systems that need to be ran after most other systems are finished EOF - end of frame
-    fn run_eof_systems(&mut self) {
-        self.ecs.write_storage::<FinishedActivity>().clear();
-    }
+    dispatchers: GameDispatchers,
+    input: InputSource,
+    raws_watcher: Option<RawsWatcher>,
 }
 
 /// Defines the app's state for the game
@@ -215,15 +116,18 @@ impl State {
 pub enum AppState {
     MainMenu { hovering: MenuSelection },
     SettingsMenu { hovering: SettingsSelection },
+    KeybindingsMenu { hovering: usize, listening: bool },
     NewGameInitialize { hovering: NewGameMenuSelection, world_cfg: InputWorldConfig, form_errors: Vec<String> },
     NewGameStart { world_cfg: WorldConfig },
     LoadGameMenu { hovering: usize },
     LoadGameStart { file_name: String },
     MapChange { level_name: String, player_world_pos: Position },
+    DepthChange { dungeon: String, entrance: Position, depth: u32 },
     InGame,
     ActivityBound { response_delay: Duration },
     PlayerInInventory,
     SaveGame,
+    DataErrorScreen { error: String },
     PreRun { next_state: Box<AppState> },
 }
 
@@ -269,6 +173,9 @@ impl AppState {
 
 impl GameState for State {
     fn tick(&mut self, ctx: &mut BTerm) {
+        let turn = self.ecs.read_resource::<TurnCounter>().0;
+        self.input.begin_frame(ctx, turn);
+
         let mut frame_state: FrameState;
         {
             // this is in a new scope because we need to mutate self (the ecs) later in the fn
@@ -279,16 +186,22 @@ impl GameState for State {
         match frame_state.current.clone() {
             AppState::NewGameStart { world_cfg } => {
                 info!("Game startup occured");
-                initialize_new_game_world(&mut self.ecs, &world_cfg);
-                set_level_font(&self.ecs, ctx);
+                self.input.world_started(world_cfg.seed);
+                if let Err(e) = initialize_new_game_world(&mut self.ecs, &world_cfg) {
+                    error!("Game startup failed: {}", e);
+                    frame_state.change_to(AppState::DataErrorScreen { error: e.to_string() });
+                } else {
+                    set_level_font(&self.ecs, ctx);
 
-                let mut item_spawner = ItemSpawnerSystem;
-                item_spawner.run_now(&self.ecs);
-                frame_state.change_to(AppState::InGame);
-                info!("Game startup finished. Switching to ingame state");
+                    let mut item_spawner = ItemSpawnerSystem;
+                    item_spawner.run_now(&self.ecs);
+                    frame_state.change_to(AppState::InGame);
+                    info!("Game startup finished. Switching to ingame state");
+                }
             }
             AppState::LoadGameStart { file_name } => {
                 debug!("Attempting to load save file, {}", file_name);
+                self.input.save_loaded(&file_name);
                 load_game(&mut self.ecs, file_name);
                 set_level_font(&self.ecs, ctx);
 
@@ -296,79 +209,90 @@ impl GameState for State {
                 debug!("Loaded save file");
             }
             AppState::InGame => {
-                match p_input_game(&mut self.ecs, ctx) {
+                match p_input_game(&mut self.ecs, ctx, &self.cfg.keybindings) {
                     PlayerResponse::Waiting => {
                         // Player hasn't done anything yet so only run essential systems
                     }
                     PlayerResponse::TurnAdvance => {
                         turn_counter_incr(&mut self.ecs);
-                        self.run_response_systems();
+                        self.dispatchers.response.dispatch(&self.ecs);
+                        stream_chunks(&mut self.ecs);
+                        if let Some(level_change) = doorway_transition(&self.ecs) {
+                            frame_state.change_to(level_change);
+                        } else if let Some(depth_change) = stairs_transition(&self.ecs) {
+                            frame_state.change_to(depth_change);
+                        }
                     }
                     PlayerResponse::StateChange(delta_state) => {
                         frame_state.change_to(delta_state);
                     }
                 }
-                self.run_ingame_systems();
-                self.run_eof_systems();
+                self.dispatchers.ingame.dispatch(&self.ecs);
+                run_eof_systems(&mut self.ecs);
             }
             AppState::PlayerInInventory => {
-                match p_input_inventory(&mut self.ecs, ctx, &mut self.cfg.inventory) {
+                match p_input_inventory(&mut self.ecs, ctx, &mut self.cfg.inventory, &self.cfg.keybindings) {
                     InventoryResponse::Waiting => {
                         // Player hasn't done anything yet so only run essential systems
+                        self.dispatchers.inventory.dispatch(&self.ecs);
                     }
                     InventoryResponse::ActionReady => {
                         handle_one_item_actions(&mut self.ecs);
-                        let mut equip_system = EquipActionHandler;
-                        equip_system.run_now(&self.ecs);
-                        let mut consume_handler = ConsumeHandler;
-                        consume_handler.run_now(&self.ecs);
-                        let mut heal_handler = HealActionHandler;
-                        heal_handler.run_now(&self.ecs);
-                        let mut damage_sys = DamageSystem;
-                        damage_sys.run_now(&self.ecs);
+                        self.dispatchers.item_action.dispatch(&self.ecs);
                     }
                     InventoryResponse::SecondItemSelected { second_item } => {
                         handle_two_item_actions(&mut self.ecs, &second_item);
-                        let mut craft_system = HandleCraftingSystem;
-                        craft_system.run_now(&self.ecs);
+                        self.dispatchers.crafting.dispatch(&self.ecs);
                     }
                     InventoryResponse::StateChange(delta_state) => {
                         frame_state.change_to(delta_state);
+                        self.dispatchers.inventory.dispatch(&self.ecs);
                     }
                 }
-                let mut item_spawner = ItemSpawnerSystem;
-                item_spawner.run_now(&self.ecs);
-                let mut zero_qty_item_cleanup = ZeroQtyItemCleanup;
-                zero_qty_item_cleanup.run_now(&self.ecs);
             }
             AppState::ActivityBound { response_delay } => {
-                p_input_activity(&mut self.ecs, ctx);
-                self.run_activity_bound_systems();
+                p_input_activity(&mut self.ecs, ctx, &self.cfg.keybindings);
+                self.dispatchers.activity_bound.dispatch(&self.ecs);
 
                 frame_state.change_to(if check_player_finished(&mut self.ecs) {
                     turn_counter_incr(&mut self.ecs);
-                    self.run_response_systems();
+                    self.dispatchers.response.dispatch(&self.ecs);
                     AppState::InGame
                 } else {
                     AppState::ActivityBound { response_delay }
                 });
             }
-            AppState::MapChange { level_name, player_world_pos: _ } => {
+            AppState::MapChange { level_name, player_world_pos } => {
                 debug!("going to {}", level_name);
-                unreachable!("TODO: donot switch to mapchange state");
-                // cleanup_old_map(&mut self.ecs);
-                // let new_level = create_map(&mut self.ecs, &level_name);
-                // self.ecs.insert(MapRes(new_level));
-
-                // set_level_font(&self.ecs, ctx);
-                // move_player_to(&player_world_pos, &mut self.ecs);
-                // frame_state.change_to(AppState::InGame);
+                if let Err(e) = change_level(&mut self.ecs, &level_name, &player_world_pos) {
+                    error!("Level change to {} failed: {}", level_name, e);
+                    frame_state.change_to(AppState::DataErrorScreen { error: e.to_string() });
+                } else {
+                    set_level_font(&self.ecs, ctx);
+
+                    let mut item_spawner = ItemSpawnerSystem;
+                    item_spawner.run_now(&self.ecs);
+                    frame_state.change_to(AppState::InGame);
+                }
+            }
+            AppState::DepthChange { dungeon, entrance, depth } => {
+                debug!("going to depth {} of {}", depth, dungeon);
+                if let Err(e) = change_depth(&mut self.ecs, &dungeon, &entrance, depth) {
+                    error!("Dungeon change to {} depth {} failed: {}", dungeon, depth, e);
+                    frame_state.change_to(AppState::DataErrorScreen { error: e.to_string() });
+                } else {
+                    set_level_font(&self.ecs, ctx);
+
+                    let mut item_spawner = ItemSpawnerSystem;
+                    item_spawner.run_now(&self.ecs);
+                    frame_state.change_to(AppState::InGame);
+                }
             }
             AppState::MainMenu { hovering } => {
                 let mut timer_update = UpdateAnimationTimers;
                 timer_update.run_now(&self.ecs);
 
-                match p_input_main_menu(ctx, &hovering) {
+                match p_input_main_menu(ctx, &hovering, &self.cfg.keybindings) {
                     MenuAction::Selected(selected) => {
                         frame_state.change_to(match selected {
                             MenuSelection::NewGame => AppState::new_game_init(),
@@ -380,6 +304,7 @@ impl GameState for State {
                             }
                             MenuSelection::QuitGame => {
                                 info!("Quitting the game from the main menu.");
+                                self.input.flush();
                                 exit(1);
                             }
                         });
@@ -395,28 +320,12 @@ impl GameState for State {
                 }
             }
             AppState::NewGameInitialize { hovering, world_cfg: mut cfg_input, form_errors } => {
-                match p_input_new_game_menu(ctx) {
+                self.ecs.write_resource::<WorldPreview>().refresh(&cfg_input);
+                match p_input_new_game_menu(ctx, &self.cfg.keybindings) {
                     NewGameMenuAction::Text(ch) => {
-                        match hovering {
-                            NewGameMenuSelection::WorldName => {
-                                cfg_input.world_name.push(ch);
-                            }
-                            NewGameMenuSelection::Width => {
-                                if ch.is_ascii_digit() {
-                                    cfg_input.width.push(ch);
-                                }
-                            }
-                            NewGameMenuSelection::Height => {
-                                if ch.is_ascii_digit() {
-                                    cfg_input.height.push(ch);
-                                }
-                            }
-                            NewGameMenuSelection::Seed => {
-                                cfg_input.seed.push(ch);
-                            }
-                            NewGameMenuSelection::Finalize => {}
-                        }
-                        if !matches!(hovering, NewGameMenuSelection::Finalize) {
+                        let takes_char = ch.is_ascii_digit() || !hovering.is_numeric();
+                        if let Some(field) = cfg_input.text_field(&hovering).filter(|_| takes_char) {
+                            field.push(ch);
                             frame_state.change_to(AppState::NewGameInitialize {
                                 hovering,
                                 world_cfg: cfg_input,
@@ -425,30 +334,8 @@ impl GameState for State {
                         }
                     }
                     NewGameMenuAction::DelChar => {
-                        match hovering {
-                            NewGameMenuSelection::WorldName => {
-                                if !cfg_input.world_name.is_empty() {
-                                    cfg_input.world_name.remove(cfg_input.world_name.len() - 1);
-                                }
-                            }
-                            NewGameMenuSelection::Width => {
-                                if !cfg_input.width.is_empty() {
-                                    cfg_input.width.remove(cfg_input.width.len() - 1);
-                                }
-                            }
-                            NewGameMenuSelection::Height => {
-                                if !cfg_input.height.is_empty() {
-                                    cfg_input.height.remove(cfg_input.height.len() - 1);
-                                }
-                            }
-                            NewGameMenuSelection::Seed => {
-                                if !cfg_input.seed.is_empty() {
-                                    cfg_input.seed.remove(cfg_input.seed.len() - 1);
-                                }
-                            }
-                            NewGameMenuSelection::Finalize => {}
-                        }
-                        if !matches!(hovering, NewGameMenuSelection::Finalize) {
+                        if let Some(field) = cfg_input.text_field(&hovering) {
+                            field.pop();
                             frame_state.change_to(AppState::NewGameInitialize {
                                 hovering,
                                 world_cfg: cfg_input,
@@ -467,6 +354,14 @@ impl GameState for State {
                                 });
                             }
                         },
+                        NewGameMenuSelection::NoisePreset => {
+                            cfg_input.noise_preset = NOISE_DB.lock().unwrap().preset_after(&cfg_input.noise_preset);
+                            frame_state.change_to(AppState::NewGameInitialize {
+                                hovering,
+                                world_cfg: cfg_input,
+                                form_errors,
+                            });
+                        }
                         _ => {}
                     },
                     NewGameMenuAction::Up => frame_state.change_to(AppState::NewGameInitialize {
@@ -487,7 +382,7 @@ impl GameState for State {
             }
             AppState::LoadGameMenu { hovering } => {
                 let save_games = self.ecs.read_resource::<GameSaves>();
-                match p_input_load_game_menu(ctx) {
+                match p_input_load_game_menu(ctx, &self.cfg.keybindings) {
                     LoadMenuAction::MoveDown => {
                         let new_pos = if hovering == save_games.saves.len() - 1 { 0 } else { hovering + 1 };
                         frame_state.change_to(AppState::LoadGameMenu { hovering: new_pos });
@@ -506,9 +401,14 @@ impl GameState for State {
                     LoadMenuAction::Waiting => {}
                 }
             }
-            AppState::SettingsMenu { hovering } => match p_input_settings(ctx) {
+            AppState::SettingsMenu { hovering } => match p_input_settings(ctx, &hovering, &self.cfg.keybindings) {
                 SettingsAction::Selected => {
-                    handle_setting_selected(&hovering, &mut self.cfg.general, ctx);
+                    if let Some(next_state) = handle_setting_selected(&hovering, &mut self.cfg.general, ctx) {
+                        frame_state.change_to(next_state);
+                    }
+                }
+                SettingsAction::Hovering(new_selection) => {
+                    frame_state.change_to(AppState::SettingsMenu { hovering: new_selection });
                 }
                 SettingsAction::ReturnToMainMenu => {
                     self.cfg.general.save();
@@ -518,9 +418,34 @@ impl GameState for State {
                 }
                 SettingsAction::Waiting => {}
             },
-            AppState::SaveGame => match p_input_save_game(ctx) {
+            AppState::KeybindingsMenu { hovering, listening } => {
+                let rows = self.cfg.keybindings.rows();
+                match p_input_rebind_menu(ctx, listening) {
+                    RebindAction::MoveUp => frame_state.change_to(AppState::KeybindingsMenu {
+                        hovering: if hovering == 0 { rows.len() - 1 } else { hovering - 1 },
+                        listening,
+                    }),
+                    RebindAction::MoveDown => frame_state
+                        .change_to(AppState::KeybindingsMenu { hovering: (hovering + 1) % rows.len(), listening }),
+                    RebindAction::Listen => {
+                        frame_state.change_to(AppState::KeybindingsMenu { hovering, listening: true });
+                    }
+                    RebindAction::Bind(key) => {
+                        self.cfg.keybindings.bind(&rows[hovering], key);
+                        frame_state.change_to(AppState::KeybindingsMenu { hovering, listening: false });
+                    }
+                    RebindAction::Clear => self.cfg.keybindings.clear(&rows[hovering]),
+                    RebindAction::Back => {
+                        self.cfg.keybindings.save();
+                        frame_state.change_to(AppState::SettingsMenu { hovering: SettingsSelection::Keybindings });
+                    }
+                    RebindAction::Waiting => {}
+                }
+            }
+            AppState::SaveGame => match p_input_save_game(ctx, &self.cfg.keybindings) {
                 SaveAction::Save => {
                     save_game(&mut self.ecs);
+                    self.input.flush();
                     cleanup_game(&mut self.ecs);
                     frame_state.change_to(AppState::PreRun {
                         next_state: Box::new(AppState::MainMenu { hovering: MenuSelection::NewGame }),
@@ -530,6 +455,7 @@ impl GameState for State {
                     frame_state.change_to(AppState::InGame);
                 }
                 SaveAction::QuitWithoutSaving => {
+                    self.input.flush();
                     cleanup_game(&mut self.ecs);
                     frame_state.change_to(AppState::PreRun {
                         next_state: Box::new(AppState::MainMenu { hovering: MenuSelection::NewGame }),
@@ -537,11 +463,20 @@ impl GameState for State {
                 }
                 SaveAction::QuickSave => {
                     save_game(&mut self.ecs);
+                    self.input.flush();
                     frame_state.change_to(AppState::PreRun { next_state: Box::new(AppState::InGame) });
                     self.ecs.write_resource::<MessageLog>().log("Saved game.");
                 }
                 SaveAction::Waiting => {}
             },
+            AppState::DataErrorScreen { .. } => {
+                if p_input_data_error(ctx, &self.cfg.keybindings) {
+                    cleanup_game(&mut self.ecs);
+                    frame_state.change_to(AppState::PreRun {
+                        next_state: Box::new(AppState::MainMenu { hovering: MenuSelection::NewGame }),
+                    });
+                }
+            }
             AppState::PreRun { next_state } => {
                 run_pre_state_systems(next_state.as_ref(), &mut self.ecs);
                 frame_state.change_to(*next_state);
@@ -549,6 +484,7 @@ impl GameState for State {
         }
 
         // Essential Systems run every frame
+        run_presentation_systems(&mut self.dispatchers, &mut self.ecs);
         update_fancy_positions(&self.ecs);
         delta_time_update(&mut self.ecs, ctx);
         self.ecs.maintain();
@@ -562,10 +498,14 @@ impl GameState for State {
             AppState::InGame
             | AppState::PlayerInInventory
             | AppState::NewGameStart { .. }
-            | AppState::MapChange { .. } => {
+            | AppState::MapChange { .. }
+            | AppState::DepthChange { .. } => {
                 draw_sprite_layers(&self.ecs);
                 debug_info(ctx, &self.ecs, &self.cfg.inventory);
-                debug_input(ctx, &self.ecs);
+                debug_input(ctx, &mut self.ecs, &self.cfg.keybindings);
+                if self.raws_watcher.as_mut().is_some_and(|watcher| watcher.poll()) {
+                    reload_raws(&mut self.ecs);
+                }
             }
             _ => {}
         }
@@ -644,15 +584,12 @@ fn run_pre_state_systems(state: &AppState, ecs: &mut World) {
 
 /// These systems are ran every time a state is changed
 fn run_exit_state_systems(state: &AppState, ecs: &mut World) {
-    match state {
-        AppState::MainMenu { .. } => {
-            ecs.write_resource::<AnimationRenderer>().clear();
-        }
-        _ => {}
+    if let AppState::MainMenu { .. } = state {
+        ecs.write_resource::<AnimationRenderer>().clear();
     }
 }
 
-struct TurnCounter(pub usize);
+pub struct TurnCounter(pub usize);
 impl TurnCounter {
     pub fn zero() -> Self {
         Self(0)
@@ -664,12 +601,59 @@ fn turn_counter_incr(ecs: &mut World) {
     tc.0 += 1;
 }
 
+/// The path following `--replay` in the launch arguments
+fn replay_arg() -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+    args.iter().position(|arg| arg == "--replay").and_then(|idx| args.get(idx + 1)).cloned()
+}
+
+/// Checks the raws for problems instead of starting the game when `--validate-raws` is in the launch arguments
+fn validate_raws_arg() -> bool {
+    env::args().any(|arg| arg == "--validate-raws")
+}
+
+/// The LDtk level a new game starts in given by `--level <name>`, a world is generated when it is missing
+fn start_level_arg() -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+    args.iter().position(|arg| arg == "--level").and_then(|idx| args.get(idx + 1)).cloned()
+}
+
+/// The world to write out instead of starting the game, given by `--export-world <name>`. The other settings of
+/// the new game menu can follow it as `--width`, `--height`, `--sea-level`, `--seed`, `--resource-density` and
+/// `--noise-preset`, each with its value after it
+fn export_world_arg() -> Option<InputWorldConfig> {
+    let args: Vec<String> = env::args().collect();
+    let value = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|idx| args.get(idx + 1)).cloned();
+
+    let mut iwc = InputWorldConfig { world_name: value("--export-world")?, ..Default::default() };
+    let settings = [
+        ("--width", &mut iwc.width),
+        ("--height", &mut iwc.height),
+        ("--sea-level", &mut iwc.sea_level),
+        ("--seed", &mut iwc.seed),
+        ("--resource-density", &mut iwc.resource_density),
+        ("--noise-preset", &mut iwc.noise_preset),
+    ];
+    for (flag, setting) in settings {
+        if let Some(given) = value(flag) {
+            *setting = given;
+        }
+    }
+    Some(iwc)
+}
+
+/// Reloads the raws whenever they change on disk when `--watch-raws` is in the launch arguments
+fn watch_raws_arg() -> bool {
+    env::args().any(|arg| arg == "--watch-raws")
+}
+
 // CL - Console layer, represents the indices for each console
-pub const CL_EFFECTS2: usize = 3; // Used for special effect tiles on top of other effects
-pub const CL_EFFECTS: usize = 2; // Used for special effect tiles
-pub const CL_TEXT: usize = 4; // Used for UI
+pub const CL_EFFECTS2: usize = 4; // Used for special effect tiles on top of other effects
+pub const CL_EFFECTS: usize = 3; // Used for special effect tiles
+pub const CL_TEXT: usize = 5; // Used for UI
 pub const CL_WORLD: usize = 0; // Used for terrain tiles
-pub const CL_INTERACTABLES: usize = 1; // Used for the few or so moving items/entities on screen
+pub const CL_WORLD_OVERLAY: usize = 1; // Used for terrain tiles from the map's second terrain font
+pub const CL_INTERACTABLES: usize = 2; // Used for the few or so moving items/entities on screen
 
 // FONTS - the indices are based on the order the fonts are added in the context init
 pub const FONT_EFFECTS: usize = 0;
@@ -702,9 +686,69 @@ fn main() -> BError {
     error!("Errors will be tracked in this file.");
     warn!("Warnings will be tracked in this file.");
 
-    initialize_game_databases();
+    if validate_raws_arg() {
+        let problems = validate_raws();
+        for problem in problems.iter() {
+            eprintln!("{}", problem);
+        }
+        if problems.is_empty() {
+            println!("No problems found in the raws.");
+            exit(0);
+        }
+        eprintln!("{} problems found in the raws.", problems.len());
+        exit(1);
+    }
+
+    if let Some(iwc) = export_world_arg() {
+        if let Err(e) = initialize_game_databases() {
+            eprintln!("Game data could not be loaded: {}", e);
+            exit(1);
+        }
+        let world_cfg = match WorldConfig::from_settings(&iwc) {
+            Ok(world_cfg) => world_cfg,
+            Err(errors) => {
+                for error in errors.iter() {
+                    eprintln!("{}", error);
+                }
+                exit(1);
+            }
+        };
+        match export_world(&world_cfg) {
+            Ok(paths) => {
+                for path in paths.iter() {
+                    println!("Wrote {}", path.display());
+                }
+                exit(0);
+            }
+            Err(e) => {
+                eprintln!("{} could not be exported: {}", world_cfg.world_name, e);
+                exit(1);
+            }
+        }
+    }
+
+    let start_state = match initialize_game_databases() {
+        Ok(()) => {
+            let world_cfg = WorldConfig { start_level: start_level_arg(), ..Default::default() };
+            AppState::PreRun { next_state: Box::new(AppState::NewGameStart { world_cfg }) }
+        }
+        Err(e) => {
+            error!("Game data could not be loaded: {}", e);
+            AppState::DataErrorScreen { error: e.to_string() }
+        }
+    };
 
     let cfg = ConfigMaster::load();
+    let input = match replay_arg() {
+        Some(path) => match Replay::load(&path) {
+            Ok(replay) => InputSource::replaying(replay),
+            Err(e) => {
+                eprintln!("Replay cannot be played: {}", e);
+                exit(1);
+            }
+        },
+        None => InputSource::recording(),
+    };
     let interactable_font = match cfg.general.sprite_mode {
         settings::SpriteMode::Outline => "interactable_tiles_outline.png",
         settings::SpriteMode::Blocked => "interactable_tiles.png",
@@ -723,6 +767,7 @@ fn main() -> BError {
         .with_font("terrain_town_forest.png", 8u32, 8u32)
         .with_dimensions(160, 120)
         .with_simple_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, "terrain_forest.png")
+        .with_simple_console_no_bg(DISPLAY_WIDTH, DISPLAY_HEIGHT, "terrain_town_forest.png")
         .with_fancy_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, interactable_font)
         .with_fancy_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, "effects_tiles.png")
         .with_fancy_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, "effects_tiles.png")
@@ -733,73 +778,10 @@ fn main() -> BError {
     initialize_printer_palette();
 
     // Setup ECS
-    let mut world = World::new();
-
-    // Component Registration, the ECS needs to have every type of component registered
-    world.register::<Position>();
-    world.register::<Player>();
-    world.register::<BeingID>();
-    world.register::<Renderable>();
-    world.register::<Blocking>();
-    world.register::<HealthStats>();
-    world.register::<BreakAction>();
-    world.register::<AttackAction>();
-    world.register::<PickupAction>();
-    world.register::<FishAction>();
-    world.register::<Breakable>();
-    world.register::<SufferDamage>();
-    world.register::<Fishable>();
-    world.register::<WaitingForFish>();
-    world.register::<FishOnTheLine>();
-    world.register::<DeleteCondition>();
-    world.register::<FinishedActivity>();
-    world.register::<Name>();
-    world.register::<RandomWalkerAI>();
-    world.register::<GoalMoverAI>();
-    world.register::<Item>();
-    world.register::<Water>();
-    world.register::<Grass>();
-    world.register::<InBag>();
-    world.register::<MoveAction>();
-    world.register::<CraftAction>();
-    world.register::<EquipAction>();
-    world.register::<Transform>();
-    world.register::<Interactor>();
-    world.register::<EntityStats>();
-    world.register::<SelectedInventoryItem>();
-    world.register::<EquipmentSlots>();
-    world.register::<Equipable>();
-    world.register::<Equipped>();
-    world.register::<AttackBonus>();
-    world.register::<Consumable>();
-    world.register::<ConsumeAction>();
-    world.register::<HealAction>();
-    world.register::<GameAction>();
-    world.register::<FishingMinigame>();
-    world.register::<LevelPersistent>();
-    world.register::<SizeFlexor>();
-    world.register::<GlyphFlash>();
-    world.register::<Viewshed>();
-
-    // Still components but used for saving the data in the ecs
-    world.register::<SimpleMarker<SerializeMe>>();
-    world.register::<SerializationHelper>();
-    world.insert(SimpleMarkerAllocator::<SerializeMe>::new());
-
-    // Resource Initialization, the ECS needs a basic definition of every resource that will be in the game
-    world.insert(AppState::PreRun {
-        next_state: Box::new(AppState::NewGameStart { world_cfg: WorldConfig::default() }),
-    });
-    world.insert(DeltaTime(Duration::ZERO));
-    world.insert(TileAnimationBuilder::new());
-    world.insert(AnimationRenderer::new());
-    world.insert(ItemSpawner::new());
-    world.insert(MessageLog::new());
-    world.insert(MapRes(Map::empty(0, 0)));
-    world.insert(TurnCounter::zero());
-    world.insert(GameSaves::default());
-    world.insert(LoadedWorld::default());
-
-    let game_state = State { ecs: world, cfg };
+    let mut world = initialize_ecs();
+    world.insert(start_state);
+
+    let raws_watcher = if watch_raws_arg() { Some(RawsWatcher::new()) } else { None };
+    let game_state = State { ecs: world, cfg, dispatchers: GameDispatchers::new(), input, raws_watcher };
     main_loop(context, game_state)
 }
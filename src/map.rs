@@ -1,15 +1,18 @@
 use crate::{
     camera::get_camera_bounds,
     char_c::{CH_SOLID, CH_WATER},
+    colors::to_rgb,
     components::{HealthStats, Position},
+    daylight::WorldLight,
     droptables::Drops,
+    dungeon_gen::DungeonFloor,
+    map_gen::WorldConfig,
+    FONT_TERRAIN_FOREST, FONT_TERRAIN_TOWN_FOREST,
 };
 use bracket_lib::terminal::{ColorPair, DrawBatch, Point, BLACK};
 use serde::{Deserialize, Serialize};
 use specs::{Entity, World};
 
-pub const WHITE: (u8, u8, u8) = (255, 255, 255);
-
 #[derive(Clone, Serialize, Deserialize, Default)]
 pub struct Map {
     pub tiles: Vec<WorldTile>,
@@ -17,6 +20,18 @@ pub struct Map {
     pub height: usize,
     pub chunk_coords: WorldCoords,
     pub tile_atlas_index: usize,
+    /// The LDtk level this map was built from, `None` for generated worlds
+    #[serde(default)]
+    pub level_name: Option<String>,
+    /// The generated world this map is a window of chunks onto, `None` for LDtk levels
+    #[serde(default)]
+    pub world: Option<WorldConfig>,
+    /// The dungeon floor this map is, `None` above ground
+    #[serde(default)]
+    pub dungeon: Option<DungeonFloor>,
+    /// Tiles the player has seen at some point
+    #[serde(default)]
+    pub revealed: RevealedTiles,
 
     #[serde(skip_serializing, skip_deserializing)]
     pub tile_entities: Vec<Vec<TileEntity>>,
@@ -25,6 +40,41 @@ pub struct Map {
 #[derive(Default, Clone)]
 pub struct MapRes(pub Map);
 
+/// One bit for each tile of a map telling if the player has seen it. Tiles past the end have never been seen
+/// so an empty set fits a map of any size.
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct RevealedTiles {
+    bits: Vec<u64>,
+}
+
+impl RevealedTiles {
+    pub fn get(&self, idx: usize) -> bool {
+        self.bits.get(idx / 64).is_some_and(|word| word & (1 << (idx % 64)) != 0)
+    }
+
+    pub fn set(&mut self, idx: usize, revealed: bool) {
+        let word = idx / 64;
+        if word >= self.bits.len() {
+            if !revealed {
+                return;
+            }
+            self.bits.resize(word + 1, 0);
+        }
+        if revealed {
+            self.bits[word] |= 1 << (idx % 64);
+        } else {
+            self.bits[word] &= !(1 << (idx % 64));
+        }
+    }
+
+    /// Copies `len` tiles starting at `from` in `other` to the tiles starting at `to`
+    pub fn copy_from(&mut self, to: usize, other: &RevealedTiles, from: usize, len: usize) {
+        for offset in 0..len {
+            self.set(to + offset, other.get(from + offset));
+        }
+    }
+}
+
 /// This is used over position when (de)serialization is needed.
 /// Position cannot impl Deserialize because it needs to impl ConvertSaveload
 #[derive(Deserialize, Serialize, Clone, Default)]
@@ -46,11 +96,40 @@ pub struct WorldTile {
     pub transparent: bool,
     pub is_blocked: bool,
     pub height: u8,
+    /// Drawn with the map's font when `None`
+    #[serde(default)]
+    pub font: Option<usize>,
 }
 
 impl WorldTile {
     pub fn water(height: u8) -> WorldTile {
-        Self { name: "Water".to_string(), atlas_idx: CH_WATER as usize, transparent: true, height, is_blocked: false }
+        Self::water_named("Water", height)
+    }
+
+    /// Water carved by a river, its entities are also tagged `River`
+    pub fn river(height: u8) -> WorldTile {
+        Self::water_named("River", height)
+    }
+
+    /// Water filling a basin a river ran into
+    pub fn lake(height: u8) -> WorldTile {
+        Self::water_named("Lake", height)
+    }
+
+    fn water_named(name: &str, height: u8) -> WorldTile {
+        Self {
+            name: name.to_string(),
+            atlas_idx: CH_WATER as usize,
+            transparent: true,
+            height,
+            is_blocked: false,
+            font: None,
+        }
+    }
+
+    /// Sea, river or lake
+    pub fn is_water(&self) -> bool {
+        ["Water", "River", "Lake"].contains(&self.name.as_str())
     }
 
     pub fn grass() -> Self {
@@ -60,6 +139,7 @@ impl WorldTile {
             transparent: true,
             height: 0,
             is_blocked: false,
+            font: None,
         }
     }
 }
@@ -85,6 +165,8 @@ pub struct WorldObject {
     pub foreground: Option<(u8, u8, u8)>,
     pub loot: Option<Drops>,
     pub impact_sound: String,
+    /// Tiles lit up around the object when it isn't day
+    pub light_radius: Option<usize>,
 }
 
 /// Defines the type of entity existing in a tile for quick lookup and action handling
@@ -111,11 +193,40 @@ impl TileEntity {
     pub fn is_blocker(&self) -> bool {
         matches!(self, TileEntity::Blocking(_))
     }
+
+    pub fn entity(&self) -> Entity {
+        match self {
+            TileEntity::Fishable(e) | TileEntity::Breakable(e) | TileEntity::Item(e) | TileEntity::Blocking(e) => *e,
+        }
+    }
+
+    /// The order the contents of a tile are kept in, blockers first and then by entity id within each kind.
+    /// This decides which entity is found first on a tile so the index always sorts by it.
+    fn index_order(&self) -> (u8, u32) {
+        let kind = match self {
+            TileEntity::Blocking(_) => 0,
+            TileEntity::Breakable(_) => 1,
+            TileEntity::Fishable(_) => 2,
+            TileEntity::Item(_) => 3,
+        };
+        (kind, self.entity().id())
+    }
 }
 
 impl Map {
     pub fn empty(width: usize, height: usize) -> Self {
-        Self { tiles: vec![], tile_entities: vec![], width, height, chunk_coords: (0, 0).into(), tile_atlas_index: 0 }
+        Self {
+            tiles: vec![],
+            tile_entities: vec![],
+            width,
+            height,
+            chunk_coords: (0, 0).into(),
+            tile_atlas_index: 0,
+            level_name: None,
+            world: None,
+            dungeon: None,
+            revealed: RevealedTiles::default(),
+        }
     }
 
     // Makes empty map of a size
@@ -127,6 +238,10 @@ impl Map {
             height,
             chunk_coords: world_coords.into(),
             tile_atlas_index: 0,
+            level_name: None,
+            world: None,
+            dungeon: None,
+            revealed: RevealedTiles::default(),
         }
     }
 
@@ -134,6 +249,20 @@ impl Map {
         xy_to_idx_given_width(x, y, self.width)
     }
 
+    /// The other terrain font, tiles of a generated world whose biome uses it are drawn on the overlay layer
+    pub fn overlay_font(&self) -> usize {
+        if self.tile_atlas_index == FONT_TERRAIN_TOWN_FOREST {
+            FONT_TERRAIN_FOREST
+        } else {
+            FONT_TERRAIN_TOWN_FOREST
+        }
+    }
+
+    /// The generated world the map is part of, a dungeon floor is part of the world its entrance is in
+    pub fn world_config(&self) -> Option<&WorldConfig> {
+        self.world.as_ref().or(self.dungeon.as_ref().map(|floor| &floor.world))
+    }
+
     #[allow(unused)]
     pub fn chunk_x(&self) -> usize {
         self.chunk_coords.x
@@ -161,6 +290,29 @@ impl Map {
         })
     }
 
+    /// Adds to the contents of the tile at `idx` in index order
+    pub fn place_tile_entity(&mut self, idx: usize, tile_entity: TileEntity) {
+        if let Some(contents) = self.tile_entities.get_mut(idx) {
+            let at = contents.partition_point(|te| te.index_order() < tile_entity.index_order());
+            contents.insert(at, tile_entity);
+        }
+    }
+
+    /// Returns false when the tile did not contain `tile_entity`
+    pub fn remove_tile_entity(&mut self, idx: usize, tile_entity: &TileEntity) -> bool {
+        let contents = match self.tile_entities.get_mut(idx) {
+            Some(contents) => contents,
+            None => return false,
+        };
+        match contents.iter().position(|te| te == tile_entity) {
+            Some(at) => {
+                contents.remove(at);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Checks a position on the map to see if it is blocked
     pub fn is_blocked(&self, pos: &Position) -> bool {
         self.tile_entities[self.xy_to_idx(pos.x, pos.y)].iter().any(|te| te.is_blocker())
@@ -206,24 +358,33 @@ pub fn is_goal(curr_pos: &Position, dest_pos: &Position) -> bool {
     curr_pos == dest_pos
 }
 
-/// Renders the current map resource to the current console layer
-pub fn render_map(ecs: &World, batch: &mut DrawBatch) {
+/// Renders the current map resource to the current console layer, tinted by the light over each tile
+/// Tiles drawn with a font other than the map's go on `overlay`, its console uses `Map::overlay_font`
+pub fn render_map(ecs: &World, batch: &mut DrawBatch, overlay: &mut DrawBatch) {
     let map = ecs.fetch::<MapRes>();
+    let light = ecs.fetch::<WorldLight>();
 
     let bounding_box = get_camera_bounds(ecs);
 
     for x in bounding_box.x1..bounding_box.x2 {
         for y in bounding_box.y1..bounding_box.y2 {
+            let screen_x = x - bounding_box.x1;
+            let screen_y = y - bounding_box.y1;
+            let tint = ColorPair::new(to_rgb(light.tint_at(Point::new(x, y))), BLACK);
+
             let atlas_index = if x < map.0.width as i32 && y < map.0.height as i32 && x >= 0 && y >= 0 {
-                map.0.tiles[map.0.xy_to_idx(x as usize, y as usize)].atlas_idx
+                let tile = &map.0.tiles[map.0.xy_to_idx(x as usize, y as usize)];
+                if tile.font.is_some_and(|font| font != map.0.tile_atlas_index) {
+                    overlay.set(Point::new(screen_x, screen_y), tint, tile.atlas_idx);
+                    xy_to_idx_given_width(0, 2, 16)
+                } else {
+                    tile.atlas_idx
+                }
             } else {
                 xy_to_idx_given_width(0, 2, 16)
             };
 
-            let screen_x = x - bounding_box.x1;
-            let screen_y = y - bounding_box.y1;
-
-            batch.set(Point::new(screen_x, screen_y), ColorPair::new(WHITE, BLACK), atlas_index);
+            batch.set(Point::new(screen_x, screen_y), tint, atlas_index);
         }
     }
 }
@@ -0,0 +1,259 @@
+//! Rivers and lakes. A river starts on high ground and runs downhill over the height noise until it reaches the sea,
+//! when it gets stuck in a basin it fills it as a lake instead. Rivers are traced in world tiles from the noise alone
+//! so one that crosses several chunks is carved the same way into each of them, whichever is generated first.
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use bracket_lib::{random::RandomNumberGenerator, terminal::Rect};
+use specs::{
+    saveload::{MarkedBuilder, SimpleMarker},
+    Builder, World, WorldExt,
+};
+
+use crate::{
+    chunks::{world_chunks, ChunkPos, CHUNK_SIZE},
+    components::{Blocking, Position, River, Water},
+    data_read::{noise::HEIGHT_NOISE, prelude::NOISE_DB},
+    map::{Map, WorldTile},
+    noise::Noise,
+    saveload::SerializeMe,
+};
+
+use super::WorldConfig;
+
+/// Spots tried for a river source in every chunk
+const SOURCE_TRIES: usize = 2;
+/// Lowest height noise value a river can start from
+const SOURCE_HEIGHT: f32 = 0.7;
+/// Rivers that have not reached the sea or a basin by now just end
+const MAX_RIVER_LENGTH: usize = 192;
+/// Chunks this far away can have a river reaching into the chunk being generated
+const RIVER_REACH: usize = MAX_RIVER_LENGTH / CHUNK_SIZE + 1;
+const LAKE_RADIUS: i64 = 5;
+/// How far above the bottom of a basin its lake fills
+const LAKE_DEPTH: f32 = 0.03;
+const VALLEY_RADIUS: i64 = 3;
+/// How much the banks of a river are lowered right next to it
+const VALLEY_DEPTH: u8 = 12;
+
+const DIRECTIONS: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// The water a river leaves behind, in world tiles
+#[derive(Default)]
+struct Watercourse {
+    river: Vec<(i64, i64)>,
+    lake: Vec<(i64, i64)>,
+}
+
+/// Carves every river and lake that reaches into `area` and spawns their water.
+/// Valleys only lower the height of the tiles around a river, the sea fill afterwards decides what floods.
+pub fn carve_rivers(map: &mut Map, ecs: &mut World, wc: &WorldConfig, area: &Rect) {
+    let watercourses = {
+        let noise_db = NOISE_DB.lock().unwrap();
+        let height_noise = match noise_db.get_by_name(HEIGHT_NOISE) {
+            Some(height_noise) => height_noise,
+            None => return,
+        };
+        let sea_level = wc.sea_level as f32 / 255.0;
+
+        let (chunks_x, chunks_y) = world_chunks(wc);
+        let bounds = (chunks_x * CHUNK_SIZE, chunks_y * CHUNK_SIZE);
+        let chunk = ChunkPos::containing(map.chunk_coords.x + area.x1 as usize, map.chunk_coords.y + area.y1 as usize);
+
+        let mut watercourses = vec![];
+        for cy in chunk.y.saturating_sub(RIVER_REACH)..(chunk.y + RIVER_REACH + 1).min(chunks_y) {
+            for cx in chunk.x.saturating_sub(RIVER_REACH)..(chunk.x + RIVER_REACH + 1).min(chunks_x) {
+                for source in river_sources(wc, height_noise, ChunkPos { x: cx, y: cy }) {
+                    let height_at = |(x, y): (i64, i64)| height_noise.get_normal_2d(x as f32, y as f32);
+                    watercourses.push(trace_river(source, sea_level, bounds, height_at));
+                }
+            }
+        }
+        watercourses
+    };
+
+    let origin = (map.chunk_coords.x as i64, map.chunk_coords.y as i64);
+    let local = |(x, y): (i64, i64)| {
+        let (x, y) = (x - origin.0, y - origin.1);
+        (x >= area.x1 as i64 && x < area.x2 as i64 && y >= area.y1 as i64 && y < area.y2 as i64)
+            .then_some((x as usize, y as usize))
+    };
+
+    // where valleys overlap a tile is only lowered by the deepest of them so crossing rivers don't dig pits
+    let mut valleys: HashMap<(usize, usize), u8> = HashMap::new();
+    for course in watercourses.iter() {
+        for &(rx, ry) in course.river.iter() {
+            for dy in -VALLEY_RADIUS..=VALLEY_RADIUS {
+                for dx in -VALLEY_RADIUS..=VALLEY_RADIUS {
+                    if let Some(pos) = local((rx + dx, ry + dy)) {
+                        let distance = dx.abs().max(dy.abs());
+                        let depth = (VALLEY_DEPTH as i64 * (VALLEY_RADIUS + 1 - distance) / (VALLEY_RADIUS + 1)) as u8;
+                        let deepest = valleys.entry(pos).or_default();
+                        *deepest = (*deepest).max(depth);
+                    }
+                }
+            }
+        }
+    }
+    for ((x, y), depth) in valleys {
+        let idx = map.xy_to_idx(x, y);
+        map.tiles[idx].height = map.tiles[idx].height.saturating_sub(depth);
+    }
+
+    let mut watered = HashSet::new();
+    for course in watercourses.iter() {
+        for (x, y) in course.lake.iter().filter_map(|&pos| local(pos)) {
+            if watered.insert((x, y)) {
+                let height = map.tiles[map.xy_to_idx(x, y)].height;
+                map.set_tile(&WorldTile::lake(height), x, y);
+                ecs.create_entity()
+                    .with(Water {})
+                    .with(Position::new(x, y))
+                    .with(Blocking {})
+                    .marked::<SimpleMarker<SerializeMe>>()
+                    .build();
+            }
+        }
+        for (x, y) in course.river.iter().filter_map(|&pos| local(pos)) {
+            if watered.insert((x, y)) {
+                let height = map.tiles[map.xy_to_idx(x, y)].height;
+                map.set_tile(&WorldTile::river(height), x, y);
+                ecs.create_entity()
+                    .with(Water {})
+                    .with(River {})
+                    .with(Position::new(x, y))
+                    .with(Blocking {})
+                    .marked::<SimpleMarker<SerializeMe>>()
+                    .build();
+            }
+        }
+    }
+}
+
+/// Where the rivers of a chunk start, in world tiles. Seeded from the chunk so every chunk agrees on them.
+fn river_sources(wc: &WorldConfig, height_noise: &Noise, chunk: ChunkPos) -> Vec<(i64, i64)> {
+    let mut hasher = DefaultHasher::new();
+    (wc.seed, chunk.x, chunk.y, "rivers").hash(&mut hasher);
+    let mut rng = RandomNumberGenerator::seeded(hasher.finish());
+
+    (0..SOURCE_TRIES)
+        .map(|_| {
+            let x = chunk.x * CHUNK_SIZE + rng.range(0, CHUNK_SIZE);
+            let y = chunk.y * CHUNK_SIZE + rng.range(0, CHUNK_SIZE);
+            (x as i64, y as i64)
+        })
+        .filter(|&(x, y)| height_noise.get_normal_2d(x as f32, y as f32) >= SOURCE_HEIGHT)
+        .collect()
+}
+
+/// Follows the steepest way down from `source` until the sea, the edge of the world or a basin is reached.
+/// A basin is filled with a lake around its lowest tile.
+fn trace_river(
+    source: (i64, i64),
+    sea_level: f32,
+    bounds: (usize, usize),
+    height_at: impl Fn((i64, i64)) -> f32,
+) -> Watercourse {
+    let in_world = |(x, y): (i64, i64)| x >= 0 && y >= 0 && x < bounds.0 as i64 && y < bounds.1 as i64;
+
+    let mut course = Watercourse::default();
+    let mut current = source;
+    let mut height = height_at(current);
+    for _ in 0..MAX_RIVER_LENGTH {
+        course.river.push(current);
+
+        let lowest = DIRECTIONS
+            .iter()
+            .map(|(dx, dy)| (current.0 + dx, current.1 + dy))
+            .filter(|&pos| in_world(pos))
+            .map(|pos| (pos, height_at(pos)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let (next, next_height) = match lowest {
+            Some(lowest) => lowest,
+            None => break,
+        };
+        if next_height >= height {
+            course.lake = fill_basin(current, height, in_world, &height_at);
+            break;
+        }
+        if next_height < sea_level {
+            break;
+        }
+        current = next;
+        height = next_height;
+    }
+    course
+}
+
+/// The tiles around the bottom of a basin low enough to be under its lake
+fn fill_basin(
+    bottom: (i64, i64),
+    bottom_height: f32,
+    in_world: impl Fn((i64, i64)) -> bool,
+    height_at: impl Fn((i64, i64)) -> f32,
+) -> Vec<(i64, i64)> {
+    let mut lake = vec![];
+    for dy in -LAKE_RADIUS..=LAKE_RADIUS {
+        for dx in -LAKE_RADIUS..=LAKE_RADIUS {
+            let pos = (bottom.0 + dx, bottom.1 + dy);
+            if dx * dx + dy * dy <= LAKE_RADIUS * LAKE_RADIUS
+                && in_world(pos)
+                && height_at(pos) <= bottom_height + LAKE_DEPTH
+            {
+                lake.push(pos);
+            }
+        }
+    }
+    lake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: (usize, usize) = (100, 100);
+
+    #[test]
+    fn river_only_runs_downhill() {
+        let hills = |(x, y): (i64, i64)| 0.5 + 0.25 * (x as f32 * 0.13).sin() + 0.25 * (y as f32 * 0.07).cos();
+        let course = trace_river((20, 20), 0.0, BOUNDS, hills);
+        assert!(course.river.len() > 1);
+        for step in course.river.windows(2) {
+            let (from, to) = (step[0], step[1]);
+            assert_eq!(from.0.abs_diff(to.0) + from.1.abs_diff(to.1), 1, "rivers move one tile at a time");
+            assert!(hills(to) < hills(from), "river went uphill from {:?} to {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn river_stops_at_sea_level() {
+        // a slope that drops by 0.01 a tile going right and crosses the sea level at x = 50
+        let slope = |(x, _): (i64, i64)| 1.0 - x as f32 / 100.0;
+        let course = trace_river((0, 5), 0.5, BOUNDS, slope);
+        assert_eq!(course.river.last(), Some(&(50, 5)));
+        assert!(course.river.iter().all(|&pos| slope(pos) >= 0.5));
+        assert!(course.lake.is_empty());
+    }
+
+    #[test]
+    fn basin_forms_lake() {
+        let bowl = |(x, y): (i64, i64)| 0.1 + ((x - 30).pow(2) + (y - 30).pow(2)) as f32 / 1000.0;
+        let course = trace_river((30, 20), 0.0, BOUNDS, bowl);
+        assert_eq!(course.river.last(), Some(&(30, 30)));
+        assert!(course.lake.contains(&(30, 30)));
+        // the bowl is shallow enough that the whole lake radius is under water
+        assert_eq!(course.lake.len(), 81);
+        assert!(course.lake.iter().all(|&pos| bowl(pos) <= bowl((30, 30)) + LAKE_DEPTH));
+    }
+
+    #[test]
+    fn basin_lake_stays_below_its_walls() {
+        let pit = |(x, y): (i64, i64)| if (x - 30).pow(2) + (y - 30).pow(2) <= 4 { 0.1 } else { 1.0 };
+        let lake = fill_basin((30, 30), 0.1, |_| true, pit);
+        assert_eq!(lake.len(), 13);
+        assert!(lake.iter().all(|&pos| pit(pos) == 0.1));
+    }
+}
@@ -1,35 +1,91 @@
-use std::hash::{DefaultHasher, Hash, Hasher};
+mod hydrology;
+mod prefabs;
+mod preview;
+mod spawn_point;
+mod towns;
 
-use bracket_lib::random::RandomNumberGenerator;
-use log::{error, info};
-use specs::{Builder, World, WorldExt};
+use std::{
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use bracket_lib::{
+    random::RandomNumberGenerator,
+    terminal::{Point, Rect},
+};
+use hydrology::carve_rivers;
+use log::{error, warn};
+use prefabs::place_prefabs;
+use serde::{Deserialize, Serialize};
+use specs::{
+    saveload::{MarkedBuilder, SimpleMarker},
+    Builder, World, WorldExt,
+};
+use towns::place_towns;
 
 use crate::{
+    chunks::{delete_world_chunks, load_world_around, ChunkPos, ChunkWindow, CHUNK_SIZE},
     components::{Blocking, Position, Water},
-    data_read::prelude::{build_world_obj, NOISE_DB},
+    data_read::{
+        biomes::BiomeDatabase,
+        noise::{NoiseDatabase, HEIGHT_NOISE, RESOURCE_NOISE},
+        prelude::{build_being, build_world_obj, BIOME_DB, DUNGEON_DB, NOISE_DB, SPAWN_DB},
+        spawns::Spawn,
+    },
+    dungeon_gen::build_stairs,
     game_init::InputWorldConfig,
     map::{Map, WorldTile},
-    saveload::{save_game_exists, SAVE_EXTENSION},
+    saveload::{save_game_exists, SerializeMe, SAVE_EXTENSION},
     FONT_TERRAIN_FOREST,
 };
 
-#[derive(Clone, PartialEq, Eq)]
+pub use self::{
+    preview::{PreviewCell, WorldPreview, PREVIEW_ACROSS, PREVIEW_DOWN},
+    spawn_point::find_player_spawn,
+};
+
+/// Random open tiles looked at for a dungeon entrance before the chunk goes without
+const ENTRANCE_TRIES: usize = 20;
+/// Furthest a creature of a group is put from the tile the group was rolled on
+const GROUP_RADIUS: usize = 2;
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorldConfig {
     pub world_name: String,
+    /// Size of the world in tiles, always a multiple of `CHUNK_SIZE` so the world is made of whole chunks
     pub width: usize,
     pub height: usize,
     pub sea_level: u8,
     pub seed: u64,
+    /// The LDtk level to start in instead of generating a world
+    pub start_level: Option<String>,
+    /// Where the player's start was looked for around in world tiles, creatures keep their distance from it
+    #[serde(default)]
+    pub player_start: (usize, usize),
+    /// The noise preset the world is generated with, the noises are used as the raws have them when `None`
+    #[serde(default)]
+    pub noise_preset: Option<String>,
+    /// Percent of the usual amount of resources placed
+    #[serde(default = "full_density")]
+    pub resource_density: u16,
+}
+
+fn full_density() -> u16 {
+    100
 }
 
 impl Default for WorldConfig {
     fn default() -> Self {
         Self {
             world_name: "".to_string(),
-            width: 100,
-            height: 100,
+            width: 2 * CHUNK_SIZE,
+            height: 2 * CHUNK_SIZE,
             sea_level: (0.13f32 * 255.0).round() as u8,
             seed: 0,
+            start_level: None,
+            player_start: (0, 0),
+            noise_preset: None,
+            resource_density: full_density(),
         }
     }
 }
@@ -44,6 +100,19 @@ impl WorldConfig {
         if save_game_exists(&format!("{}.{}", iwc.world_name, SAVE_EXTENSION)) {
             errors.push("World name already exists".to_string());
         }
+        match Self::from_settings(iwc) {
+            Ok(wc) if errors.is_empty() => Ok(wc),
+            Ok(_) => Err(errors),
+            Err(mut setting_errors) => {
+                errors.append(&mut setting_errors);
+                Err(errors)
+            }
+        }
+    }
+
+    /// Reads every setting of the form but the world name, which is only checked when the world is made
+    pub fn from_settings(iwc: &InputWorldConfig) -> Result<Self, Vec<String>> {
+        let mut errors = vec![];
 
         let sea_level = match iwc.sea_level.parse::<u8>() {
             Ok(h) => h,
@@ -53,16 +122,16 @@ impl WorldConfig {
             }
         };
         let height = match iwc.height.parse::<usize>() {
-            Ok(h) => h,
-            Err(_) => {
-                errors.push("Invalid height".to_string());
+            Ok(h) if h > 0 && h % CHUNK_SIZE == 0 => h,
+            _ => {
+                errors.push(format!("Invalid height must be a multiple of {}", CHUNK_SIZE));
                 0
             }
         };
         let width = match iwc.width.parse::<usize>() {
-            Ok(w) => w,
-            Err(_) => {
-                errors.push("Invalid width".to_string());
+            Ok(w) if w > 0 && w % CHUNK_SIZE == 0 => w,
+            _ => {
+                errors.push(format!("Invalid width must be a multiple of {}", CHUNK_SIZE));
                 0
             }
         };
@@ -74,100 +143,393 @@ impl WorldConfig {
             hasher.finish()
         };
 
+        let resource_density = match iwc.resource_density.parse::<u16>() {
+            Ok(d) => d,
+            Err(_) => {
+                errors.push("Invalid resource density must be a percent".to_string());
+                0
+            }
+        };
+        let noise_preset = Some(iwc.noise_preset.clone()).filter(|preset| !preset.is_empty());
+
         if !errors.is_empty() {
             return Err(errors);
         }
-        Ok(Self { world_name: iwc.world_name.clone(), width, height, seed, sea_level })
+        Ok(Self {
+            world_name: iwc.world_name.clone(),
+            width,
+            height,
+            seed,
+            sea_level,
+            start_level: None,
+            player_start: (0, 0),
+            noise_preset,
+            resource_density,
+        })
     }
 }
 
-// Generates a map and populates ecs with relavent objects and world things
-pub fn gen_world(ecs: &mut World, wc: &WorldConfig) -> Map {
+/// Generates the chunks of the world around `around`, a position in world tiles. Chunks from an older world with
+/// the same name are thrown away.
+pub fn gen_world(ecs: &mut World, wc: &WorldConfig, around: &Position) -> Map {
+    delete_world_chunks(&wc.world_name);
+    load_world_around(ecs, wc, around)
+}
+
+/// A map covering `window` with none of its chunks generated yet
+pub fn empty_world_map(wc: &WorldConfig, window: &ChunkWindow) -> Map {
+    let mut new_map = Map::new(window.width(), window.height(), window.origin());
+    new_map.tile_atlas_index = FONT_TERRAIN_FOREST;
+    new_map.world = Some(wc.clone());
+    new_map
+}
+
+/// Generates one chunk into the part of `map` it covers and populates ecs with relavent objects and world things.
+/// The noise is sampled in world tiles and the rng is seeded from the chunk so a chunk always comes out the same.
+pub fn gen_chunk(ecs: &mut World, wc: &WorldConfig, map: &mut Map, chunk: ChunkPos) {
     {
         let mut noise_db = NOISE_DB.lock().unwrap();
-        noise_db.reseed(wc.seed);
+        noise_db.configure(wc.seed, wc.noise_preset.as_deref());
     }
-    let mut rng = RandomNumberGenerator::seeded(wc.seed);
+    let mut hasher = DefaultHasher::new();
+    (wc.seed, chunk.x, chunk.y).hash(&mut hasher);
+    let mut rng = RandomNumberGenerator::seeded(hasher.finish());
 
-    let mut new_map = Map::new(wc.width, wc.height, (0, 0));
+    let area = chunk.area_in(map);
+    let biomes = generate_terrain(map, &area);
+    carve_rivers(map, ecs, wc, &area);
+    fill_water_to_level(map, wc.sea_level, ecs, &area);
+    // nothing else is generated on the land prefabs and towns are built on
+    let mut sites = place_prefabs(map, ecs, &mut rng, &area);
+    let town_sites = place_towns(map, ecs, &mut rng, &area, &sites);
+    sites.extend(town_sites);
+    let mut occupied = generate_resources(map, ecs, &mut rng, wc, &biomes, &sites);
+    place_dungeon_entrances(map, ecs, &mut rng, &area, &sites, &mut occupied);
+    spawn_creatures(map, ecs, &mut rng, wc, &biomes, &sites, &occupied);
+}
 
-    new_map.tile_atlas_index = FONT_TERRAIN_FOREST;
-    for x in 0..wc.width {
-        for y in 0..wc.height {
-            new_map.set_tile(&WorldTile::grass(), x, y);
-        }
-    }
+/// The biome picked for every tile of an area, as indices into the biome table
+struct AreaBiomes {
+    area: Rect,
+    biomes: Vec<Option<usize>>,
+}
 
-    // just a random boulder
-    // let _ = build_world_obj("Boulder".to_string(), Position::new(15, 20), ecs);
+impl AreaBiomes {
+    fn at(&self, x: usize, y: usize) -> Option<usize> {
+        let (dx, dy) = (x - self.area.x1 as usize, y - self.area.y1 as usize);
+        self.biomes[dx + dy * self.area.width() as usize]
+    }
 
-    generate_heights(&mut new_map);
-    fill_water_to_level(&mut new_map, wc.sea_level, ecs);
-    generate_resources(&mut new_map, ecs, &mut rng);
+    fn tiles(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.area.y1 as usize..self.area.y2 as usize)
+            .flat_map(move |y| (self.area.x1 as usize..self.area.x2 as usize).map(move |x| (x, y)))
+    }
+}
 
-    new_map
+/// Tiles nothing is spawned on
+fn is_open(map: &Map, x: usize, y: usize) -> bool {
+    let tile = &map.tiles[map.xy_to_idx(x, y)];
+    !tile.is_blocked && !tile.is_water() && tile.name != "Mountain"
 }
 
-fn generate_resources(map: &mut Map, ecs: &mut World, rng: &mut RandomNumberGenerator) {
+/// Nothing is generated inside of `prefab_sites`, returns the tiles a world object was placed on
+fn generate_resources(
+    map: &mut Map,
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    wc: &WorldConfig,
+    biomes: &AreaBiomes,
+    prefab_sites: &[Rect],
+) -> HashSet<(usize, usize)> {
     let noise_db = NOISE_DB.lock().unwrap();
-    let r_noise = noise_db.get_by_name("resources").unwrap();
+    let biome_db = BIOME_DB.lock().unwrap();
+    let mut occupied = HashSet::new();
+    let r_noise = match noise_db.get_by_name(RESOURCE_NOISE) {
+        Some(r_noise) => r_noise,
+        None => return occupied,
+    };
 
-    for x in 0..map.width {
-        for y in 0..map.height {
-            let map_tile = &map.tiles[map.xy_to_idx(x, y)];
-            if ["Mountain", "Water"].contains(&map_tile.name.as_str()) {
+    for (x, y) in biomes.tiles() {
+        if !is_open(map, x, y) || prefab_sites.iter().any(|site| site.point_in_rect(Point::new(x, y))) {
+            continue;
+        }
+
+        let (world_x, world_y) = (map.chunk_coords.x + x, map.chunk_coords.y + y);
+        let resource = match biomes.at(x, y) {
+            Some(biome) => biome_db.biomes[biome].resource_at(r_noise.get_normal_2d(world_x as f32, world_y as f32)),
+            None => r_noise.get_name_of(world_x, world_y),
+        };
+        if let Some((name, weight)) = resource {
+            let check = rng.rand::<u64>() as f32 / u64::MAX as f32;
+            if check > weight * wc.resource_density as f32 / 100.0 {
                 continue;
             }
 
-            if let Some((name, weight)) = r_noise.get_name_of(x, y) {
-                let check = rng.rand::<u64>() as f32 / u64::MAX as f32;
-                info!("{}", check);
-                if check > weight {
-                    info!("skipping tile");
-                    continue;
+            match build_world_obj(name, Position::new(x, y), ecs) {
+                Ok(_) => {
+                    occupied.insert((x, y));
                 }
+                Err(e) => error!("Resources failed to build: {}", e),
+            }
+        }
+    }
+    occupied
+}
 
-                match map_tile.name.as_str() {
-                    "Grass" => {
-                        if name == "Boulder" {
-                            let check = rng.rand::<u64>() as f32 / u64::MAX as f32;
-                            if check > 0.5 {
-                                continue;
-                            }
-                        }
-                    }
-                    _ => {}
+/// Rolls every dungeon for an entrance on an open tile of `area`, the tiles used are added to `occupied`
+fn place_dungeon_entrances(
+    map: &Map,
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    area: &Rect,
+    prefab_sites: &[Rect],
+    occupied: &mut HashSet<(usize, usize)>,
+) {
+    let dungeon_db = DUNGEON_DB.lock().unwrap();
+
+    for dungeon in dungeon_db.dungeons.iter() {
+        let roll = rng.rand::<u64>() as f32 / u64::MAX as f32;
+        if roll >= dungeon.entrance_chance {
+            continue;
+        }
+        let spot = (0..ENTRANCE_TRIES)
+            .map(|_| (rng.range(area.x1, area.x2) as usize, rng.range(area.y1, area.y2) as usize))
+            .find(|&(x, y)| {
+                is_open(map, x, y)
+                    && !occupied.contains(&(x, y))
+                    && !prefab_sites.iter().any(|site| site.point_in_rect(Point::new(x, y)))
+            });
+        if let Some((x, y)) = spot {
+            match build_stairs(ecs, &dungeon.entrance, &dungeon.name, 1, Position::new(x, y)) {
+                Ok(_) => {
+                    occupied.insert((x, y));
                 }
+                Err(e) => error!("{} entrance failed to build: {}", dungeon.name, e),
+            }
+        }
+    }
+}
+
+/// Rolls each open tile against the spawn table, a successful roll puts a group down on open tiles around it.
+/// At most one creature is put on a tile.
+fn spawn_creatures(
+    map: &Map,
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    wc: &WorldConfig,
+    biomes: &AreaBiomes,
+    prefab_sites: &[Rect],
+    occupied: &HashSet<(usize, usize)>,
+) {
+    let biome_db = BIOME_DB.lock().unwrap();
+    let spawn_db = SPAWN_DB.lock().unwrap();
+    let (start_x, start_y) = wc.player_start;
+    let (offset_x, offset_y) = (map.chunk_coords.x, map.chunk_coords.y);
+    let mut taken = occupied.clone();
+    let can_spawn = |taken: &HashSet<(usize, usize)>, x: usize, y: usize| {
+        biomes.area.point_in_rect(Point::new(x, y))
+            && is_open(map, x, y)
+            && !taken.contains(&(x, y))
+            && !prefab_sites.iter().any(|site| site.point_in_rect(Point::new(x, y)))
+    };
+
+    let fits = |spawn: &Spawn, x: usize, y: usize| {
+        let biome = biomes.at(x, y).map(|biome| biome_db.biomes[biome].name.as_str());
+        spawn.fits(biome, &map.tiles[map.xy_to_idx(x, y)].name)
+    };
+
+    for (x, y) in biomes.tiles() {
+        if !can_spawn(&taken, x, y) {
+            continue;
+        }
+        let from_start = (offset_x + x).abs_diff(start_x).max((offset_y + y).abs_diff(start_y));
 
-                if let Err(e) = build_world_obj(name, Position::new(x, y), ecs) {
-                    error!("Resources failed to build: {:?}", e);
+        for spawn in spawn_db.spawns.iter() {
+            if !fits(spawn, x, y) || from_start < spawn.min_player_distance {
+                continue;
+            }
+            let roll = rng.rand::<u64>() as f32 / u64::MAX as f32;
+            if roll >= spawn.density {
+                continue;
+            }
+
+            let size = rng.range(spawn.group[0], spawn.group[1] + 1);
+            let mut placed = 0;
+            for (gx, gy) in group_spots(x, y) {
+                if placed == size {
+                    break;
+                }
+                if !can_spawn(&taken, gx, gy) || !fits(spawn, gx, gy) {
+                    continue;
+                }
+                match build_being(&spawn.name, Position::new(gx, gy), ecs) {
+                    Ok(_) => {
+                        taken.insert((gx, gy));
+                        placed += 1;
+                    }
+                    Err(e) => {
+                        warn!("{} could not spawn: {}", spawn.name, e);
+                        break;
+                    }
                 }
             }
+            break;
         }
     }
 }
 
-fn fill_water_to_level(map: &mut Map, level: u8, ecs: &mut World) {
-    for x in 0..map.width {
-        for y in 0..map.height {
+/// Tiles a group spreads over, nearest to where it started first
+fn group_spots(x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..=GROUP_RADIUS).flat_map(move |r| {
+        let (x1, y1) = (x.saturating_sub(r), y.saturating_sub(r));
+        (y1..=y + r)
+            .flat_map(move |sy| (x1..=x + r).map(move |sx| (sx, sy)))
+            .filter(move |&(sx, sy)| sx.abs_diff(x).max(sy.abs_diff(y)) == r)
+    })
+}
+
+fn fill_water_to_level(map: &mut Map, level: u8, ecs: &mut World, area: &Rect) {
+    for x in area.x1 as usize..area.x2 as usize {
+        for y in area.y1 as usize..area.y2 as usize {
             if let Some(tile) = map.tiles.get(map.xy_to_idx(x, y)) {
-                if tile.height < level {
+                // rivers and lakes already have their water
+                if tile.height < level && !tile.is_water() {
                     map.set_tile(&WorldTile::water(tile.height), x, y);
-                    ecs.create_entity().with(Water {}).with(Position::new(x, y)).with(Blocking {}).build();
+                    ecs.create_entity()
+                        .with(Water {})
+                        .with(Position::new(x, y))
+                        .with(Blocking {})
+                        .marked::<SimpleMarker<SerializeMe>>()
+                        .build();
                 }
             }
         }
     }
 }
 
-fn generate_heights(map: &mut Map) {
+/// Paints the ground of `area` from the biome under each tile.
+/// When there are no biomes the height noise's own tile mapping is used instead.
+fn generate_terrain(map: &mut Map, area: &Rect) -> AreaBiomes {
     let noise_db = NOISE_DB.lock().unwrap();
-    let noise = noise_db.get_by_name("height").unwrap();
+    let biome_db = BIOME_DB.lock().unwrap();
 
-    for x in 0..map.width {
-        for y in 0..map.height {
-            let world_tile = noise.gen_tile(x, y);
+    let mut biomes = AreaBiomes { area: *area, biomes: Vec::with_capacity((area.width() * area.height()) as usize) };
+    for y in area.y1 as usize..area.y2 as usize {
+        for x in area.x1 as usize..area.x2 as usize {
+            let (world_x, world_y) = (map.chunk_coords.x + x, map.chunk_coords.y + y);
+            let (biome, world_tile) = terrain_at(&noise_db, &biome_db, world_x, world_y);
             map.set_tile(&world_tile, x, y);
+            biomes.biomes.push(biome);
+        }
+    }
+    biomes
+}
+
+/// The biome and ground tile at a spot given in world tiles
+fn terrain_at(
+    noise_db: &NoiseDatabase,
+    biome_db: &BiomeDatabase,
+    world_x: usize,
+    world_y: usize,
+) -> (Option<usize>, WorldTile) {
+    // a missing noise leaves every biome in the middle of its range, loading the raws fails without the height
+    let sample =
+        |name: &str| noise_db.get_by_name(name).map_or(0.5, |n| n.get_normal_2d(world_x as f32, world_y as f32));
+    let height = sample(HEIGHT_NOISE);
+
+    let biome = biome_db.biome_at(sample("temperature"), sample("moisture"), height);
+    let world_tile = biome.and_then(|biome| biome_db.biomes[biome].tile_at(height)).unwrap_or_else(|| {
+        noise_db.get_by_name(HEIGHT_NOISE).map(|n| n.gen_tile(world_x, world_y)).unwrap_or_default()
+    });
+    (biome, world_tile)
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Join, WorldExt};
+
+    use super::*;
+    use crate::{
+        components::Name,
+        data_read::spawns::SpawnDatabase,
+        simulation::{generate_in, generate_world},
+    };
+
+    /// Every tile and named entity of a world, enough to tell two worlds apart
+    #[derive(PartialEq, Debug)]
+    struct WorldSummary {
+        tiles: Vec<(String, usize, u8)>,
+        entities: Vec<(String, usize, usize)>,
+    }
+
+    fn world_summary(wc: &WorldConfig) -> WorldSummary {
+        let (ecs, map) = generate_world(wc).unwrap();
+        let tiles = map.tiles.iter().map(|tile| (tile.name.clone(), tile.atlas_idx, tile.height)).collect();
+        let names = ecs.read_storage::<Name>();
+        let positions = ecs.read_storage::<Position>();
+        let mut entities: Vec<_> =
+            (&names, &positions).join().map(|(name, pos)| (name.0.clone(), pos.x, pos.y)).collect();
+        entities.sort();
+        WorldSummary { tiles, entities }
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_world() {
+        let wc = WorldConfig { world_name: "map_gen_test_same_seed".to_string(), seed: 21, ..Default::default() };
+        let first = world_summary(&wc);
+        assert!(!first.entities.is_empty());
+        assert_eq!(world_summary(&wc), first);
+
+        let other = WorldConfig { seed: 22, ..wc };
+        assert_ne!(world_summary(&other).tiles, first.tiles);
+    }
+
+    #[test]
+    fn creatures_spawn_on_open_tiles_away_from_the_start() {
+        // water down the left side and gravel down the right, the spawn only fits the grass between them
+        let mut map = Map::new(32, 32, (0, 0));
+        for y in 0..32 {
+            for x in 0..8 {
+                map.set_tile(&WorldTile::water(0), x, y);
+            }
+            let mut gravel = WorldTile::grass();
+            gravel.name = "Gravel".to_string();
+            map.set_tile(&gravel, 24, y);
+        }
+        let area = Rect::with_size(0, 0, 32, 32);
+        let biomes = AreaBiomes { area, biomes: vec![None; 32 * 32] };
+        let wc = WorldConfig { player_start: (16, 16), ..Default::default() };
+        let spawn = Spawn {
+            name: "Greg Goat".to_string(),
+            biomes: vec![],
+            tiles: vec!["Grass".to_string()],
+            density: 0.05,
+            group: [2, 3],
+            min_player_distance: 6,
+        };
+
+        let (ecs, ()) = generate_in(|ecs| {
+            *SPAWN_DB.lock().unwrap() = SpawnDatabase { spawns: vec![spawn] };
+            let mut rng = RandomNumberGenerator::seeded(9);
+            spawn_creatures(&map, ecs, &mut rng, &wc, &biomes, &[], &HashSet::new());
+        })
+        .unwrap();
+
+        let names = ecs.read_storage::<Name>();
+        let positions = ecs.read_storage::<Position>();
+        let spots: Vec<(usize, usize)> = (&names, &positions).join().map(|(_, pos)| (pos.x, pos.y)).collect();
+        assert!(!spots.is_empty(), "nothing spawned");
+        for &(x, y) in spots.iter() {
+            let tile = &map.tiles[map.xy_to_idx(x, y)];
+            assert_eq!(tile.name, "Grass", "spawned on {} at {:?}", tile.name, (x, y));
+            // a group starts far enough away but its members can spread back towards the start
+            assert!(
+                x.abs_diff(16).max(y.abs_diff(16)) >= 6 - GROUP_RADIUS,
+                "spawned next to the start at {:?}",
+                (x, y)
+            );
         }
+        let unique: HashSet<&(usize, usize)> = spots.iter().collect();
+        assert_eq!(unique.len(), spots.len(), "two creatures share a tile");
     }
 }
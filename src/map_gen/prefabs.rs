@@ -0,0 +1,90 @@
+//! Stamps the hand made prefabs from `prefabs.ldtk` onto the land of a generated world.
+
+use bracket_lib::{random::RandomNumberGenerator, terminal::Rect};
+use log::{debug, warn};
+use specs::World;
+
+use crate::{
+    components::Position,
+    data_read::{ldtk::PREFABS_LDTK_FILE, prelude::PREFAB_DB},
+    levels::{ground_font, level_size, placed_entities, spawn_placed, stamp_ground},
+    map::Map,
+};
+
+/// Tiles a prefab is never placed over
+const UNBUILDABLE_TILES: [&str; 1] = ["Mountain"];
+
+/// Places prefabs on flat land inside of `area` and returns the area each one covers so nothing else is generated
+/// inside of them. Every site a prefab fits is rolled against its chance once, sites are stepped by the prefab's size.
+/// Prefabs never cross the edge of `area` and `min_spacing` only keeps them apart from the others placed in it, the
+/// prefabs of a neighbouring chunk can be closer.
+pub fn place_prefabs(map: &mut Map, ecs: &mut World, rng: &mut RandomNumberGenerator, area: &Rect) -> Vec<Rect> {
+    let prefab_db = PREFAB_DB.lock().unwrap();
+    // the area of each placed prefab along with its spacing
+    let mut placed: Vec<(Rect, usize)> = vec![];
+
+    for prefab in prefab_db.prefabs.iter() {
+        let level = prefab_db.level_of(prefab);
+        if ground_font(&prefab_db.project, level).is_some_and(|font| font != map.tile_atlas_index) {
+            warn!("Prefab {} is not drawn with the world's tileset so it is never placed", prefab.name);
+            continue;
+        }
+        let (width, height) = level_size(level);
+        if width == 0 || height == 0 || width as i32 > area.width() || height as i32 > area.height() {
+            continue;
+        }
+
+        for y in (area.y1 as usize..=area.y2 as usize - height).step_by(height) {
+            for x in (area.x1 as usize..=area.x2 as usize - width).step_by(width) {
+                let roll = rng.rand::<u64>() as f32 / u64::MAX as f32;
+                if roll >= prefab.chance {
+                    continue;
+                }
+
+                let site = Rect::with_size(x, y, width, height);
+                let crowded = placed
+                    .iter()
+                    .any(|(other, spacing)| gap_between(&site, other) < prefab.min_spacing.max(*spacing) as i32);
+                if crowded || !is_buildable(map, &site, prefab.max_height_diff) {
+                    continue;
+                }
+
+                stamp_ground(ecs, &prefab_db.project, level, map, (x, y));
+                for entity in placed_entities(level) {
+                    let pos = Position::new(x + entity.grid[0] as usize, y + entity.grid[1] as usize);
+                    if let Err(e) = spawn_placed(ecs, PREFABS_LDTK_FILE, &level.identifier, entity, pos) {
+                        warn!("Prefab {} left out an entity: {}", prefab.name, e);
+                    }
+                }
+                debug!("Prefab {} placed at {}, {}", prefab.name, x, y);
+                placed.push((site, prefab.min_spacing));
+            }
+        }
+    }
+
+    placed.into_iter().map(|(site, _)| site).collect()
+}
+
+/// The number of tiles between two areas, going diagonally counts as one tile
+pub(super) fn gap_between(a: &Rect, b: &Rect) -> i32 {
+    let gap_x = (b.x1 - a.x2).max(a.x1 - b.x2).max(0);
+    let gap_y = (b.y1 - a.y2).max(a.y1 - b.y2).max(0);
+    gap_x.max(gap_y)
+}
+
+/// Land that is not water or mountains and is flat enough
+pub(super) fn is_buildable(map: &Map, site: &Rect, max_height_diff: u8) -> bool {
+    let mut lowest = u8::MAX;
+    let mut highest = u8::MIN;
+    for y in site.y1..site.y2 {
+        for x in site.x1..site.x2 {
+            let tile = &map.tiles[map.xy_to_idx(x as usize, y as usize)];
+            if tile.is_blocked || tile.is_water() || UNBUILDABLE_TILES.contains(&tile.name.as_str()) {
+                return false;
+            }
+            lowest = lowest.min(tile.height);
+            highest = highest.max(tile.height);
+        }
+    }
+    highest - lowest <= max_height_diff
+}
@@ -0,0 +1,95 @@
+//! A small map of the whole world drawn on the new game menu. It is sampled straight from the noises so it shows
+//! the land, water and resources a world will have without generating any of its chunks.
+
+use bracket_lib::random::RandomNumberGenerator;
+
+use crate::{
+    data_read::{
+        noise::RESOURCE_NOISE,
+        prelude::{BIOME_DB, NOISE_DB},
+    },
+    game_init::InputWorldConfig,
+};
+
+use super::{terrain_at, WorldConfig};
+
+/// Cells across and down the preview
+pub const PREVIEW_ACROSS: usize = 38;
+pub const PREVIEW_DOWN: usize = 18;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PreviewCell {
+    Water,
+    /// Open ground at a height from 0 to 255
+    Land(u8),
+    Mountain,
+    /// Ground likely to have a resource on it
+    Resource,
+}
+
+/// Kept as a resource so the preview is only sampled again when the settings it was made from change
+#[derive(Default)]
+pub struct WorldPreview {
+    /// The form the cells were sampled for, with the world name left out since it doesn't change the world
+    made_from: Option<InputWorldConfig>,
+    /// Empty when the settings can't make a world
+    pub cells: Vec<PreviewCell>,
+}
+
+impl WorldPreview {
+    /// Samples the preview again when the settings of the form are different from the last time
+    pub fn refresh(&mut self, iwc: &InputWorldConfig) {
+        let mut settings = iwc.clone();
+        settings.world_name.clear();
+        if self.made_from.as_ref() == Some(&settings) {
+            return;
+        }
+
+        self.cells = match WorldConfig::from_settings(&settings) {
+            Ok(wc) if wc.width > 0 && wc.height > 0 => sample_world(&wc),
+            _ => Vec::new(),
+        };
+        self.made_from = Some(settings);
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> Option<PreviewCell> {
+        self.cells.get(x + y * PREVIEW_ACROSS).copied()
+    }
+}
+
+/// One cell for each evenly spaced spot of the world
+fn sample_world(wc: &WorldConfig) -> Vec<PreviewCell> {
+    let mut noise_db = NOISE_DB.lock().unwrap();
+    noise_db.configure(wc.seed, wc.noise_preset.as_deref());
+    let biome_db = BIOME_DB.lock().unwrap();
+    let r_noise = noise_db.get_by_name(RESOURCE_NOISE);
+    let mut rng = RandomNumberGenerator::seeded(wc.seed);
+
+    let mut cells = Vec::with_capacity(PREVIEW_ACROSS * PREVIEW_DOWN);
+    for y in 0..PREVIEW_DOWN {
+        for x in 0..PREVIEW_ACROSS {
+            let (world_x, world_y) = (x * wc.width / PREVIEW_ACROSS, y * wc.height / PREVIEW_DOWN);
+            let (biome, tile) = terrain_at(&noise_db, &biome_db, world_x, world_y);
+            if tile.height < wc.sea_level {
+                cells.push(PreviewCell::Water);
+                continue;
+            }
+            if tile.is_blocked {
+                cells.push(PreviewCell::Mountain);
+                continue;
+            }
+
+            let resource = match (biome, r_noise) {
+                (Some(biome), Some(noise)) => {
+                    biome_db.biomes[biome].resource_at(noise.get_normal_2d(world_x as f32, world_y as f32))
+                }
+                (None, Some(noise)) => noise.get_name_of(world_x, world_y),
+                _ => None,
+            };
+            let roll = rng.rand::<u64>() as f32 / u64::MAX as f32;
+            let has_resource = resource.is_some_and(|(_, weight)| roll < weight * wc.resource_density as f32 / 100.0);
+            cells.push(if has_resource { PreviewCell::Resource } else { PreviewCell::Land(tile.height) });
+        }
+    }
+    cells
+}
@@ -0,0 +1,123 @@
+//! Finding somewhere for the player to start in a freshly generated world. The start has to be open ground that
+//! connects to enough other open ground that the player isn't stuck on a tiny island or in a pocket of rocks.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    components::Position,
+    map::{successors, Map},
+};
+
+/// Fewest tiles that must be reachable from the start
+const MIN_REACHABLE_AREA: usize = 200;
+
+/// The open tile nearest to `around` whose reachable area is big enough, `None` when the map has none.
+/// The map's tile entities must be indexed for blockers to be seen.
+pub fn find_player_spawn(map: &Map, around: &Position) -> Option<Position> {
+    // every tile of an area that was too small, so it is only flooded once
+    let mut enclosed = HashSet::new();
+    let furthest = around.x.max(map.width - around.x).max(around.y).max(map.height - around.y);
+
+    for ring in 0..=furthest {
+        for pos in ring_around(map, around, ring) {
+            let idx = map.xy_to_idx(pos.x, pos.y);
+            if !can_start_on(map, &pos) || enclosed.contains(&idx) {
+                continue;
+            }
+            match reachable_from(map, &pos) {
+                Ok(()) => return Some(pos),
+                Err(area) => enclosed.extend(area),
+            }
+        }
+    }
+    None
+}
+
+fn can_start_on(map: &Map, pos: &Position) -> bool {
+    let tile = &map.tiles[map.xy_to_idx(pos.x, pos.y)];
+    !tile.is_blocked && !tile.is_water() && !map.is_blocked(pos)
+}
+
+/// Tiles on the map exactly `ring` tiles away from `center` counting diagonals as one
+fn ring_around<'a>(map: &'a Map, center: &Position, ring: usize) -> impl Iterator<Item = Position> + 'a {
+    let (cx, cy) = (center.x as i64, center.y as i64);
+    let r = ring as i64;
+    (cy - r..=cy + r)
+        .flat_map(move |y| (cx - r..=cx + r).map(move |x| (x, y)))
+        .filter(move |&(x, y)| (x - cx).abs().max((y - cy).abs()) == r)
+        .filter(move |&(x, y)| x >= 0 && y >= 0 && (x as usize) < map.width && (y as usize) < map.height)
+        .map(|(x, y)| Position::new(x as usize, y as usize))
+}
+
+/// Flood fills from `start` until `MIN_REACHABLE_AREA` tiles are found, the tiles of the area are returned when
+/// it is too small
+fn reachable_from(map: &Map, start: &Position) -> Result<(), Vec<usize>> {
+    let mut seen = HashSet::from([map.xy_to_idx(start.x, start.y)]);
+    let mut frontier = VecDeque::from([*start]);
+
+    while let Some(pos) = frontier.pop_front() {
+        for (next, _) in successors(map, &pos) {
+            if !seen.insert(map.xy_to_idx(next.x, next.y)) {
+                continue;
+            }
+            if seen.len() >= MIN_REACHABLE_AREA {
+                return Ok(());
+            }
+            frontier.push_back(next);
+        }
+    }
+    Err(seen.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, World, WorldExt};
+
+    use super::*;
+    use crate::{
+        components::{Blocking, Breakable, Fishable, Item},
+        indexing::index_map,
+        map::WorldTile,
+    };
+
+    /// A grass map where every tile `is_water` says is water, with blocking entities on the water like world gen
+    fn map_with_water(width: usize, height: usize, is_water: impl Fn(usize, usize) -> bool) -> Map {
+        let mut ecs = World::new();
+        ecs.register::<Position>();
+        ecs.register::<Blocking>();
+        ecs.register::<Breakable>();
+        ecs.register::<Fishable>();
+        ecs.register::<Item>();
+
+        let mut map = Map::new(width, height, (0, 0));
+        for x in 0..width {
+            for y in 0..height {
+                if is_water(x, y) {
+                    map.set_tile(&WorldTile::water(0), x, y);
+                    ecs.create_entity().with(Position::new(x, y)).with(Blocking {}).build();
+                }
+            }
+        }
+        index_map(&mut map, &ecs);
+        map
+    }
+
+    #[test]
+    fn small_island_has_no_spawn() {
+        let map = map_with_water(30, 30, |x, y| !(13..18).contains(&x) || !(13..18).contains(&y));
+        assert_eq!(find_player_spawn(&map, &Position::new(15, 15)), None);
+    }
+
+    #[test]
+    fn water_centre_starts_on_nearest_land() {
+        let map = map_with_water(40, 40, |x, y| (15..=25).contains(&x) && (15..=25).contains(&y));
+        // the lake reaches 5 tiles out from the centre so the first ring with land is 6 tiles out
+        assert_eq!(find_player_spawn(&map, &Position::new(20, 20)), Some(Position::new(14, 14)));
+    }
+
+    #[test]
+    fn centre_on_map_edge_skips_tiles_off_the_map() {
+        let map = map_with_water(40, 40, |x, y| x < 3 && y < 3);
+        assert_eq!(find_player_spawn(&map, &Position::new(0, 0)), Some(Position::new(3, 0)));
+    }
+}
@@ -0,0 +1,204 @@
+//! Villages laid out on flat land of generated worlds. A village is a road running across the middle of its site
+//! with a row of houses along each side, every house has a door facing the road and may have someone living in it.
+
+use bracket_lib::{random::RandomNumberGenerator, terminal::Rect};
+use log::{debug, warn};
+use specs::{
+    saveload::{MarkedBuilder, SimpleMarker},
+    Builder, World, WorldExt,
+};
+
+use crate::{
+    components::{Blocking, Position},
+    data_read::{
+        prelude::{build_being, build_world_obj, TOWN_DB},
+        towns::Town,
+    },
+    map::{Map, WorldTile},
+    saveload::SerializeMe,
+};
+
+use super::prefabs::{gap_between, is_buildable};
+
+/// Random spots looked at for a town before the chunk goes without
+const SITE_TRIES: usize = 10;
+/// Fewest tiles between a town and anything else built in the same chunk
+const TOWN_SPACING: i32 = 4;
+/// Rows of road running through a town
+const ROAD_WIDTH: usize = 2;
+
+/// Which side of the road a row of houses is on
+#[derive(Clone, Copy)]
+enum Side {
+    North,
+    South,
+}
+
+/// Builds towns on flat land inside of `area` away from the `taken` sites and returns the area each one covers
+pub fn place_towns(
+    map: &mut Map,
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    area: &Rect,
+    taken: &[Rect],
+) -> Vec<Rect> {
+    let town_db = TOWN_DB.lock().unwrap();
+    let mut placed: Vec<Rect> = vec![];
+
+    for town in town_db.towns.iter() {
+        let (width, height) = (town.width as i32, town.height as i32);
+        if width > area.width() || height > area.height() {
+            continue;
+        }
+        let roll = rng.rand::<u64>() as f32 / u64::MAX as f32;
+        if roll >= town.chance {
+            continue;
+        }
+
+        for _ in 0..SITE_TRIES {
+            let x = rng.range(area.x1, area.x2 - width + 1);
+            let y = rng.range(area.y1, area.y2 - height + 1);
+            let site = Rect::with_size(x, y, width, height);
+            let crowded = taken.iter().chain(placed.iter()).any(|other| gap_between(&site, other) < TOWN_SPACING);
+            if crowded || !is_buildable(map, &site, town.max_height_diff) {
+                continue;
+            }
+
+            build_town(map, ecs, rng, town, &site);
+            debug!("Town {} built at {}, {}", town.name, x, y);
+            placed.push(site);
+            break;
+        }
+    }
+    placed
+}
+
+fn build_town(map: &mut Map, ecs: &mut World, rng: &mut RandomNumberGenerator, town: &Town, site: &Rect) {
+    let (x1, x2) = (site.x1 as usize, site.x2 as usize);
+    let road_y = site.y1 as usize + town.height / 2 - 1;
+    for y in road_y..road_y + ROAD_WIDTH {
+        for x in x1..x2 {
+            pave(map, &town.road, x, y);
+        }
+    }
+    if let Some(campfire) = &town.campfire {
+        for pos in [Position::new(x1, road_y), Position::new(x2 - 1, road_y + ROAD_WIDTH - 1)] {
+            if let Err(e) = build_world_obj(campfire, pos, ecs) {
+                warn!("{} could not light a campfire: {}", town.name, e);
+            }
+        }
+    }
+
+    for side in [Side::North, Side::South] {
+        // houses start a tile in from the edge of the town and leave a tile of gap between them and the road
+        let front_y = match side {
+            Side::North => road_y - 2,
+            Side::South => road_y + ROAD_WIDTH + 1,
+        };
+        let room_back = match side {
+            Side::North => front_y + 1 - site.y1 as usize,
+            Side::South => site.y2 as usize - front_y,
+        };
+        let [smallest, largest] = town.house_size;
+
+        let mut x = x1 + 1;
+        while x + smallest < x2 {
+            let width = rng.range(smallest, largest + 1).min(x2 - 1 - x);
+            let depth = rng.range(smallest, largest + 1).min(room_back);
+            let house = match side {
+                Side::North => Rect::with_size(x, front_y + 1 - depth, width, depth),
+                Side::South => Rect::with_size(x, front_y, width, depth),
+            };
+            build_house(map, ecs, rng, town, &house, side);
+            x += width + rng.range(1, 3);
+        }
+    }
+}
+
+/// Walls in a house with a door in the middle of the wall facing the road and rolls for someone to live in it
+fn build_house(map: &mut Map, ecs: &mut World, rng: &mut RandomNumberGenerator, town: &Town, house: &Rect, side: Side) {
+    let (x1, y1, x2, y2) = (house.x1 as usize, house.y1 as usize, house.x2 as usize, house.y2 as usize);
+    let (front_y, path_y) = match side {
+        Side::North => (y2 - 1, y2),
+        Side::South => (y1, y1 - 1),
+    };
+    let door_x = rng.range(x1 + 1, x2 - 1);
+
+    for y in y1..y2 {
+        for x in x1..x2 {
+            let is_wall = x == x1 || x == x2 - 1 || y == y1 || y == y2 - 1;
+            if (x, y) == (door_x, front_y) {
+                pave(map, &town.door, x, y);
+            } else if is_wall {
+                pave(map, &town.wall, x, y);
+                ecs.create_entity()
+                    .with(Position::new(x, y))
+                    .with(Blocking {})
+                    .marked::<SimpleMarker<SerializeMe>>()
+                    .build();
+            } else {
+                pave(map, &town.floor, x, y);
+            }
+        }
+    }
+    pave(map, &town.road, door_x, path_y);
+
+    let roll = rng.rand::<u64>() as f32 / u64::MAX as f32;
+    if roll >= town.resident_chance {
+        return;
+    }
+    if let Some(name) = town.roll_resident(rng) {
+        let home = Position::new((x1 + x2) / 2, (y1 + y2) / 2);
+        if let Err(e) = build_being(name, home, ecs) {
+            warn!("{} could not move into {}: {}", name, town.name, e);
+        }
+    }
+}
+
+/// Lays a town tile down without flattening the land under it
+fn pave(map: &mut Map, tile: &WorldTile, x: usize, y: usize) {
+    let height = map.tiles[map.xy_to_idx(x, y)].height;
+    map.set_tile(tile, x, y);
+    let idx = map.xy_to_idx(x, y);
+    map.tiles[idx].height = height;
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket_lib::terminal::Point;
+    use specs::Join;
+
+    use super::*;
+    use crate::simulation::generate_in;
+
+    #[test]
+    fn towns_are_placed_inside_bounds() {
+        let area = Rect::with_size(8, 8, 48, 40);
+        let taken = [Rect::with_size(8, 8, 6, 6)];
+        let (_, placed_any) = generate_in(|ecs| {
+            let mut placed_any = false;
+            for seed in 0..200 {
+                let mut map = Map::new(64, 64, (0, 0));
+                let mut rng = RandomNumberGenerator::seeded(seed);
+                let sites = place_towns(&mut map, ecs, &mut rng, &area, &taken);
+                ecs.maintain();
+
+                for site in sites.iter() {
+                    assert!(site.x1 >= area.x1 && site.y1 >= area.y1, "{:?} starts outside {:?}", site, area);
+                    assert!(site.x2 <= area.x2 && site.y2 <= area.y2, "{:?} ends outside {:?}", site, area);
+                    assert!(gap_between(site, &taken[0]) >= TOWN_SPACING, "{:?} is built against {:?}", site, taken);
+                }
+                for pos in ecs.read_storage::<Position>().join() {
+                    let built_in_town = sites.iter().any(|site| site.point_in_rect(Point::new(pos.x, pos.y)));
+                    assert!(built_in_town, "seed {} put {:?} outside of its towns", seed, pos);
+                }
+                placed_any |= !sites.is_empty();
+                ecs.delete_all();
+                ecs.maintain();
+            }
+            placed_any
+        })
+        .unwrap();
+        assert!(placed_any, "no seed placed a town");
+    }
+}
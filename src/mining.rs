@@ -1,20 +1,9 @@
-use std::time::Duration;
-
 use crate::{
-    audio::play_sound_effect,
-    char_c::CH_STRIKE,
-    components::{
-        BreakAction, Breakable, EntityStats, HealthStats, Name, Renderable, SizeFlexor, SufferDamage, ToolType,
-    },
-    data_read::ENTITY_DB,
-    game_init::PlayerEntity,
-    tile_animation::{AnimationRequest, TileAnimationBuilder},
-    ui::message_log::MessageLog,
-    z_order::EFFECT_Z,
+    components::{BreakAction, Breakable, EntityStats, HealthStats, Name, SufferDamage, ToolType},
+    events::{GameEvent, GameEvents},
 };
-use bracket_lib::color::WHITE;
-use log::{debug, error, info};
-use specs::{Entities, Entity, Join, Read, ReadStorage, System, Write, WriteStorage};
+use log::debug;
+use specs::{Entities, Entity, Join, ReadStorage, System, Write, WriteStorage};
 
 /// Allows tile to be breakable. The tile must contain a breakable and health stats component.
 /// The attacker must contain a strength and have breakactions queued up in their system.
@@ -25,13 +14,11 @@ impl<'a> System<'a> for TileDestructionSystem {
     type SystemData = (
         WriteStorage<'a, BreakAction>,
         WriteStorage<'a, SufferDamage>,
-        Write<'a, MessageLog>,
-        Write<'a, TileAnimationBuilder>,
+        Write<'a, GameEvents>,
         ReadStorage<'a, EntityStats>,
         ReadStorage<'a, Breakable>,
         ReadStorage<'a, HealthStats>,
         ReadStorage<'a, Name>,
-        Read<'a, PlayerEntity>,
         Entities<'a>,
     );
 
@@ -40,51 +27,30 @@ impl<'a> System<'a> for TileDestructionSystem {
         (
             mut break_actions,
             mut suffer_damage,
-            mut log,
-            mut anim_builder,
+            mut events,
             stats,
             breakable,
             health_stats,
             names,
-            player_e,
             entities,
         ): Self::SystemData,
     ) {
-        let edb = &ENTITY_DB.lock().unwrap();
-        for (breaker, stats, action, name) in (&entities, &stats, &break_actions, &names).join() {
-            if let Some((tile_entity, tile_name, target_breakable, target_stats)) =
+        for (breaker, stats, action, _) in (&entities, &stats, &break_actions, &names).join() {
+            if let Some((tile_entity, _, target_breakable, target_stats)) =
                 (&entities, &names, &breakable, &health_stats).join().find(|(e, ..)| *e == action.target)
             {
                 if !inventory_contains_tool(&target_breakable.by) {
-                    log.log(format!("You do not own the correct tool for this {name}."));
+                    events.send(GameEvent::MissingTool { breaker, target: tile_entity });
                     continue;
                 }
                 if target_stats.defense > stats.set.strength {
-                    log.log("Took no damage because defense is greater");
+                    events.send(GameEvent::AttackBlocked { attacker: breaker, target: tile_entity });
                     continue;
                 }
 
                 let damage = stats.set.strength - target_stats.defense;
-                log.log(format!("{} dealt {} damage to {}", name.0, damage, tile_name.0));
+                events.send(GameEvent::DamageDealt { attacker: breaker, target: tile_entity, amount: damage });
                 SufferDamage::new_damage(&mut suffer_damage, action.target, -(damage as i32));
-
-                if breaker == player_e.0 {
-                    let sound_name = match edb.world_objs.get_by_name(&tile_name.0) {
-                        Some(info) => &info.impact_sound,
-                        None => "",
-                    };
-                    play_sound_effect(sound_name);
-                }
-
-                let size_flex =
-                    AnimationRequest::StretchShrink(tile_entity, SizeFlexor::new(&[(0.75, 1.25), (1.0, 1.0)], 25.0));
-                let flash_white = AnimationRequest::GlyphFlash(
-                    tile_entity,
-                    Duration::from_secs_f32(0.15),
-                    Renderable::clear_bg(CH_STRIKE, WHITE, EFFECT_Z),
-                );
-                anim_builder.request(size_flex);
-                anim_builder.request(flash_white);
             }
         }
 
@@ -94,10 +60,7 @@ impl<'a> System<'a> for TileDestructionSystem {
 
 // TODO: when we get the inventory added check that it contains the tool
 fn inventory_contains_tool(tool_type: &ToolType) -> bool {
-    match tool_type {
-        ToolType::Hand => true,
-        _ => false,
-    }
+    matches!(tool_type, ToolType::Hand)
 }
 
 impl SufferDamage {
@@ -131,24 +94,3 @@ impl<'a> System<'a> for DamageSystem {
         damage.clear();
     }
 }
-
-pub struct RemoveDeadTiles;
-
-impl<'a> System<'a> for RemoveDeadTiles {
-    type SystemData = (ReadStorage<'a, HealthStats>, ReadStorage<'a, Name>, Entities<'a>);
-
-    fn run(&mut self, (breakable, names, entities): Self::SystemData) {
-        for (stats, e, name) in (&breakable, &entities, &names).join() {
-            if stats.hp == 0 {
-                match entities.delete(e) {
-                    Ok(..) => {
-                        info!("{} is dead and was deleted, items should have spawned if any.", name);
-                    }
-                    Err(err) => {
-                        error!("Failed to clean up {} : {}", e.id(), err);
-                    }
-                }
-            }
-        }
-    }
-}
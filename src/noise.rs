@@ -26,18 +26,7 @@ impl Noise {
     // Shorthand function to generate the tile based on the configuration of this world generator
     pub fn gen_tile(&self, x: usize, y: usize) -> WorldTile {
         let value = self.get_normal_2d(x as f32, y as f32);
-        if let Some(tile) = self.find_tile_map(value) {
-            let world_tile = WorldTile {
-                name: tile.name,
-                atlas_idx: tile.atlas_idx.unwrap(),
-                height: (value * 255.0).round() as u8,
-                is_blocked: tile.is_blocked.is_some(),
-                ..Default::default()
-            };
-            return world_tile;
-        }
-
-        WorldTile::default()
+        self.find_tile_map(value).and_then(|tile| tile.to_world_tile(value)).unwrap_or_default()
     }
 
     pub fn get_name_of(&self, x: usize, y: usize) -> Option<(String, f32)> {
@@ -55,23 +44,39 @@ impl Noise {
         (self.noise.get_noise(x * self.scale.x, y * self.scale.y) + 1.0) * 0.5
     }
 
-    // TODO: make unit tests for this
     pub fn find_tile_map(&self, value: f32) -> Option<RawWorldTile> {
-        // need to find the id that the value > chance at the highest is true
-        // value - chance ie value = 0.8, chance = 0.9, 0.75, 0.5, 0.25
-        // -0.1, *0.05*, 0.3, 0.65
-        // min [] > 0
-        if let Some(tm) = &self
-            .mapping
-            .iter()
-            .map(|tm| (tm, value - tm.height))
-            .filter(|(_, diff)| diff.is_positive())
-            .min_by(|(_, chance), (_, chance2)| chance.total_cmp(chance2))
-        {
-            return Some(tm.0.clone());
-        }
-        None
+        find_tile_map(&self.mapping, value)
+    }
+}
+
+impl RawWorldTile {
+    /// The tile for a spot of land where the height noise gave `value`, `None` for mappings that are not tiles
+    pub fn to_world_tile(&self, value: f32) -> Option<WorldTile> {
+        Some(WorldTile {
+            name: self.name.clone(),
+            atlas_idx: self.atlas_idx?,
+            height: (value * 255.0).round() as u8,
+            is_blocked: self.is_blocked.is_some(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Picks the mapping with the highest `height` that `value` is above
+pub fn find_tile_map(mapping: &[RawWorldTile], value: f32) -> Option<RawWorldTile> {
+    // need to find the id that the value > chance at the highest is true
+    // value - chance ie value = 0.8, chance = 0.9, 0.75, 0.5, 0.25
+    // -0.1, *0.05*, 0.3, 0.65
+    // min [] > 0
+    if let Some(tm) = &mapping
+        .iter()
+        .map(|tm| (tm, value - tm.height))
+        .filter(|(_, diff)| diff.is_positive())
+        .min_by(|(_, chance), (_, chance2)| chance.total_cmp(chance2))
+    {
+        return Some(tm.0.clone());
     }
+    None
 }
 
 #[cfg(test)]
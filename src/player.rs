@@ -6,13 +6,15 @@ use crate::{
     },
     game_init::PlayerEntity,
     items::inventory_contains,
+    keybindings::{Action, BindingContext, Keybindings},
+    levels::edge_transition,
     map::{MapRes, TileEntity},
     saveload::{any_save_game_exists, SaveAction},
-    settings::SettingsAction,
+    settings::{SettingsAction, SettingsSelection},
     ui::message_log::MessageLog,
     AppState, Position,
 };
-use bracket_lib::terminal::{BTerm, Point, VirtualKeyCode as VKC};
+use bracket_lib::terminal::{BTerm, Point};
 use log::info;
 use serde::{Deserialize, Serialize};
 use specs::{prelude::*, Component};
@@ -27,37 +29,34 @@ pub enum PlayerResponse {
     Waiting,
 }
 
-pub fn p_input_game(ecs: &mut World, ctx: &BTerm) -> PlayerResponse {
-    match ctx.key {
-        None => PlayerResponse::Waiting,
-        Some(key) => {
-            match key {
-                VKC::W | VKC::Up => try_move_player(0, -1, ecs),
-                VKC::S | VKC::Down => try_move_player(0, 1, ecs),
-                VKC::A | VKC::Left => try_move_player(-1, 0, ecs),
-                VKC::D | VKC::Right => try_move_player(1, 0, ecs),
-                VKC::P => try_pickup(ecs), // p for pickup
-                VKC::M => {
-                    switch_interaction_mode(ecs);
-                    PlayerResponse::Waiting
-                }
-                VKC::I => {
-                    play_sound_effect("ui_inventory");
-                    PlayerResponse::StateChange(AppState::PlayerInInventory)
-                }
-                VKC::Escape => PlayerResponse::StateChange(AppState::SaveGame),
-                VKC::Space => {
-                    let mut log = ecs.fetch_mut::<MessageLog>();
-                    log.log("The player stands around.");
-                    PlayerResponse::TurnAdvance
-                }
-                _ => PlayerResponse::Waiting, // Unbound keypress so just ignore it
-            }
+pub fn p_input_game(ecs: &mut World, ctx: &BTerm, binds: &Keybindings) -> PlayerResponse {
+    match binds.pressed(BindingContext::Game, ctx) {
+        Some(Action::MoveUp) => try_move_player(0, -1, ecs),
+        Some(Action::MoveDown) => try_move_player(0, 1, ecs),
+        Some(Action::MoveLeft) => try_move_player(-1, 0, ecs),
+        Some(Action::MoveRight) => try_move_player(1, 0, ecs),
+        Some(Action::Pickup) => try_pickup(ecs),
+        Some(Action::ToggleInteractMode) => {
+            switch_interaction_mode(ecs);
+            PlayerResponse::Waiting
         }
+        Some(Action::OpenInventory) => {
+            play_sound_effect("ui_inventory");
+            PlayerResponse::StateChange(AppState::PlayerInInventory)
+        }
+        Some(Action::OpenSaveMenu) => PlayerResponse::StateChange(AppState::SaveGame),
+        Some(Action::Wait) => player_wait(ecs),
+        _ => PlayerResponse::Waiting, // Unbound keypress so just ignore it
     }
 }
 
-fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerResponse {
+pub(crate) fn player_wait(ecs: &mut World) -> PlayerResponse {
+    let mut log = ecs.fetch_mut::<MessageLog>();
+    log.log("The player stands around.");
+    PlayerResponse::TurnAdvance
+}
+
+pub(crate) fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerResponse {
     let mut positions = ecs.write_storage::<Position>();
     let players = ecs.read_storage::<Player>();
     let interactors = ecs.read_storage::<Interactor>();
@@ -67,7 +66,10 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerRespons
 
         let map = ecs.fetch::<MapRes>();
         if !map.0.in_bounds(target_pos) {
-            return PlayerResponse::Waiting;
+            return match edge_transition(&map.0, target_pos) {
+                Some(level_change) => PlayerResponse::StateChange(level_change),
+                None => PlayerResponse::Waiting,
+            };
         }
 
         match map.0.first_entity_in_pos(&Position::from(target_pos)) {
@@ -117,7 +119,7 @@ fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> PlayerRespons
     PlayerResponse::Waiting
 }
 
-fn try_pickup(ecs: &mut World) -> PlayerResponse {
+pub(crate) fn try_pickup(ecs: &mut World) -> PlayerResponse {
     let mut pickups = ecs.write_storage::<PickupAction>();
 
     let player_entity = ecs.read_resource::<PlayerEntity>();
@@ -134,7 +136,7 @@ fn try_pickup(ecs: &mut World) -> PlayerResponse {
     PlayerResponse::Waiting
 }
 
-fn switch_interaction_mode(ecs: &mut World) {
+pub(crate) fn switch_interaction_mode(ecs: &mut World) {
     let player_entity = ecs.read_resource::<PlayerEntity>();
     let mut interactors = ecs.write_storage::<Interactor>();
 
@@ -160,20 +162,17 @@ pub fn check_player_finished(ecs: &mut World) -> bool {
     (&players, &mut finished_activities).join().next().is_some()
 }
 
-pub fn p_input_activity(ecs: &mut World, ctx: &mut BTerm) {
-    if ctx.key.is_none() {
-        return;
+pub fn p_input_activity(ecs: &mut World, ctx: &mut BTerm, binds: &Keybindings) {
+    if let Some(Action::ActivityInput) = binds.pressed(BindingContext::Activity, ctx) {
+        player_game_action(ecs);
     }
+}
 
-    match ctx.key.unwrap() {
-        VKC::Space => {
-            info!("Player pressed game action");
-            let mut game_actions = ecs.write_storage::<GameAction>();
-            let player_entity = ecs.read_resource::<PlayerEntity>();
-            let _ = game_actions.insert(player_entity.0, GameAction {});
-        }
-        _ => {}
-    }
+pub(crate) fn player_game_action(ecs: &mut World) {
+    info!("Player pressed game action");
+    let mut game_actions = ecs.write_storage::<GameAction>();
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    let _ = game_actions.insert(player_entity.0, GameAction {});
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -203,53 +202,50 @@ pub enum MenuAction {
     Waiting,
 }
 
-pub fn p_input_main_menu(ctx: &mut BTerm, hovering: &MenuSelection) -> MenuAction {
-    if let Some(key) = ctx.key {
-        match key {
-            VKC::Down | VKC::S => MenuAction::Hovering(match hovering {
-                MenuSelection::NewGame if any_save_game_exists() => MenuSelection::LoadGame,
-                MenuSelection::NewGame => MenuSelection::Settings,
-                MenuSelection::LoadGame => MenuSelection::Settings,
-                MenuSelection::Settings => MenuSelection::QuitGame,
-                MenuSelection::QuitGame => MenuSelection::NewGame,
-            }),
-            VKC::Up | VKC::W => MenuAction::Hovering(match hovering {
-                MenuSelection::NewGame => MenuSelection::QuitGame,
-                MenuSelection::LoadGame => MenuSelection::NewGame,
-                MenuSelection::Settings if any_save_game_exists() => MenuSelection::LoadGame,
-                MenuSelection::Settings => MenuSelection::NewGame,
-                MenuSelection::QuitGame => MenuSelection::Settings,
-            }),
-            VKC::Return => MenuAction::Selected(*hovering),
-            _ => MenuAction::Waiting,
-        }
-    } else {
-        MenuAction::Waiting
+pub fn p_input_main_menu(ctx: &mut BTerm, hovering: &MenuSelection, binds: &Keybindings) -> MenuAction {
+    match binds.pressed(BindingContext::Menu, ctx) {
+        Some(Action::MenuDown) => MenuAction::Hovering(match hovering {
+            MenuSelection::NewGame if any_save_game_exists() => MenuSelection::LoadGame,
+            MenuSelection::NewGame => MenuSelection::Settings,
+            MenuSelection::LoadGame => MenuSelection::Settings,
+            MenuSelection::Settings => MenuSelection::QuitGame,
+            MenuSelection::QuitGame => MenuSelection::NewGame,
+        }),
+        Some(Action::MenuUp) => MenuAction::Hovering(match hovering {
+            MenuSelection::NewGame => MenuSelection::QuitGame,
+            MenuSelection::LoadGame => MenuSelection::NewGame,
+            MenuSelection::Settings if any_save_game_exists() => MenuSelection::LoadGame,
+            MenuSelection::Settings => MenuSelection::NewGame,
+            MenuSelection::QuitGame => MenuSelection::Settings,
+        }),
+        Some(Action::MenuSelect) => MenuAction::Selected(*hovering),
+        _ => MenuAction::Waiting,
     }
 }
 
-pub fn p_input_save_game(ctx: &mut BTerm) -> SaveAction {
-    if let Some(key) = ctx.key {
-        match key {
-            VKC::Return | VKC::S => SaveAction::Save,
-            VKC::Escape => SaveAction::Cancel,
-            VKC::Space => SaveAction::QuickSave,
-            VKC::D => SaveAction::QuitWithoutSaving,
-            _ => SaveAction::Waiting,
-        }
-    } else {
-        SaveAction::Waiting
+/// Returns true once the player has read the error and wants to leave
+pub fn p_input_data_error(ctx: &mut BTerm, binds: &Keybindings) -> bool {
+    matches!(binds.pressed(BindingContext::Menu, ctx), Some(Action::MenuSelect) | Some(Action::MenuBack))
+}
+
+pub fn p_input_save_game(ctx: &mut BTerm, binds: &Keybindings) -> SaveAction {
+    match binds.pressed(BindingContext::SaveMenu, ctx) {
+        Some(Action::Save) => SaveAction::Save,
+        Some(Action::CancelSave) => SaveAction::Cancel,
+        Some(Action::QuickSave) => SaveAction::QuickSave,
+        Some(Action::QuitWithoutSaving) => SaveAction::QuitWithoutSaving,
+        _ => SaveAction::Waiting,
     }
 }
 
-pub fn p_input_settings(ctx: &mut BTerm) -> SettingsAction {
-    if let Some(key) = ctx.key {
-        match key {
-            VKC::Return => SettingsAction::Selected,
-            VKC::Escape => SettingsAction::ReturnToMainMenu,
-            _ => SettingsAction::Waiting,
-        }
-    } else {
-        SettingsAction::Waiting
+pub fn p_input_settings(ctx: &mut BTerm, hovering: &SettingsSelection, binds: &Keybindings) -> SettingsAction {
+    match binds.pressed(BindingContext::Menu, ctx) {
+        Some(Action::MenuUp) | Some(Action::MenuDown) => SettingsAction::Hovering(match hovering {
+            SettingsSelection::SpriteMode => SettingsSelection::Keybindings,
+            SettingsSelection::Keybindings => SettingsSelection::SpriteMode,
+        }),
+        Some(Action::MenuSelect) => SettingsAction::Selected,
+        Some(Action::MenuBack) => SettingsAction::ReturnToMainMenu,
+        _ => SettingsAction::Waiting,
     }
 }
@@ -0,0 +1,383 @@
+//! Records the input of a play session so it can be fed back into the game later.
+//! Every key read by the `p_input_*` functions comes from `ctx.key` so recording and replaying happen on the
+//! `BTerm` at the very start of a frame, before any input is read.
+//! The frame times are kept as well since timed activities such as fishing depend on them, and so is the mouse
+//! since the debug tools are clicked.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use bracket_lib::terminal::BTerm;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    keys::{key_from_name, key_name},
+    saveload::{save_game_exists, SAVE_PATH},
+};
+
+pub const REPLAY_FOLDER: &str = "./replays/";
+const LAST_RUN_REPLAY: &str = "last_run.edoreplay";
+/// How often the recording is written out in case the game closes without saving, about once a minute
+const REPLAY_FLUSH_FRAMES: usize = 3600;
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Replay {
+    /// The seeds of the worlds that were started while recording, in the order they were started
+    pub world_seeds: Vec<u64>,
+    /// The save file that was loaded while recording, it must still exist for the replay to line up
+    pub loaded_save: Option<String>,
+    pub frame_times_ms: Vec<f32>,
+    pub inputs: Vec<RecordedInput>,
+    /// Only the frames where the mouse moved or the click changed are kept
+    pub mouse: Vec<RecordedMouse>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecordedInput {
+    pub frame: usize,
+    /// The `TurnCounter` when the key was pressed, used to check the replay hasn't drifted
+    pub turn: usize,
+    pub key: String,
+    pub shift: bool,
+    pub control: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct MouseState {
+    /// In window pixels like `BTerm::mouse_pos`, so it is turned into console positions the same way when replayed
+    pub pos: (i32, i32),
+    pub left_click: bool,
+}
+
+impl MouseState {
+    fn of(ctx: &BTerm) -> Self {
+        Self { pos: ctx.mouse_pos, left_click: ctx.left_click }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecordedMouse {
+    pub frame: usize,
+    pub mouse: MouseState,
+}
+
+/// A replay file is one of these per line. Each flush appends the lines recorded since the last one, so the
+/// file never has to be written out again from the start.
+#[derive(Serialize, Deserialize)]
+enum ReplayLine {
+    WorldSeed(u64),
+    LoadedSave(String),
+    /// Carries on from the frame times of the line before it
+    FrameTimes(Vec<f32>),
+    Input(RecordedInput),
+    Mouse(RecordedMouse),
+}
+
+impl Replay {
+    /// Fails when the file can't be read or the save it loads is gone, the replay would not line up without it
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let raw_replay =
+            fs::read_to_string(path).map_err(|e| format!("{} could not be read: {}", path.display(), e))?;
+        let mut replay = Replay::default();
+        for (idx, raw_line) in raw_replay.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let line = serde_json::from_str(raw_line)
+                .map_err(|e| format!("{} could not be parsed at line {}: {}", path.display(), idx + 1, e))?;
+            match line {
+                ReplayLine::WorldSeed(seed) => replay.world_seeds.push(seed),
+                ReplayLine::LoadedSave(file_name) => replay.loaded_save = Some(file_name),
+                ReplayLine::FrameTimes(times) => replay.frame_times_ms.extend(times),
+                ReplayLine::Input(input) => replay.inputs.push(input),
+                ReplayLine::Mouse(mouse) => replay.mouse.push(mouse),
+            }
+        }
+
+        if let Some(file_name) = replay.loaded_save.as_ref().filter(|file_name| !save_game_exists(file_name)) {
+            return Err(format!(
+                "{} loads the save {} which is no longer in {}, it can't be played without it",
+                path.display(),
+                file_name,
+                SAVE_PATH
+            ));
+        }
+        Ok(replay)
+    }
+
+    /// Empties the replay file so a new recording can be appended to it
+    fn start_file(path: &Path) {
+        if let Some(folder) = path.parent() {
+            let _ = fs::create_dir_all(folder);
+        }
+        if let Err(e) = File::create(path) {
+            error!("Replay Creating Error: {}", e);
+        }
+    }
+
+    /// Moves everything in the replay to the end of the replay file, leaving the replay empty
+    fn append(&mut self, path: &Path) {
+        let file = match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => file,
+            Err(e) => {
+                error!("Replay Saving Error: {}", e);
+                return;
+            }
+        };
+
+        let mut lines = vec![];
+        lines.extend(self.world_seeds.iter().copied().map(ReplayLine::WorldSeed));
+        lines.extend(self.loaded_save.clone().map(ReplayLine::LoadedSave));
+        if !self.frame_times_ms.is_empty() {
+            lines.push(ReplayLine::FrameTimes(self.frame_times_ms.clone()));
+        }
+        lines.extend(self.inputs.iter().cloned().map(ReplayLine::Input));
+        lines.extend(self.mouse.iter().cloned().map(ReplayLine::Mouse));
+
+        let mut writer = BufWriter::new(file);
+        for line in lines.iter() {
+            let written = serde_json::to_string(line)
+                .map_err(|e| e.to_string())
+                .and_then(|data| writeln!(writer, "{}", data).map_err(|e| e.to_string()));
+            if let Err(e) = written {
+                error!("Replay Saving Error: {}", e);
+                break;
+            }
+        }
+        if let Err(e) = writer.flush() {
+            error!("Replay Saving Error: {}", e);
+        }
+        *self = Replay::default();
+    }
+}
+
+fn last_run_path() -> PathBuf {
+    Path::new(REPLAY_FOLDER).join(LAST_RUN_REPLAY)
+}
+
+/// Where the input for each frame comes from
+pub enum InputSource {
+    Keyboard,
+    /// `unsaved` only holds what was recorded since the last flush
+    Recording {
+        unsaved: Replay,
+        path: PathBuf,
+        frame: usize,
+        mouse: MouseState,
+    },
+    Replaying {
+        replay: Replay,
+        frame: usize,
+        next_input: usize,
+        next_mouse: usize,
+        mouse: MouseState,
+        next_world: usize,
+    },
+}
+
+impl InputSource {
+    pub fn recording() -> Self {
+        Self::recording_to(last_run_path())
+    }
+
+    /// Records into the file at `path` instead of the last run replay
+    pub fn recording_to(path: PathBuf) -> Self {
+        Replay::start_file(&path);
+        Self::Recording { unsaved: Replay::default(), path, frame: 0, mouse: MouseState::default() }
+    }
+
+    pub fn replaying(replay: Replay) -> Self {
+        info!(
+            "Replaying {} frames, world seeds {:?}, loaded save {:?}",
+            replay.frame_times_ms.len(),
+            replay.world_seeds,
+            replay.loaded_save
+        );
+        Self::Replaying { replay, frame: 0, next_input: 0, next_mouse: 0, mouse: MouseState::default(), next_world: 0 }
+    }
+
+    /// Records the frame's input or overwrites it with the replayed input, must be called before any input is read
+    pub fn begin_frame(&mut self, ctx: &mut BTerm, turn: usize) {
+        match self {
+            InputSource::Keyboard => {}
+            InputSource::Recording { unsaved, path, frame, mouse } => {
+                unsaved.frame_times_ms.push(ctx.frame_time_ms);
+                if let Some(key) = ctx.key {
+                    match key_name(key) {
+                        Some(name) => unsaved.inputs.push(RecordedInput {
+                            frame: *frame,
+                            turn,
+                            key: name.to_string(),
+                            shift: ctx.shift,
+                            control: ctx.control,
+                        }),
+                        None => warn!("{:?} has no name so it was left out of the replay", key),
+                    }
+                }
+                if MouseState::of(ctx) != *mouse {
+                    *mouse = MouseState::of(ctx);
+                    unsaved.mouse.push(RecordedMouse { frame: *frame, mouse: *mouse });
+                }
+                *frame += 1;
+                if *frame % REPLAY_FLUSH_FRAMES == 0 {
+                    unsaved.append(path);
+                }
+            }
+            InputSource::Replaying { replay, frame, next_input, next_mouse, mouse, .. } => {
+                let frame_time = match replay.frame_times_ms.get(*frame) {
+                    Some(ft) => *ft,
+                    None => {
+                        info!("Replay finished at frame {}, input is back to the keyboard.", frame);
+                        *self = InputSource::Keyboard;
+                        return;
+                    }
+                };
+                ctx.frame_time_ms = frame_time;
+                ctx.key = None;
+                ctx.shift = false;
+                ctx.control = false;
+
+                if let Some(input) = replay.inputs.get(*next_input).filter(|input| input.frame == *frame) {
+                    if input.turn != turn {
+                        warn!(
+                            "Replay drifted at frame {}, expected turn {} but it is turn {}",
+                            frame, input.turn, turn
+                        );
+                    }
+                    ctx.key = key_from_name(&input.key);
+                    ctx.shift = input.shift;
+                    ctx.control = input.control;
+                    *next_input += 1;
+                }
+                if let Some(recorded) = replay.mouse.get(*next_mouse).filter(|recorded| recorded.frame == *frame) {
+                    *mouse = recorded.mouse;
+                    *next_mouse += 1;
+                }
+                ctx.mouse_pos = mouse.pos;
+                ctx.left_click = mouse.left_click;
+                *frame += 1;
+            }
+        }
+    }
+
+    /// Replays stop when the world isn't the one they were recorded in since none of the input would line up
+    pub fn world_started(&mut self, seed: u64) {
+        match self {
+            InputSource::Recording { unsaved, .. } => unsaved.world_seeds.push(seed),
+            InputSource::Replaying { replay, next_world, .. } => {
+                let expected = replay.world_seeds.get(*next_world).copied();
+                *next_world += 1;
+                if expected != Some(seed) {
+                    error!(
+                        "Replay was recorded in a world with seed {:?} but seed {} was started, input is back to the keyboard.",
+                        expected, seed
+                    );
+                    *self = InputSource::Keyboard;
+                }
+            }
+            InputSource::Keyboard => {}
+        }
+    }
+
+    pub fn save_loaded(&mut self, file_name: &str) {
+        if let InputSource::Recording { unsaved, .. } = self {
+            unsaved.loaded_save = Some(file_name.to_string());
+        }
+    }
+
+    /// Appends what was recorded since the last flush to the replay file
+    pub fn flush(&mut self) {
+        if let InputSource::Recording { unsaved, path, frame, .. } = self {
+            unsaved.append(path);
+            info!("Replay of {} frames was saved", frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use bracket_lib::terminal::{VirtualKeyCode as VKC, RGB};
+
+    use super::*;
+    use crate::{keybindings::Keybindings, map_gen::WorldConfig, simulation::Simulation};
+
+    fn headless_ctx() -> BTerm {
+        BTerm {
+            width_pixels: 0,
+            height_pixels: 0,
+            original_height_pixels: 0,
+            original_width_pixels: 0,
+            fps: 60.0,
+            frame_time_ms: 16.7,
+            active_console: 0,
+            key: None,
+            mouse_pos: (0, 0),
+            left_click: false,
+            shift: false,
+            control: false,
+            alt: false,
+            web_button: None,
+            quitting: false,
+            post_scanlines: false,
+            post_screenburn: false,
+            screen_burn_color: RGB::new(),
+            mouse_visible: true,
+        }
+    }
+
+    /// Runs a new world through `input` for as many frames as `keys` has, pressing each key on its frame and
+    /// moving the mouse along. Returns where the mouse was on each frame.
+    fn play(input: &mut InputSource, world_name: &str, keys: &[Option<VKC>]) -> (Simulation, Vec<MouseState>) {
+        let world_cfg = WorldConfig { world_name: world_name.to_string(), seed: 11, ..Default::default() };
+        let mut sim = Simulation::new(&world_cfg).unwrap();
+        input.world_started(world_cfg.seed);
+
+        let binds = Keybindings::default();
+        let mut ctx = headless_ctx();
+        let mut mice = vec![];
+        for (frame, key) in keys.iter().enumerate() {
+            ctx.key = *key;
+            ctx.mouse_pos = (frame as i32 / 3, 7);
+            ctx.left_click = frame == 4;
+            input.begin_frame(&mut ctx, sim.turn());
+            mice.push(MouseState::of(&ctx));
+            sim.step_keys(&mut ctx, &binds);
+        }
+        (sim, mice)
+    }
+
+    #[test]
+    fn replay_plays_back_what_was_recorded() {
+        let path = env::temp_dir().join("edo_replay_round_trip.edoreplay");
+        let keys = [Some(VKC::D), None, Some(VKC::S), Some(VKC::Space), None, Some(VKC::A), Some(VKC::W), None];
+
+        let mut recording = InputSource::recording_to(path.clone());
+        let (recorded, recorded_mice) = play(&mut recording, "replay_recorded", &keys);
+        recording.flush();
+
+        let replay = Replay::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(replay.world_seeds, vec![11]);
+        assert_eq!(replay.frame_times_ms.len(), keys.len());
+        let mut replaying = InputSource::replaying(replay);
+        // the keys pressed while replaying are ignored in favor of the recorded ones
+        let (replayed, replayed_mice) = play(&mut replaying, "replay_replayed", &[Some(VKC::Escape); 8]);
+
+        assert!(recorded.turn() > 0);
+        assert_eq!(replayed.turn(), recorded.turn());
+        assert_eq!(replayed.player_position(), recorded.player_position());
+        assert_eq!(replayed_mice, recorded_mice);
+    }
+
+    #[test]
+    fn replay_stops_in_another_world() {
+        let replay = Replay { world_seeds: vec![11], frame_times_ms: vec![16.7; 10], ..Default::default() };
+        let mut input = InputSource::replaying(replay);
+        input.world_started(12);
+        assert!(matches!(input, InputSource::Keyboard));
+    }
+}
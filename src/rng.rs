@@ -0,0 +1,51 @@
+use std::ops::{Deref, DerefMut};
+
+use bracket_lib::random::RandomNumberGenerator;
+
+/// Mixed into the world seed so gameplay rolls don't mirror the rolls made while generating the world
+const GAMEPLAY_SEED_MIX: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// The single source of randomness for gameplay systems.
+/// It is derived from the world seed so the same world and the same inputs will always play out the same way.
+pub struct GameRng {
+    rng: RandomNumberGenerator,
+}
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self { rng: RandomNumberGenerator::seeded(seed) }
+    }
+
+    pub fn from_world_seed(world_seed: u64) -> Self {
+        Self::seeded(world_seed ^ GAMEPLAY_SEED_MIX)
+    }
+
+    /// Pulls a new seed from the current sequence and restarts from it.
+    /// Used when saving since the generator's position can't be stored, this way a loaded game rolls the same
+    /// numbers as the game that kept running after the save.
+    pub fn reseed_from_sequence(&mut self) -> u64 {
+        let next_seed = self.rng.next_u64();
+        *self = Self::seeded(next_seed);
+        next_seed
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::seeded(0)
+    }
+}
+
+impl Deref for GameRng {
+    type Target = RandomNumberGenerator;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rng
+    }
+}
+
+impl DerefMut for GameRng {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.rng
+    }
+}
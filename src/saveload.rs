@@ -1,10 +1,11 @@
 use std::convert::Infallible;
 use std::fs::{self, create_dir, File};
+use std::io::Write;
 use std::path::Path;
 
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use specs::saveload::{DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker, SimpleMarkerAllocator};
+use specs::saveload::{DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker};
 #[allow(deprecated)] // must be imported so ConvertSaveload works
 use specs::{
     error::NoError,
@@ -14,15 +15,19 @@ use specs::{
 use specs::{Builder, Component, ConvertSaveload, Join, NullStorage, VecStorage, World, WorldExt};
 
 use crate::being::BeingID;
+use crate::chunks::{commit_chunks, discard_unsaved_chunks};
 use crate::components::{
-    AttackBonus, Blocking, Breakable, Consumable, DeleteCondition, EntityStats, Equipable, EquipmentSlots, Equipped,
-    Fishable, GoalMoverAI, Grass, HealthStats, InBag, Interactor, Item, LevelPersistent, Name, Position,
-    RandomWalkerAI, Renderable, Viewshed, Water,
+    AttackBonus, Blocking, Breakable, Consumable, DeleteCondition, Doorway, EntityStats, Equipable, EquipmentSlots,
+    Equipped, Fishable, GoalMoverAI, Grass, HealthStats, InBag, Interactor, Item, LevelPersistent, LightSource, Name,
+    Position, RandomWalkerAI, Renderable, River, Stairs, Viewshed, Water,
 };
-use crate::data_read::ENTITY_DB;
+use crate::data_read::game_data;
+use crate::data_read::prelude::{goal_mover_ai, AUDIOMAN};
 use crate::game_init::PlayerEntity;
+use crate::indexing::SpatialIndex;
 use crate::map::{Map, MapRes};
 use crate::player::Player;
+use crate::rng::GameRng;
 use crate::saveload_menu::LoadedWorld;
 use crate::ui::message_log::MessageLog;
 
@@ -30,7 +35,7 @@ use crate::ui::message_log::MessageLog;
 macro_rules! serialize_individually {
     ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
         $(
-        SerializeComponents::<Infallible, SimpleMarker<SerializeMe>>::serialize(
+        SerializeComponents::<Infallible, _>::serialize(
             &( $ecs.read_storage::<$type>(), ),
             &$data.0,
             &$data.1,
@@ -60,12 +65,20 @@ macro_rules! deserialize_individually {
 #[storage(NullStorage)]
 pub struct SerializeMe {}
 
+/// Marks the entities of a chunk while it is written to or read from its own file.
+/// Kept apart from `SerializeMe` so the ids in a chunk file never clash with the entities already loaded.
+#[derive(Component)]
+#[storage(NullStorage)]
+pub struct SerializeChunk {}
+
 /// Useful when trying to save things that aren't a normal component
 #[derive(Component, Clone, ConvertSaveload)]
 #[storage(VecStorage)]
 pub struct SerializationHelper {
     map: Map,
     message_log: MessageLog,
+    /// `None` in saves made before the gameplay rng was saved
+    rng_seed: Option<u64>,
 }
 
 pub enum SaveAction {
@@ -105,9 +118,10 @@ pub fn any_save_game_exists() -> bool {
 pub fn save_game(ecs: &mut World) {
     let MapRes(map) = ecs.get_mut::<MapRes>().unwrap().clone();
     let message_log = ecs.get_mut::<MessageLog>().unwrap().clone();
+    let rng_seed = ecs.get_mut::<GameRng>().unwrap().reseed_from_sequence();
     let savehelper = ecs
         .create_entity()
-        .with(SerializationHelper { map, message_log })
+        .with(SerializationHelper { map, message_log, rng_seed: Some(rng_seed) })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
@@ -129,15 +143,10 @@ pub fn save_game(ecs: &mut World) {
             }
         }
     };
-    {
-        let data = (ecs.entities(), ecs.read_storage::<SimpleMarker<SerializeMe>>());
-
-        let mut serializer = serde_json::Serializer::new(writer);
-        #[rustfmt::skip]
-        serialize_individually!(ecs, serializer, data, Position, Renderable, LevelPersistent, EntityStats, Blocking, Fishable,
-                                Name, HealthStats, Breakable, DeleteCondition, Item, InBag, Consumable, Equipped, Equipable,
-                                BeingID, Viewshed,
-                                Player, EquipmentSlots, Water, Grass, Interactor, AttackBonus, SerializationHelper);
+    let mut serializer = serde_json::Serializer::new(writer);
+    write_components::<SimpleMarker<SerializeMe>, _>(ecs, &mut serializer);
+    if let Some(wc) = ecs.read_resource::<MapRes>().0.world_config() {
+        commit_chunks(&wc.world_name);
     }
     info!("{} was saved", file_name);
 
@@ -160,18 +169,7 @@ pub fn load_game(ecs: &mut World, file_name: String) {
 
     let mut deserializer = serde_json::Deserializer::from_str(&save_data);
 
-    {
-        let mut d = (
-            &mut ecs.entities(),
-            &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(),
-            &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>(),
-        );
-        #[rustfmt::skip]
-        deserialize_individually!(ecs, deserializer, d, Position, Renderable, LevelPersistent, EntityStats, Blocking, Fishable,
-                                Name, HealthStats, Breakable, DeleteCondition, Item, InBag, Consumable, Equipped, Equipable,
-                                BeingID, Viewshed,
-                                Player, EquipmentSlots, Water, Grass, Interactor, AttackBonus, SerializationHelper);
-    }
+    read_components::<SimpleMarker<SerializeMe>, _>(ecs, &mut deserializer);
 
     // This is going to be replaced when it gets loaded below but it cannot be inserted in there
     // since some borrowing is going on
@@ -188,50 +186,29 @@ pub fn load_game(ecs: &mut World, file_name: String) {
             let mut map = ecs.write_resource::<MapRes>();
             *map = MapRes(helper_data.map.clone());
             map.0.tile_entities = vec![Vec::new(); map.0.width * map.0.height];
+            ecs.write_resource::<SpatialIndex>().request_rebuild();
 
             let mut msg_log = ecs.write_resource::<MessageLog>();
             *msg_log = helper_data.message_log.clone();
-            debug!("Message and map loaded Successful");
+
+            if let Some(wc) = map.0.world_config() {
+                discard_unsaved_chunks(&wc.world_name);
+            }
+
+            let rng_seed = helper_data.rng_seed.unwrap_or_else(|| {
+                warn!("Save was made before the rng was saved, continuing from the world seed");
+                map.0.world_config().map_or(0, |wc| wc.seed)
+            });
+            let mut rng = ecs.write_resource::<GameRng>();
+            *rng = GameRng::seeded(rng_seed);
+            AUDIOMAN.lock().unwrap().reseed(rng_seed);
+            debug!("Message, map and rng loaded Successful");
 
             delete_me = Some(helper_e);
         } else {
             error!("No map found when loading the savegame.");
         }
 
-        // Recreate AI components for beings
-        let beings = ecs.write_storage::<BeingID>();
-        let edb = &ENTITY_DB.lock().unwrap();
-        for (being_e, being_id) in (&entities, &beings).join() {
-            match edb.beings.get_by_id(being_id.0) {
-                Some(being_info) => {
-                    if let Some(ai) = &being_info.ai {
-                        match ai.start_mode.as_str() {
-                            "random_walk" => {
-                                let mut random_walk = ecs.write_storage::<RandomWalkerAI>();
-                                let _ = random_walk.insert(being_e, RandomWalkerAI {});
-                            }
-                            "goal" => {
-                                let goals = match &ai.goals {
-                                    Some(goals) => {
-                                        goals.iter().map(|goal| Name(goal.to_string())).collect::<Vec<Name>>()
-                                    }
-                                    None => {
-                                        warn!("{} has Goal ai type but no defined goals", &being_info.name);
-                                        continue;
-                                    }
-                                };
-                                let mut goal_movers = ecs.write_storage::<GoalMoverAI>();
-                                let _ = goal_movers
-                                    .insert(being_e, GoalMoverAI::with_desires(&goals, ai.goal_range.unwrap()));
-                            }
-                            _ => (),
-                        }
-                    }
-                }
-                None => continue,
-            }
-        }
-
         if let Some((player_e, _)) = (&entities, &player).join().next() {
             let mut player_e_res = ecs.write_resource::<PlayerEntity>();
             *player_e_res = PlayerEntity(player_e);
@@ -241,7 +218,65 @@ pub fn load_game(ecs: &mut World, file_name: String) {
         }
     }
 
+    recreate_being_ai(ecs, |_| true);
+
     ecs.insert(LoadedWorld { file_name: Some(file_name) });
     debug!("Loading game complete");
     ecs.delete_entity(delete_me.unwrap()).expect("Unable to delete helper after loading.");
 }
+
+/// Writes every saved component of the entities marked with `M`
+pub(crate) fn write_components<M: Marker, W: Write>(ecs: &World, serializer: &mut serde_json::Serializer<W>) {
+    let data = (ecs.entities(), ecs.read_storage::<M>());
+    #[rustfmt::skip]
+    serialize_individually!(ecs, *serializer, data, Position, Renderable, LevelPersistent, EntityStats, Blocking, Fishable,
+                            Name, HealthStats, Breakable, DeleteCondition, Item, InBag, Consumable, Equipped, Equipable,
+                            BeingID, Viewshed, LightSource,
+                            Player, EquipmentSlots, Water, River, Grass, Doorway, Stairs, Interactor, AttackBonus, SerializationHelper);
+}
+
+/// Reads back what `write_components` wrote, the new entities are marked with `M`
+pub(crate) fn read_components<'de, M: Marker, R: serde_json::de::Read<'de>>(
+    ecs: &World,
+    deserializer: &mut serde_json::Deserializer<R>,
+) {
+    let mut d = (&mut ecs.entities(), &mut ecs.write_storage::<M>(), &mut ecs.write_resource::<M::Allocator>());
+    #[rustfmt::skip]
+    deserialize_individually!(ecs, *deserializer, d, Position, Renderable, LevelPersistent, EntityStats, Blocking, Fishable,
+                            Name, HealthStats, Breakable, DeleteCondition, Item, InBag, Consumable, Equipped, Equipable,
+                            BeingID, Viewshed, LightSource,
+                            Player, EquipmentSlots, Water, River, Grass, Doorway, Stairs, Interactor, AttackBonus, SerializationHelper);
+}
+
+/// AI is not saved so it is recreated from the static data of the beings `recreate` accepts
+pub(crate) fn recreate_being_ai(ecs: &World, recreate: impl Fn(Entity) -> bool) {
+    let entities = ecs.entities();
+    let beings = ecs.read_storage::<BeingID>();
+    let edb = game_data();
+    for (being_e, being_id) in (&entities, &beings).join() {
+        if !recreate(being_e) {
+            continue;
+        }
+        match edb.beings.get_by_id(being_id.0) {
+            Some(being_info) => {
+                if let Some(ai) = &being_info.ai {
+                    match ai.start_mode.as_str() {
+                        "random_walk" => {
+                            let mut random_walk = ecs.write_storage::<RandomWalkerAI>();
+                            let _ = random_walk.insert(being_e, RandomWalkerAI {});
+                        }
+                        "goal" => match goal_mover_ai(&being_info.name, ai) {
+                            Ok(goal_mover) => {
+                                let mut goal_movers = ecs.write_storage::<GoalMoverAI>();
+                                let _ = goal_movers.insert(being_e, goal_mover);
+                            }
+                            Err(e) => error!("{} was loaded without its ai: {}", being_info.name, e),
+                        },
+                        _ => (),
+                    }
+                }
+            }
+            None => continue,
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use std::fs;
 
-use bracket_lib::prelude::{BTerm, VirtualKeyCode};
+use bracket_lib::prelude::BTerm;
 
-use crate::saveload::SAVE_PATH;
+use crate::{
+    keybindings::{Action, BindingContext, Keybindings},
+    saveload::SAVE_PATH,
+};
 
 pub fn get_save_games() -> Vec<String> {
     let paths = fs::read_dir(SAVE_PATH).unwrap();
@@ -28,16 +31,12 @@ pub enum LoadMenuAction {
     Waiting,
 }
 
-pub fn p_input_load_game_menu(ctx: &mut BTerm) -> LoadMenuAction {
-    if let Some(key) = ctx.key {
-        return match key {
-            VirtualKeyCode::Down | VirtualKeyCode::S => LoadMenuAction::MoveDown,
-            VirtualKeyCode::Up | VirtualKeyCode::W => LoadMenuAction::MoveUp,
-            VirtualKeyCode::Return => LoadMenuAction::Select,
-            VirtualKeyCode::Escape => LoadMenuAction::Back,
-            _ => LoadMenuAction::Waiting,
-        };
+pub fn p_input_load_game_menu(ctx: &mut BTerm, binds: &Keybindings) -> LoadMenuAction {
+    match binds.pressed(BindingContext::Menu, ctx) {
+        Some(Action::MenuDown) => LoadMenuAction::MoveDown,
+        Some(Action::MenuUp) => LoadMenuAction::MoveUp,
+        Some(Action::MenuSelect) => LoadMenuAction::Select,
+        Some(Action::MenuBack) => LoadMenuAction::Back,
+        _ => LoadMenuAction::Waiting,
     }
-
-    LoadMenuAction::Waiting
 }
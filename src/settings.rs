@@ -1,4 +1,4 @@
-use crate::{CL_INTERACTABLES, FONT_INTERACTABLES, FONT_INTERACTABLES_OUTLINE};
+use crate::{AppState, CL_INTERACTABLES, FONT_INTERACTABLES, FONT_INTERACTABLES_OUTLINE};
 use bracket_lib::terminal::BTerm;
 use std::fs;
 
@@ -44,45 +44,46 @@ impl SettingsConfig {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub enum TextFonts {
+    #[default]
     Zaratustra,
     Terminal,
 }
 
-impl Default for TextFonts {
-    fn default() -> Self {
-        Self::Zaratustra
-    }
-}
-
 /// How the interactables sprites will be drawn on screen
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub enum SpriteMode {
+    #[default]
     Outline,
     Blocked,
 }
 
-impl Default for SpriteMode {
-    fn default() -> Self {
-        Self::Outline
-    }
-}
-
 #[derive(Clone, Eq, PartialEq, Copy)]
 pub enum SettingsSelection {
     SpriteMode,
+    Keybindings,
 }
 
 pub enum SettingsAction {
     Selected,
+    Hovering(SettingsSelection),
     Waiting,
     ReturnToMainMenu,
 }
 
-pub fn handle_setting_selected(setting: &SettingsSelection, cfg: &mut SettingsConfig, ctx: &mut BTerm) {
+/// Returns the state to move to when the setting opens its own menu
+pub fn handle_setting_selected(
+    setting: &SettingsSelection,
+    cfg: &mut SettingsConfig,
+    ctx: &mut BTerm,
+) -> Option<AppState> {
     match setting {
-        SettingsSelection::SpriteMode => toggle_sprite_mode(cfg, ctx),
+        SettingsSelection::SpriteMode => {
+            toggle_sprite_mode(cfg, ctx);
+            None
+        }
+        SettingsSelection::Keybindings => Some(AppState::KeybindingsMenu { hovering: 0, listening: false }),
     }
 }
 
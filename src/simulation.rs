@@ -0,0 +1,629 @@
+//! A headless way to run the game without a window or `BTerm`.
+//! The world is built the same way as `main` does and scripted commands are fed in place of key presses.
+//! Each call to `step` is one frame with a fixed delta time, so turns advance the same way every run.
+//! Nothing is presented to a player, the `GameEvents` sent during the last step are left for the caller to read.
+//! Chunks unloaded by a simulation are saved under its world name and deleted when it is dropped, so every
+//! simulation running at once needs a world name of its own.
+
+use std::{
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use bracket_lib::terminal::BTerm;
+use specs::{Entity, Join, RunNow, World, WorldExt};
+
+use crate::{
+    chunks::{delete_world_chunks, stream_chunks},
+    components::{InBag, Interactor, InteractorMode, Item, Position, SelectedInventoryItem},
+    config::{InventoryConfig, SortMode},
+    data_read::{initialize_game_databases, DataError},
+    events::GameEvents,
+    game_init::{initialize_ecs, initialize_new_game_world, NewWorldError, PlayerEntity},
+    inventory::{
+        check_inventory_selection, handle_one_item_actions, handle_two_item_actions, select_item, InventoryResponse,
+        SelectionStatus, UseMenuResult,
+    },
+    items::{ItemID, ItemQty, ItemSpawnerSystem},
+    keybindings::Keybindings,
+    map::Map,
+    map_gen::{gen_world, WorldConfig},
+    player::{
+        check_player_finished, p_input_activity, p_input_game, player_game_action, player_wait,
+        switch_interaction_mode, try_move_player, try_pickup, PlayerResponse,
+    },
+    systems::{run_eof_systems, GameDispatchers},
+    time::DeltaTime,
+    turn_counter_incr, AppState, TurnCounter,
+};
+
+/// The time each simulated frame takes, matches the fps cap of the windowed game
+pub const SIM_FRAME_TIME: Duration = Duration::from_micros(16_667);
+
+/// Generating a world sets the shared noises up for its seed, two worlds made at once would be generated from each
+/// other's noises
+static WORLD_GEN: Mutex<()> = Mutex::new(());
+
+/// Held while loading the raws and generating a world so worlds made at the same time come out the same every run
+fn lock_world_gen() -> MutexGuard<'static, ()> {
+    WORLD_GEN.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Runs `generate` on an ecs with nothing else in it once the raws are loaded, for testing world generation without
+/// starting a game
+pub fn generate_in<T>(generate: impl FnOnce(&mut World) -> T) -> Result<(World, T), DataError> {
+    let _generating = lock_world_gen();
+    initialize_game_databases()?;
+    let mut ecs = initialize_ecs();
+    let generated = generate(&mut ecs);
+    ecs.maintain();
+    Ok((ecs, generated))
+}
+
+/// Generates the chunks around the center of the world
+pub fn generate_world(wc: &WorldConfig) -> Result<(World, Map), DataError> {
+    generate_in(|ecs| gen_world(ecs, wc, &Position::new(wc.width / 2, wc.height / 2)))
+}
+
+/// A scripted stand in for the player's key presses
+#[derive(Clone)]
+pub enum SimCommand {
+    /// Moves the player by the delta, breaking or fishing whatever is in the way like the movement keys do
+    Move(i32, i32),
+    /// Attacks whatever is blocking the delta, regardless of the player's current interaction mode
+    Attack(i32, i32),
+    Pickup,
+    Wait,
+    ToggleInteractMode,
+    /// Performs an action on the nth item in the inventory using the inventory's sort order
+    UseItem {
+        idx: usize,
+        action: ItemUse,
+    },
+    /// Crafts the nth item with the mth item in the inventory
+    Craft {
+        first: usize,
+        second: usize,
+    },
+    /// The action key pressed during an activity such as the fishing minigame
+    GameAction,
+    /// A frame where nothing is pressed
+    Idle,
+}
+
+/// The actions of the use menu that change the world with one item, crafting takes two and is `SimCommand::Craft`
+#[derive(Clone, Copy)]
+pub enum ItemUse {
+    Drop,
+    Equip,
+    Consume,
+}
+
+impl From<ItemUse> for UseMenuResult {
+    fn from(value: ItemUse) -> Self {
+        match value {
+            ItemUse::Drop => UseMenuResult::Drop,
+            ItemUse::Equip => UseMenuResult::Equip,
+            ItemUse::Consume => UseMenuResult::Consume,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SimMode {
+    InGame,
+    ActivityBound,
+}
+
+pub struct Simulation {
+    pub ecs: World,
+    pub mode: SimMode,
+    world_name: String,
+    inventory_cfg: InventoryConfig,
+    dispatchers: GameDispatchers,
+}
+
+impl Simulation {
+    /// Builds a brand new world the same way the `NewGameStart` state does
+    pub fn new(world_cfg: &WorldConfig) -> Result<Self, NewWorldError> {
+        let _generating = lock_world_gen();
+        initialize_game_databases()?;
+
+        let mut ecs = initialize_ecs();
+        ecs.insert(AppState::InGame);
+        ecs.insert(DeltaTime(SIM_FRAME_TIME));
+        initialize_new_game_world(&mut ecs, world_cfg)?;
+
+        let mut item_spawner = ItemSpawnerSystem;
+        item_spawner.run_now(&ecs);
+        ecs.maintain();
+
+        Ok(Self {
+            ecs,
+            mode: SimMode::InGame,
+            world_name: world_cfg.world_name.clone(),
+            inventory_cfg: InventoryConfig { sort_mode: SortMode::NameABC },
+            dispatchers: GameDispatchers::new(),
+        })
+    }
+
+    /// Runs every command in order, one frame each
+    pub fn run(&mut self, commands: &[SimCommand]) {
+        for command in commands {
+            self.step(command.clone());
+        }
+    }
+
+    /// Runs a single frame of the game using `command` as the player's input
+    pub fn step(&mut self, command: SimCommand) {
+        self.ecs.write_resource::<GameEvents>().clear();
+        match self.mode {
+            SimMode::InGame => self.step_ingame(command),
+            SimMode::ActivityBound => self.step_activity(command),
+        }
+        self.end_step();
+    }
+
+    /// Runs a single frame of the game reading the key pressed on `ctx` through `binds` like the game does
+    pub fn step_keys(&mut self, ctx: &mut BTerm, binds: &Keybindings) {
+        self.ecs.write_resource::<GameEvents>().clear();
+        match self.mode {
+            SimMode::InGame => {
+                let response = p_input_game(&mut self.ecs, ctx, binds);
+                self.respond_ingame(response);
+            }
+            SimMode::ActivityBound => {
+                p_input_activity(&mut self.ecs, ctx, binds);
+                self.respond_activity();
+            }
+        }
+        self.end_step();
+    }
+
+    fn end_step(&mut self) {
+        let mut delta_timer = self.ecs.write_resource::<DeltaTime>();
+        delta_timer.0 = SIM_FRAME_TIME;
+        drop(delta_timer);
+        self.ecs.maintain();
+    }
+
+    fn step_ingame(&mut self, command: SimCommand) {
+        let response = match command {
+            SimCommand::Move(dx, dy) => try_move_player(dx, dy, &mut self.ecs),
+            SimCommand::Attack(dx, dy) => self.attack(dx, dy),
+            SimCommand::Pickup => try_pickup(&mut self.ecs),
+            SimCommand::Wait => player_wait(&mut self.ecs),
+            SimCommand::ToggleInteractMode => {
+                switch_interaction_mode(&mut self.ecs);
+                PlayerResponse::Waiting
+            }
+            SimCommand::UseItem { idx, action } => {
+                self.use_item(idx, action);
+                PlayerResponse::Waiting
+            }
+            SimCommand::Craft { first, second } => {
+                self.craft(first, second);
+                PlayerResponse::Waiting
+            }
+            SimCommand::GameAction | SimCommand::Idle => PlayerResponse::Waiting,
+        };
+        self.respond_ingame(response);
+    }
+
+    fn respond_ingame(&mut self, response: PlayerResponse) {
+        match response {
+            PlayerResponse::Waiting => {}
+            PlayerResponse::TurnAdvance => {
+                turn_counter_incr(&mut self.ecs);
+                self.dispatchers.response.dispatch(&self.ecs);
+                stream_chunks(&mut self.ecs);
+            }
+            PlayerResponse::StateChange(AppState::ActivityBound { .. }) => self.mode = SimMode::ActivityBound,
+            PlayerResponse::StateChange(_) => {}
+        }
+        self.dispatchers.ingame.dispatch(&self.ecs);
+        run_eof_systems(&mut self.ecs);
+    }
+
+    fn step_activity(&mut self, command: SimCommand) {
+        if let SimCommand::GameAction = command {
+            player_game_action(&mut self.ecs);
+        }
+        self.respond_activity();
+    }
+
+    fn respond_activity(&mut self) {
+        self.dispatchers.activity_bound.dispatch(&self.ecs);
+
+        if check_player_finished(&mut self.ecs) {
+            turn_counter_incr(&mut self.ecs);
+            self.dispatchers.response.dispatch(&self.ecs);
+            self.mode = SimMode::InGame;
+        }
+    }
+
+    fn attack(&mut self, dx: i32, dy: i32) -> PlayerResponse {
+        let player_entity = self.player();
+        let prev_mode = self.set_interact_mode(&player_entity, InteractorMode::Agressive);
+        let response = try_move_player(dx, dy, &mut self.ecs);
+        if let Some(mode) = prev_mode {
+            self.set_interact_mode(&player_entity, mode);
+        }
+        response
+    }
+
+    fn set_interact_mode(&mut self, player_entity: &Entity, mode: InteractorMode) -> Option<InteractorMode> {
+        let mut interactors = self.ecs.write_storage::<Interactor>();
+        interactors.get_mut(*player_entity).map(|interactor| std::mem::replace(&mut interactor.mode, mode))
+    }
+
+    /// Mirrors opening the inventory, selecting an item and choosing an action for it
+    fn use_item(&mut self, idx: usize, action: ItemUse) {
+        let player_entity = self.player();
+        select_item(&player_entity, idx, &mut self.ecs, &self.inventory_cfg);
+        if !self.set_intended_action(&player_entity, action.into()) {
+            return;
+        }
+
+        handle_one_item_actions(&mut self.ecs);
+        self.dispatchers.item_action.dispatch(&self.ecs);
+    }
+
+    fn craft(&mut self, first: usize, second: usize) {
+        let player_entity = self.player();
+        select_item(&player_entity, first, &mut self.ecs, &self.inventory_cfg);
+        if !self.set_intended_action(&player_entity, UseMenuResult::Craft) {
+            return;
+        }
+
+        match select_item(&player_entity, second, &mut self.ecs, &self.inventory_cfg) {
+            InventoryResponse::SecondItemSelected { second_item } => {
+                handle_two_item_actions(&mut self.ecs, &second_item);
+                self.dispatchers.crafting.dispatch(&self.ecs);
+            }
+            _ => {
+                self.ecs.write_storage::<SelectedInventoryItem>().remove(player_entity);
+                self.dispatchers.inventory.dispatch(&self.ecs);
+            }
+        }
+    }
+
+    /// Returns false when the player has no item selected to act on
+    fn set_intended_action(&mut self, player_entity: &Entity, action: UseMenuResult) -> bool {
+        if check_inventory_selection(&self.ecs) != SelectionStatus::SelectionWithoutAction {
+            return false;
+        }
+        let mut selected_idxs = self.ecs.write_storage::<SelectedInventoryItem>();
+        match selected_idxs.get_mut(*player_entity) {
+            Some(selection) => {
+                selection.intended_action = Some(action);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn player(&self) -> Entity {
+        self.ecs.read_resource::<PlayerEntity>().0
+    }
+
+    pub fn turn(&self) -> usize {
+        self.ecs.read_resource::<TurnCounter>().0
+    }
+
+    pub fn player_position(&self) -> Option<Position> {
+        self.ecs.read_storage::<Position>().get(self.player()).copied()
+    }
+
+    /// The total qty of the item with `id` in the player's bag
+    pub fn inventory_qty(&self, id: ItemID) -> ItemQty {
+        let player_entity = self.player();
+        let items = self.ecs.read_storage::<Item>();
+        let in_bags = self.ecs.read_storage::<InBag>();
+        (&items, &in_bags)
+            .join()
+            .filter(|(item, bag)| bag.owner == player_entity && item.id == id)
+            .fold(ItemQty(0), |total, (item, _)| total + item.qty)
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        delete_world_chunks(&self.world_name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bracket_lib::prelude::Point;
+    use specs::Builder;
+
+    use super::*;
+    use crate::{
+        being::BeingID,
+        components::{Blocking, Equipped, Fishable, HealthStats, Name},
+        events::GameEvent,
+        items::{ItemSpawner, SpawnType},
+        map::MapRes,
+    };
+
+    /// Every test needs its own world name since tests run in parallel and the chunks are saved by name
+    fn test_world(name: &str) -> WorldConfig {
+        WorldConfig { world_name: format!("simulation_test_{}", name), seed: 7, ..Default::default() }
+    }
+
+    fn new_sim(name: &str) -> Simulation {
+        Simulation::new(&test_world(name)).unwrap()
+    }
+
+    #[test]
+    fn waiting_advances_turn() {
+        let mut sim = new_sim("waiting_advances_turn");
+        sim.run(&[SimCommand::Wait, SimCommand::Idle, SimCommand::Wait]);
+        assert_eq!(sim.turn(), 2);
+    }
+
+    #[test]
+    fn player_starts_with_fishing_rod() {
+        let sim = new_sim("player_starts_with_fishing_rod");
+        assert_eq!(sim.inventory_qty(ItemID(201)), ItemQty(1));
+    }
+
+    #[test]
+    fn same_seed_plays_out_the_same() {
+        let commands = vec![SimCommand::Wait; 40];
+        let mut first = new_sim("same_seed_first");
+        let mut second = new_sim("same_seed_second");
+        first.run(&commands);
+        second.run(&commands);
+        assert_eq!(being_positions(&first), being_positions(&second));
+    }
+
+    fn being_positions(sim: &Simulation) -> Vec<(String, Position)> {
+        let names = sim.ecs.read_storage::<Name>();
+        let positions = sim.ecs.read_storage::<Position>();
+        let beings = sim.ecs.read_storage::<BeingID>();
+        (&names, &positions, &beings).join().map(|(name, pos, _)| (name.0.clone(), *pos)).collect()
+    }
+
+    #[test]
+    fn pickup_moves_ground_item_into_bag() {
+        let mut sim = new_sim("pickup_moves_ground_item_into_bag");
+        let player_pos = sim.player_position().unwrap();
+        sim.ecs.write_resource::<ItemSpawner>().request(ItemID(3), SpawnType::OnGround(player_pos));
+        // first frame spawns the item, the second indexes it onto the map
+        sim.run(&[SimCommand::Idle, SimCommand::Idle, SimCommand::Pickup]);
+        assert_eq!(sim.inventory_qty(ItemID(3)), ItemQty(1));
+        let events = sim.ecs.read_resource::<GameEvents>();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            GameEvent::ItemPickedUp { picker, item: ItemID(3), first_of_kind: true, .. } if *picker == sim.player()
+        )));
+    }
+
+    /// A delta from the player to a neighbouring tile with nothing on it
+    fn open_neighbour(sim: &Simulation) -> (i32, i32) {
+        let player_pos = sim.player_position().unwrap();
+        let map = sim.ecs.read_resource::<MapRes>();
+        [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .find(|(dx, dy)| {
+                let target = Point::new(player_pos.x as i32 + dx, player_pos.y as i32 + dy);
+                map.0.in_bounds(target) && map.0.first_entity_in_pos(&Position::from(target)).is_none()
+            })
+            .expect("the player should spawn next to an open tile")
+    }
+
+    fn neighbour_position(sim: &Simulation, (dx, dy): (i32, i32)) -> Position {
+        let player_pos = sim.player_position().unwrap();
+        Position::from(Point::new(player_pos.x as i32 + dx, player_pos.y as i32 + dy))
+    }
+
+    /// Where the item with `id` is in the player's bag using the inventory's sort order
+    fn bag_index(sim: &Simulation, id: ItemID) -> usize {
+        let player_entity = sim.player();
+        let items = sim.ecs.read_storage::<Item>();
+        let in_bags = sim.ecs.read_storage::<InBag>();
+        let names = sim.ecs.read_storage::<Name>();
+        let mut bag: Vec<(&Name, ItemID)> = (&items, &in_bags, &names)
+            .join()
+            .filter(|(_, bag, _)| bag.owner == player_entity)
+            .map(|(item, _, name)| (name, item.id))
+            .collect();
+        bag.sort_by(|a, b| a.0.cmp(b.0));
+        bag.iter().position(|(_, item_id)| *item_id == id).expect("the item should be in the player's bag")
+    }
+
+    #[test]
+    fn attack_lowers_target_hp() {
+        let mut sim = new_sim("attack_lowers_target_hp");
+        let delta = open_neighbour(&sim);
+        let target_pos = neighbour_position(&sim, delta);
+        let target = sim
+            .ecs
+            .create_entity()
+            .with(Name("Training Dummy".to_string()))
+            .with(target_pos)
+            .with(Blocking {})
+            .with(HealthStats::new(30, 0))
+            .build();
+        // the dummy has to be indexed onto the map before it can be attacked
+        sim.step(SimCommand::Idle);
+        sim.step(SimCommand::Attack(delta.0, delta.1));
+
+        let events = sim.ecs.read_resource::<GameEvents>();
+        let dealt = events
+            .iter()
+            .find_map(|event| match event {
+                GameEvent::DamageDealt { attacker, target: hit, amount }
+                    if *attacker == sim.player() && *hit == target =>
+                {
+                    Some(*amount)
+                }
+                _ => None,
+            })
+            .expect("attacking the dummy should deal damage");
+        assert!(dealt > 0);
+        let healths = sim.ecs.read_storage::<HealthStats>();
+        assert_eq!(healths.get(target).unwrap().hp, 30 - dealt);
+        assert_eq!(sim.turn(), 1);
+    }
+
+    #[test]
+    fn killing_a_being_sends_entity_died() {
+        let mut sim = new_sim("killing_a_being_sends_entity_died");
+        let delta = open_neighbour(&sim);
+        let target_pos = neighbour_position(&sim, delta);
+        let target = sim
+            .ecs
+            .create_entity()
+            .with(Name("Training Dummy".to_string()))
+            .with(target_pos)
+            .with(Blocking {})
+            .with(HealthStats::new(1, 0))
+            .build();
+        sim.step(SimCommand::Idle);
+        // the attack, the damage it does and the clean up of the dead all happen in the same frame
+        sim.step(SimCommand::Attack(delta.0, delta.1));
+
+        let events = sim.ecs.read_resource::<GameEvents>();
+        let died = events.iter().any(|event| {
+            matches!(event, GameEvent::EntityDied { entity, name } if *entity == target && name.0 == "Training Dummy")
+        });
+        assert!(died, "the dummy should have died from the attack");
+        assert!(!sim.ecs.is_alive(target));
+    }
+
+    /// Puts one of each item in the player's bag, they are spawned at the end of the frame
+    fn give_items(sim: &mut Simulation, ids: &[ItemID]) {
+        let player_entity = sim.player();
+        let mut item_spawner = sim.ecs.write_resource::<ItemSpawner>();
+        for id in ids {
+            item_spawner.request(*id, SpawnType::InBag(player_entity));
+        }
+        drop(item_spawner);
+        sim.step(SimCommand::Idle);
+    }
+
+    #[test]
+    fn crafting_replaces_ingredients_with_output() {
+        let mut sim = new_sim("crafting_replaces_ingredients_with_output");
+        let player_entity = sim.player();
+        give_items(&mut sim, &[ItemID(0), ItemID(2)]);
+        assert_eq!(sim.inventory_qty(ItemID(0)), ItemQty(1));
+        assert_eq!(sim.inventory_qty(ItemID(2)), ItemQty(1));
+
+        // Flint with a Stick makes a Sharp Stick, only the stick is used up
+        let first = bag_index(&sim, ItemID(0));
+        let second = bag_index(&sim, ItemID(2));
+        sim.step(SimCommand::Craft { first, second });
+        assert!(sim.ecs.read_resource::<GameEvents>().iter().any(|event| matches!(
+            event,
+            GameEvent::CraftSucceeded { crafter, output: ItemID(100) } if *crafter == player_entity
+        )));
+        assert_eq!(sim.inventory_qty(ItemID(100)), ItemQty(1));
+        assert_eq!(sim.inventory_qty(ItemID(0)), ItemQty(1));
+        assert_eq!(sim.inventory_qty(ItemID(2)), ItemQty(0));
+        assert_eq!(sim.inventory_qty(ItemID(201)), ItemQty(1));
+    }
+
+    #[test]
+    fn fishing_sends_events_until_the_activity_ends() {
+        let mut sim = new_sim("fishing_sends_events_until_the_activity_ends");
+        let player_entity = sim.player();
+        let delta = open_neighbour(&sim);
+        let bubble_pos = neighbour_position(&sim, delta);
+        sim.ecs.create_entity().with(bubble_pos).with(Fishable { time_left: Duration::from_secs(600) }).build();
+        sim.step(SimCommand::Idle);
+        sim.step(SimCommand::Move(delta.0, delta.1));
+        assert_eq!(sim.mode, SimMode::ActivityBound);
+        // the player only moves once fishing is over
+        assert_ne!(sim.player_position(), Some(bubble_pos));
+
+        let (mut hooked, mut reeled, mut caught, mut escaped, mut exhausted) = (false, false, false, false, false);
+        for _ in 0..5000 {
+            if sim.mode == SimMode::InGame {
+                break;
+            }
+            sim.step(SimCommand::GameAction);
+            for event in sim.ecs.read_resource::<GameEvents>().iter() {
+                match event {
+                    GameEvent::FishHooked { fisher, .. } if *fisher == player_entity => hooked = true,
+                    GameEvent::FishingReel { fisher, .. } if *fisher == player_entity => reeled = true,
+                    GameEvent::FishCaught { fisher, item: ItemID(3) } if *fisher == player_entity => caught = true,
+                    GameEvent::FishEscaped { fisher } if *fisher == player_entity => escaped = true,
+                    GameEvent::FishingAttemptsExhausted { fisher } if *fisher == player_entity => exhausted = true,
+                    _ => {}
+                }
+            }
+        }
+
+        assert_eq!(sim.mode, SimMode::InGame, "fishing should finish");
+        assert_eq!(sim.turn(), 1);
+        if hooked {
+            assert!(reeled, "pressing the action key on a hooked fish should reel");
+            assert!(caught != escaped, "a hooked fish is either caught or escapes");
+            assert!(!exhausted);
+        } else {
+            assert!(exhausted, "fishing without a bite should run out of attempts");
+            assert!(!caught && !escaped);
+        }
+        if caught {
+            sim.step(SimCommand::Idle);
+            assert_eq!(sim.inventory_qty(ItemID(3)), ItemQty(1));
+        }
+    }
+
+    #[test]
+    fn toggling_interact_mode_switches_to_aggressive() {
+        let mut sim = new_sim("toggle_interact_mode");
+        sim.step(SimCommand::ToggleInteractMode);
+        let interactors = sim.ecs.read_storage::<Interactor>();
+        assert!(matches!(interactors.get(sim.player()).unwrap().mode, InteractorMode::Agressive));
+    }
+
+    #[test]
+    fn dropping_an_item_leaves_it_under_the_player() {
+        let mut sim = new_sim("drop_item");
+        let player_pos = sim.player_position();
+        let rod = bag_index(&sim, ItemID(201));
+        sim.step(SimCommand::UseItem { idx: rod, action: ItemUse::Drop });
+        assert_eq!(sim.inventory_qty(ItemID(201)), ItemQty(0));
+
+        let items = sim.ecs.read_storage::<Item>();
+        let positions = sim.ecs.read_storage::<Position>();
+        assert!((&items, &positions).join().any(|(item, pos)| item.id == ItemID(201) && Some(*pos) == player_pos));
+    }
+
+    #[test]
+    fn equipping_a_torch_puts_it_on_the_player() {
+        let mut sim = new_sim("equip_item");
+        give_items(&mut sim, &[ItemID(101)]);
+        let torch = bag_index(&sim, ItemID(101));
+        sim.step(SimCommand::UseItem { idx: torch, action: ItemUse::Equip });
+
+        let items = sim.ecs.read_storage::<Item>();
+        let equipped = sim.ecs.read_storage::<Equipped>();
+        assert!((&items, &equipped)
+            .join()
+            .any(|(item, equipped)| item.id == ItemID(101) && equipped.on == sim.player()));
+    }
+
+    #[test]
+    fn eating_a_bass_heals_and_uses_it_up() {
+        let mut sim = new_sim("consume_item");
+        give_items(&mut sim, &[ItemID(3)]);
+        let player_entity = sim.player();
+        let hurt_hp = {
+            let mut healths = sim.ecs.write_storage::<HealthStats>();
+            let health = healths.get_mut(player_entity).unwrap();
+            health.hp /= 2;
+            health.hp
+        };
+        let bass = bag_index(&sim, ItemID(3));
+        sim.step(SimCommand::UseItem { idx: bass, action: ItemUse::Consume });
+
+        assert_eq!(sim.inventory_qty(ItemID(3)), ItemQty(0));
+        assert!(sim.ecs.read_storage::<HealthStats>().get(player_entity).unwrap().hp > hurt_hp);
+    }
+}
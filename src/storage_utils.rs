@@ -5,6 +5,9 @@ use specs::{storage::MaskedStorage, Component, Entity, Storage};
 pub trait MaybeInsert<T: Component> {
     /// Inserts a component wrapped in an Option if it is Some(T)
     fn maybe_insert(&mut self, _onto: Entity, _component: Option<T>) {}
+
+    /// Inserts the component if it is Some(T) otherwise removes the existing component
+    fn insert_or_remove(&mut self, _onto: Entity, _component: Option<T>) {}
 }
 
 impl<T, D> MaybeInsert<T> for Storage<'_, T, D>
@@ -17,4 +20,15 @@ where
             let _ = self.insert(onto, component);
         }
     }
+
+    fn insert_or_remove(&mut self, onto: Entity, maybe_component: Option<T>) {
+        match maybe_component {
+            Some(component) => {
+                let _ = self.insert(onto, component);
+            }
+            None => {
+                self.remove(onto);
+            }
+        }
+    }
 }
@@ -0,0 +1,151 @@
+//! The dispatchers that make up a game turn or frame, each one is tied to the `AppState` it runs in.
+//! All of the system ordering lives here, a system only waits on the systems listed as its dependencies so
+//! anything without a conflict is free to run in parallel.
+//! Systems that roll the `GameRng` must depend on each other so their rolls always happen in the same order.
+//! These only depend on the `World` so they can be ran by both the windowed game and the headless `Simulation`.
+
+use specs::{Dispatcher, DispatcherBuilder, World, WorldExt};
+
+use crate::{
+    being::{GoalFindEntities, GoalMoveToEntities, HandleMoveActions, RandomMonsterMovementSystem},
+    combat::{AttackActionHandler, HealActionHandler, RemoveDeadEntities},
+    components::FinishedActivity,
+    crafting::HandleCraftingSystem,
+    daylight::UpdateLighting,
+    droptables::DeathLootDrop,
+    equipment::EquipActionHandler,
+    events::{GameEventPresenter, GameEvents},
+    fishing::{
+        CatchFishSystem, CreateFishingBubbles, FishingMinigameCheck, FishingMinigameUpdate, PollFishingTiles,
+        SetupFishingActions, WaitingForFishSystem,
+    },
+    fov::UpdateViewsheds,
+    indexing::UpdateSpatialIndex,
+    items::{ConsumeHandler, ItemPickupHandler, ItemSpawnerSystem, ZeroQtyItemCleanup},
+    mining::{DamageSystem, TileDestructionSystem},
+    tile_animation::{TileAnimationCleanUpSystem, TileAnimationSpawner, TileAnimationUpdater},
+};
+
+/// Every dispatcher used by the game, built once at startup
+pub struct GameDispatchers {
+    /// Ran every frame of `AppState::InGame`
+    pub ingame: Dispatcher<'static, 'static>,
+    /// Ran after the player finishes their turn, in both `InGame` and `ActivityBound`
+    pub response: Dispatcher<'static, 'static>,
+    /// Ran every frame of `AppState::ActivityBound`
+    pub activity_bound: Dispatcher<'static, 'static>,
+    /// Ran when the player performs an action on a single item in the inventory
+    pub item_action: Dispatcher<'static, 'static>,
+    /// Ran when the player uses two items together in the inventory
+    pub crafting: Dispatcher<'static, 'static>,
+    /// Ran on the frames of `AppState::PlayerInInventory` where no item action or craft is performed
+    pub inventory: Dispatcher<'static, 'static>,
+    /// Ran at the end of every frame to show the player what happened, it is left out of the headless `Simulation`
+    pub presentation: Dispatcher<'static, 'static>,
+}
+
+impl GameDispatchers {
+    pub fn new() -> Self {
+        Self {
+            ingame: ingame_dispatcher(),
+            response: response_dispatcher(),
+            activity_bound: activity_bound_dispatcher(),
+            item_action: item_action_dispatcher(),
+            crafting: crafting_dispatcher(),
+            inventory: inventory_dispatcher(),
+            presentation: presentation_dispatcher(),
+        }
+    }
+}
+
+fn ingame_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        // Indexing Systems ===============================>
+        .with(UpdateSpatialIndex, "spatial_index", &[])
+        // Fishing Systems ==================================>
+        .with(SetupFishingActions, "setup_fishing_actions", &[])
+        .with(PollFishingTiles, "poll_fishing_tiles", &["spatial_index"])
+        // Action Systems =================================>
+        .with(HealActionHandler, "heal_handler", &[])
+        .with(TileDestructionSystem, "tile_destruction", &[])
+        .with(DamageSystem, "damage", &["heal_handler", "tile_destruction"])
+        .with(ItemPickupHandler, "item_pickup", &["spatial_index"])
+        // Misc Systems ==================================>
+        .with(DeathLootDrop, "death_loot_drop", &["damage", "setup_fishing_actions"])
+        .with(UpdateLighting, "update_lighting", &[])
+        .with(UpdateViewsheds, "update_viewsheds", &["spatial_index", "update_lighting"])
+        // Request Based Systems ================================>
+        .with(ItemSpawnerSystem, "item_spawner", &["death_loot_drop", "item_pickup"])
+        // Animation Systems =========================================>
+        // entities must be created in the same order every run so they are given the same ids
+        .with(TileAnimationSpawner, "tile_anim_spawner", &["setup_fishing_actions", "tile_destruction", "item_spawner"])
+        .with(TileAnimationUpdater, "tile_anim_updater", &["tile_anim_spawner"])
+        .with(TileAnimationCleanUpSystem, "tile_anim_cleanup", &["tile_anim_spawner"])
+        // Cleanup Systems =======================================>
+        .with(ZeroQtyItemCleanup, "zero_qty_item_cleanup", &["item_spawner"])
+        .with(RemoveDeadEntities, "remove_dead_entities", &["death_loot_drop"])
+        .build()
+}
+
+fn response_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        .with(RandomMonsterMovementSystem, "random_walker", &[])
+        .with(GoalFindEntities, "goal_find", &["random_walker"])
+        .with(GoalMoveToEntities, "goal_mover", &["goal_find"])
+        .with(HandleMoveActions, "handle_moves", &["random_walker", "goal_mover"])
+        .with(AttackActionHandler, "attack_handler", &["goal_mover"])
+        .with(CreateFishingBubbles, "create_fishing_bubbles", &["random_walker"])
+        .build()
+}
+
+fn activity_bound_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        // Fishing Minigame Systems ====================>
+        .with(WaitingForFishSystem, "waiting_for_fish", &[])
+        .with(FishingMinigameUpdate, "fish_minigame_update", &["waiting_for_fish"])
+        .with(FishingMinigameCheck, "fish_minigame_check", &["fish_minigame_update"])
+        .with(CatchFishSystem, "catch_fish", &["fish_minigame_check"])
+        .build()
+}
+
+fn item_action_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        .with(EquipActionHandler, "equip_handler", &[])
+        .with(ConsumeHandler, "consume_handler", &[])
+        .with(HealActionHandler, "heal_handler", &["consume_handler"])
+        .with(DamageSystem, "damage", &["heal_handler"])
+        .with(ItemSpawnerSystem, "item_spawner", &[])
+        .with(ZeroQtyItemCleanup, "zero_qty_item_cleanup", &["item_spawner", "consume_handler"])
+        .build()
+}
+
+fn crafting_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        .with(HandleCraftingSystem, "craft_handler", &[])
+        .with(ItemSpawnerSystem, "item_spawner", &["craft_handler"])
+        .with(ZeroQtyItemCleanup, "zero_qty_item_cleanup", &["item_spawner"])
+        .build()
+}
+
+fn inventory_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new()
+        .with(ItemSpawnerSystem, "item_spawner", &[])
+        .with(ZeroQtyItemCleanup, "zero_qty_item_cleanup", &["item_spawner"])
+        .build()
+}
+
+/// Anything else reading `GameEvents` (quests, statistics) should be added here
+fn presentation_dispatcher() -> Dispatcher<'static, 'static> {
+    DispatcherBuilder::new().with(GameEventPresenter, "event_presenter", &[]).build()
+}
+
+/// Presents the events sent during the frame then clears them
+pub fn run_presentation_systems(dispatchers: &mut GameDispatchers, ecs: &mut World) {
+    dispatchers.presentation.dispatch(ecs);
+    ecs.write_resource::<GameEvents>().clear();
+}
+
+/// Systems that need to be ran after most other systems are finished EOF - end of frame
+pub fn run_eof_systems(ecs: &mut World) {
+    ecs.write_storage::<FinishedActivity>().clear();
+}
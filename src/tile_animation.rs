@@ -122,7 +122,7 @@ impl<'a> System<'a> for TileAnimationUpdater {
 
 // NOTE: Since some tile animations live on other entities that contain important component, they
 // will not always have a delte condition and could instead be cleaned up by other means for
-// example the dead tile cleanup which checks HealthStats.
+// example the dead entity cleanup which checks HealthStats.
 pub struct TileAnimationCleanUpSystem;
 
 impl<'a> System<'a> for TileAnimationCleanUpSystem {
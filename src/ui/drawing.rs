@@ -1,6 +1,6 @@
-use bracket_lib::terminal::{to_cp437, ColorPair, DrawBatch, Rect};
+use bracket_lib::terminal::{to_cp437, ColorPair, DrawBatch, FontCharType, Point, Rect, RGB};
 
-use crate::colors::white_fg;
+use crate::{colors::white_fg, data_read::prelude::UI_LAYOUT};
 
 pub trait AccentBox {
     fn draw_accent_box(&mut self, size: Rect, color: ColorPair) -> &mut Self;
@@ -17,3 +17,38 @@ impl AccentBox for DrawBatch {
         self
     }
 }
+
+/// Draws a panel of the ui layout from its REX Paint art and returns the region of one of its anchors.
+/// Nothing is drawn when the layout is missing the panel or the anchor so the caller can draw its own panel.
+pub fn draw_anchored(draw_batch: &mut DrawBatch, panel_name: &str, anchor_name: &str) -> Option<Rect> {
+    let layout = UI_LAYOUT.lock().unwrap();
+    let panel = layout.panel(panel_name)?;
+    let anchor = panel.anchor(anchor_name)?;
+    let art = layout.art(&panel.art)?;
+
+    if let Some(background) = panel.background {
+        draw_batch.fill_region(panel.region, ColorPair::new(background, background), to_cp437(' '));
+    }
+    for layer in art.layers.iter() {
+        for y in panel.region.y1..panel.region.y2 {
+            for x in panel.region.x1..panel.region.x2 {
+                let cell = match layer.get(x as usize, y as usize) {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+                if cell.bg.is_transparent() {
+                    continue;
+                }
+                let fg = RGB::from_u8(cell.fg.r, cell.fg.g, cell.fg.b);
+                let bg = RGB::from_u8(cell.bg.r, cell.bg.g, cell.bg.b);
+                draw_batch.set(Point::new(x, y), ColorPair::new(fg, bg), cell.ch as FontCharType);
+            }
+        }
+    }
+    Some(anchor)
+}
+
+/// The region of an anchor without drawing its panel, for filling in more than one anchor of a panel
+pub fn panel_anchor(panel_name: &str, anchor_name: &str) -> Option<Rect> {
+    UI_LAYOUT.lock().unwrap().panel(panel_name)?.anchor(anchor_name)
+}
@@ -1,7 +1,7 @@
 use crate::colors::{PL_MENU_ACCENT_TEXT, PL_MENU_TEXT};
 use crate::{
     colors::{self, to_rgb, Color},
-    components::{Equipped, InBag, Item, Name},
+    components::{EntityStats, Equipable, Equipped, InBag, Item, Name},
     config::{InventoryConfig, SortMode},
 };
 use bracket_lib::terminal::{ColorPair, DrawBatch, TextAlign};
@@ -10,7 +10,7 @@ use specs::{Join, LendJoin, ReadStorage, World, WorldExt};
 
 use crate::{components::SelectedInventoryItem, game_init::PlayerEntity};
 
-use super::drawing::AccentBox;
+use super::drawing::{draw_anchored, panel_anchor, AccentBox};
 //
 // Usage Definitions these should move into their own file
 pub const INVENTORY_BACKGROUND: Color = colors::PARCHMENT; // (44, 57, 71);
@@ -37,27 +37,103 @@ pub(crate) fn draw_inventory(draw_batch: &mut DrawBatch, ecs: &World, cfg: &Inve
 
     // TODO: show empty in inventory if inv_count == 0
     let inv_count = data.len();
-    draw_batch.draw_accent_box(
-        Rect::with_size(40, 2, 35, inv_count + 1),
-        ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND),
-    );
+    let items_area = draw_anchored(draw_batch, "inventory", "items").unwrap_or_else(|| {
+        draw_batch.draw_accent_box(
+            Rect::with_size(40, 2, 35, inv_count + 1),
+            ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND),
+        );
+        Rect::with_size(41, 3, 34, inv_count)
+    });
 
     let selected_items = ecs.read_storage::<SelectedInventoryItem>();
     let selected_item = selected_items.get(player_entity.0).map(|SelectedInventoryItem { first_item, .. }| first_item);
 
     // Draw each item in inventory
+    // the first column is left for the selection cursor
     for (offset, (item_entity, item, _, Name(name), equipped)) in data.iter().enumerate() {
+        let row = items_area.y1 + offset as i32;
+        if row >= items_area.y2 {
+            break;
+        }
         let status = if equipped.is_some() { "(E)" } else { "" };
         let qty = if item.qty.0 > 1 { format!("{}x ", item.qty) } else { "".to_string() };
         draw_batch.printer(
-            Point::new(42, 2 + offset + 1),
+            Point::new(items_area.x1 + 1, row),
             format!("#[{PL_MENU_TEXT}]{:X}| #[{PL_MENU_ACCENT_TEXT}]{status}{qty}{name}", offset + 1),
             TextAlign::Left,
             Some(to_rgb(INVENTORY_BACKGROUND).into()),
         );
 
         if Some(item_entity) == selected_item {
-            draw_batch.print(Point::new(41, 3 + offset), ">");
+            draw_batch.print(Point::new(items_area.x1, row), ">");
+        }
+    }
+
+    // the equipment and skills only have a place on the screen when the inventory is drawn from its art
+    if let Some(equipment_area) = panel_anchor("inventory", "equipment") {
+        draw_equipment(draw_batch, ecs, &equipment_area);
+    }
+    if let Some(skills_area) = panel_anchor("inventory", "skills") {
+        draw_skills(draw_batch, ecs, &skills_area);
+    }
+}
+
+/// Each equipped item takes two rows, the slot it is in and then its name
+fn draw_equipment(draw_batch: &mut DrawBatch, ecs: &World, area: &Rect) {
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    let equipped: ReadStorage<Equipped> = ecs.read_storage();
+    let equipables: ReadStorage<Equipable> = ecs.read_storage();
+    let names: ReadStorage<Name> = ecs.read_storage();
+
+    let worn = (&equipped, &equipables, &names).join().filter(|(worn_on, ..)| worn_on.on == player_entity.0);
+    let max_len = area.width() as usize - 1;
+    for (offset, (_, equipable, Name(name))) in worn.enumerate() {
+        let row = area.y1 + 2 * offset as i32;
+        if row + 1 >= area.y2 {
+            break;
+        }
+        draw_batch.printer(
+            Point::new(area.x1, row),
+            format!("#[{PL_MENU_TEXT}]{}", equipable.slot.name()),
+            TextAlign::Left,
+            Some(to_rgb(INVENTORY_BACKGROUND).into()),
+        );
+        let name: String = name.chars().take(max_len).collect();
+        draw_batch.printer(
+            Point::new(area.x1 + 1, row + 1),
+            format!("#[{PL_MENU_ACCENT_TEXT}]{name}"),
+            TextAlign::Left,
+            Some(to_rgb(INVENTORY_BACKGROUND).into()),
+        );
+    }
+}
+
+fn draw_skills(draw_batch: &mut DrawBatch, ecs: &World, area: &Rect) {
+    let player_entity = ecs.read_resource::<PlayerEntity>();
+    let stats: ReadStorage<EntityStats> = ecs.read_storage();
+    let set = match stats.get(player_entity.0) {
+        Some(stats) => stats.set,
+        None => return,
+    };
+
+    let rows = [
+        ("STR", set.strength),
+        ("DEX", set.dexterity),
+        ("VIT", set.vitality),
+        ("INT", set.intelligence),
+        ("PRE", set.precision),
+        ("CHA", set.charisma),
+    ];
+    for (offset, (label, value)) in rows.iter().enumerate() {
+        let row = area.y1 + 2 * offset as i32;
+        if row >= area.y2 {
+            break;
         }
+        draw_batch.printer(
+            Point::new(area.x1, row),
+            format!("#[{PL_MENU_TEXT}]{label} #[{PL_MENU_ACCENT_TEXT}]{value}"),
+            TextAlign::Left,
+            Some(to_rgb(INVENTORY_BACKGROUND).into()),
+        );
     }
 }
@@ -1,15 +1,24 @@
 use bracket_lib::color::GREY4;
-use bracket_lib::terminal::{to_char, to_cp437, ColorPair, DrawBatch, Point, Rect, TextAlign, WHITESMOKE};
+use bracket_lib::terminal::{to_char, to_cp437, ColorPair, DrawBatch, Point, Rect, TextAlign, RGB, WHITESMOKE};
+use itertools::Itertools;
 
+use crate::chunks::CHUNK_SIZE;
 use crate::game_init::{InputWorldConfig, NewGameMenuSelection};
+use crate::map_gen::{PreviewCell, WorldPreview, PREVIEW_ACROSS, PREVIEW_DOWN};
 use crate::saveload::any_save_game_exists;
 use crate::{
-    colors::{Color, DARKBLUE, DARKBLUEPURPLE, MIDDLERED, PL_SETTINGS_HIGHLIGHT, PL_SETTINGS_TEXT, SALMON},
+    colors::{
+        to_rgb, Color, ASHGREY, DARKBLUE, DARKBLUEPURPLE, DARKESTBROWN, FERNGREEN, KHAKI, MIDDLERED, OCEANBLUE,
+        PINEGREEN, PL_SETTINGS_HIGHLIGHT, PL_SETTINGS_TEXT, SALMON,
+    },
+    keybindings::{BindingRow, Keybindings},
     player::MenuSelection,
-    settings::{SettingsConfig, SpriteMode},
+    settings::{SettingsConfig, SettingsSelection, SpriteMode},
     CL_EFFECTS, CL_TEXT, DISPLAY_HEIGHT, DISPLAY_WIDTH,
 };
 
+use super::drawing::draw_anchored;
+
 // Menu contianing the starting options for the player
 const MENU_WIDTH: usize = 15;
 const MENU_HEIGHT: usize = 2 + MENU_OPTIONS.len() * 2;
@@ -34,13 +43,16 @@ pub fn draw_main_menu(draw_batch: &mut DrawBatch, hovered: &MenuSelection) {
     );
 
     draw_batch.target(CL_TEXT);
-    let menu_rect = Rect::with_size(MENU_START_X, MENU_START_Y, MENU_WIDTH, MENU_HEIGHT);
-    draw_batch.draw_hollow_double_box(menu_rect, ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG));
-    draw_batch.fill_region(
-        Rect::with_exact(menu_rect.x1 + 1, menu_rect.y1 + 1, menu_rect.x2, menu_rect.y2),
-        ColorPair::new(WHITESMOKE, MAIN_MENU_BG),
-        to_cp437(' '),
-    );
+    let options_area = draw_anchored(draw_batch, "main_menu", "options").unwrap_or_else(|| {
+        let menu_rect = Rect::with_size(MENU_START_X, MENU_START_Y, MENU_WIDTH, MENU_HEIGHT);
+        draw_batch.draw_hollow_double_box(menu_rect, ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG));
+        draw_batch.fill_region(
+            Rect::with_exact(menu_rect.x1 + 1, menu_rect.y1 + 1, menu_rect.x2, menu_rect.y2),
+            ColorPair::new(WHITESMOKE, MAIN_MENU_BG),
+            to_cp437(' '),
+        );
+        Rect::with_size(MENU_START_X + 3, MENU_START_Y + 2, MENU_WIDTH - 3, MENU_HEIGHT - 2)
+    });
 
     for (idx, opt) in MENU_OPTIONS.iter().enumerate() {
         let colors = if opt == &"load game" && !any_save_game_exists() {
@@ -57,11 +69,11 @@ pub fn draw_main_menu(draw_batch: &mut DrawBatch, hovered: &MenuSelection) {
             opt.to_string()
         };
 
-        draw_batch.print_color(Point::new(MENU_START_X + 3, MENU_START_Y + 2 + (2 * idx)), text, colors);
+        draw_batch.print_color(Point::new(options_area.x1, options_area.y1 + 2 * idx as i32), text, colors);
     }
 }
 
-pub fn draw_settings(draw_batch: &mut DrawBatch, cfg: &SettingsConfig) {
+pub fn draw_settings(draw_batch: &mut DrawBatch, cfg: &SettingsConfig, hovering: &SettingsSelection) {
     draw_batch.target(CL_TEXT);
     // Background
     draw_batch.fill_region(
@@ -83,12 +95,80 @@ pub fn draw_settings(draw_batch: &mut DrawBatch, cfg: &SettingsConfig) {
     };
     draw_batch.printer(
         Point::new(MENU_START_X + 1, MENU_START_Y + 2),
-        format!("#[{}]Sprite Mode: #[{}]Blocked #[{}]Outline", PL_SETTINGS_TEXT, blocked, outline),
+        format!(
+            "{}#[{}]Sprite Mode: #[{}]Blocked #[{}]Outline",
+            hover_marker(*hovering == SettingsSelection::SpriteMode),
+            PL_SETTINGS_TEXT,
+            blocked,
+            outline
+        ),
+        TextAlign::Left,
+        Some(MAIN_MENU_BG.into()),
+    );
+
+    // Keybindings
+    draw_batch.printer(
+        Point::new(MENU_START_X + 1, MENU_START_Y + 4),
+        format!("{}#[{}]Keybindings", hover_marker(*hovering == SettingsSelection::Keybindings), PL_SETTINGS_TEXT),
         TextAlign::Left,
         Some(MAIN_MENU_BG.into()),
     );
 }
 
+fn hover_marker(hovered: bool) -> String {
+    if hovered {
+        to_char(16).to_string()
+    } else {
+        " ".to_string()
+    }
+}
+
+/// How many binding rows fit on the screen at once
+const KEYBINDING_ROWS_SHOWN: usize = 40;
+
+pub fn draw_keybindings_menu(draw_batch: &mut DrawBatch, binds: &Keybindings, hovering: usize, listening: bool) {
+    draw_batch.target(CL_TEXT);
+    // Background
+    draw_batch.fill_region(
+        Rect::with_size(0, 0, DISPLAY_WIDTH * 2, DISPLAY_HEIGHT * 2),
+        ColorPair::new(WHITESMOKE, MAIN_MENU_BG),
+        to_cp437(' '),
+    );
+
+    let menu_rect = Rect::with_size(10, 4, DISPLAY_WIDTH * 2 - 20, KEYBINDING_ROWS_SHOWN + 5);
+    draw_batch.draw_hollow_double_box(menu_rect, ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG));
+    let help = if listening {
+        "Press the key to add to the action"
+    } else {
+        "Enter - add key | Backspace - clear | Esc - save and leave"
+    };
+    draw_batch.print_color(Point::new(menu_rect.x1 + 2, menu_rect.y1 + 1), help, ColorPair::new(SALMON, MAIN_MENU_BG));
+
+    // keep the hovered row in view
+    let rows = binds.rows();
+    let first_shown = hovering.saturating_sub(KEYBINDING_ROWS_SHOWN - 1);
+    for (idx, row) in rows.iter().enumerate().skip(first_shown).take(KEYBINDING_ROWS_SHOWN) {
+        let label = match row {
+            BindingRow::Action(context, action) => format!("{} - {}", context.display_name(), action.display_name()),
+            BindingRow::InventorySlot(slot) => format!("Inventory - Slot {}", slot + 1),
+        };
+        let keys = binds.keys_of(row).iter().filter(|key| !key.is_empty()).join(", ");
+        let colors = if idx == hovering {
+            ColorPair::new(MAIN_MENU_TEXT_HL, MAIN_MENU_HL)
+        } else {
+            ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG)
+        };
+        let keys = if idx == hovering && listening { "...".to_string() } else { keys };
+        let y = menu_rect.y1 + 3 + (idx - first_shown) as i32;
+        draw_batch.print_color(
+            Point::new(menu_rect.x1 + 2, y),
+            format!("{}{}", hover_marker(idx == hovering), label),
+            colors,
+        );
+        draw_batch.print_color(Point::new(menu_rect.x1 + 34, y), keys, colors);
+    }
+}
+
 pub fn draw_load_game_menu(draw_batch: &mut DrawBatch, save_games: &[String], hovering: usize) {
     // Background
     draw_batch.target(CL_TEXT);
@@ -130,6 +210,7 @@ pub fn draw_new_game_menu(
     hovering: &NewGameMenuSelection,
     world_cfg: &InputWorldConfig,
     form_errors: &[String],
+    preview: &WorldPreview,
 ) {
     // Background
     draw_batch.target(CL_TEXT);
@@ -139,11 +220,13 @@ pub fn draw_new_game_menu(
         to_cp437(' '),
     );
 
-    let menu_start_x = MENU_START_X - 10;
+    let menu_start_x = 4;
+    let menu_width = 29;
     let menu_height = 20;
+    let menu_top = MENU_START_Y - menu_height;
 
     draw_batch.target(CL_TEXT);
-    let menu_rect = Rect::with_size(menu_start_x, MENU_START_Y - menu_height, 29, menu_height);
+    let menu_rect = Rect::with_size(menu_start_x, menu_top, menu_width, menu_height);
     draw_batch.draw_hollow_double_box(menu_rect, ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG));
     draw_batch.fill_region(
         Rect::with_exact(menu_rect.x1 + 1, menu_rect.y1 + 1, menu_rect.x2, menu_rect.y2),
@@ -153,45 +236,107 @@ pub fn draw_new_game_menu(
 
     let hl = ColorPair::new(MAIN_MENU_TEXT_HL, MAIN_MENU_HL);
     let no = ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG);
-    let (name, width, height, seed, finish) = match hovering {
-        NewGameMenuSelection::WorldName => (hl, no, no, no, no),
-        NewGameMenuSelection::Width => (no, hl, no, no, no),
-        NewGameMenuSelection::Height => (no, no, hl, no, no),
-        NewGameMenuSelection::Seed => (no, no, no, hl, no),
-        NewGameMenuSelection::Finalize => (no, no, no, no, hl),
-    };
+    let color_of = |field: NewGameMenuSelection| if *hovering == field { hl } else { no };
+    let preset = if world_cfg.noise_preset.is_empty() { "Default" } else { world_cfg.noise_preset.as_str() };
 
-    draw_batch.print_color(
-        Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 1),
-        format!("World Name: {}", world_cfg.world_name),
-        name,
-    );
+    let rows = [
+        (1, format!("World Name: {}", world_cfg.world_name), color_of(NewGameMenuSelection::WorldName)),
+        (3, "==Map==".to_string(), no),
+        (4, format!("Width (x{}): {}", CHUNK_SIZE, world_cfg.width), color_of(NewGameMenuSelection::Width)),
+        (5, format!("Height (x{}): {}", CHUNK_SIZE, world_cfg.height), color_of(NewGameMenuSelection::Height)),
+        (6, format!("Sea Level (0-255): {}", world_cfg.sea_level), color_of(NewGameMenuSelection::SeaLevel)),
+        (7, format!("Seed: {}", world_cfg.seed), color_of(NewGameMenuSelection::Seed)),
+        (9, "==Generation==".to_string(), no),
+        (10, format!("Resources: {}%", world_cfg.resource_density), color_of(NewGameMenuSelection::ResourceDensity)),
+        (11, format!("Noise: {}{}{}", to_char(17), preset, to_char(16)), color_of(NewGameMenuSelection::NoisePreset)),
+    ];
+    for (row, text, colors) in rows {
+        draw_batch.print_color(Point::new(menu_start_x + 1, menu_top + row), text, colors);
+    }
 
-    draw_batch.print_color(Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 3), "==Map==", no);
     draw_batch.print_color(
-        Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 4),
-        format!("Width: {}", world_cfg.width),
-        width,
+        Point::new(menu_start_x + 29 / 2, MENU_START_Y),
+        "Finish".to_string(),
+        color_of(NewGameMenuSelection::Finalize),
     );
-    draw_batch.print_color(
-        Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 5),
-        format!("Height: {}", world_cfg.height),
-        height,
+
+    for (idx, err) in form_errors.iter().enumerate() {
+        draw_batch.print_color(Point::new(menu_start_x + 29 / 2, MENU_START_Y - (menu_height + idx + 1)), err, hl);
+    }
+
+    draw_world_preview(draw_batch, preview, menu_start_x + menu_width + 3, menu_top);
+}
+
+/// Draws the preview boxed in with its top left corner at `x`, `y`
+fn draw_world_preview(draw_batch: &mut DrawBatch, preview: &WorldPreview, x: usize, y: usize) {
+    let frame = Rect::with_size(x, y, PREVIEW_ACROSS + 1, PREVIEW_DOWN + 1);
+    draw_batch.draw_hollow_double_box(frame, ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG));
+    draw_batch.print_color(Point::new(x + 2, y), "Preview", ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG));
+
+    if preview.cells.is_empty() {
+        let text = "No world with these settings";
+        let at = Point::new(x + (PREVIEW_ACROSS + 2 - text.len()) / 2, y + PREVIEW_DOWN / 2);
+        draw_batch.print_color(at, text, ColorPair::new(GREY4, MAIN_MENU_BG));
+        return;
+    }
+
+    for cy in 0..PREVIEW_DOWN {
+        for cx in 0..PREVIEW_ACROSS {
+            let (glyph, colors) = match preview.cell(cx, cy) {
+                Some(PreviewCell::Water) => ('~', ColorPair::new(WHITESMOKE, to_rgb(OCEANBLUE))),
+                Some(PreviewCell::Land(height)) => (' ', ColorPair::new(WHITESMOKE, land_color(height))),
+                Some(PreviewCell::Mountain) => ('^', ColorPair::new(to_rgb(DARKESTBROWN), to_rgb(ASHGREY))),
+                Some(PreviewCell::Resource) => (to_char(6), ColorPair::new(to_rgb(PINEGREEN), to_rgb(FERNGREEN))),
+                None => continue,
+            };
+            draw_batch.set(Point::new(x + 1 + cx, y + 1 + cy), colors, to_cp437(glyph));
+        }
+    }
+}
+
+/// Low land is green and turns drier the higher it is
+fn land_color(height: u8) -> RGB {
+    to_rgb(FERNGREEN).lerp(to_rgb(KHAKI), height as f32 / 255.0)
+}
+
+pub fn draw_data_error(draw_batch: &mut DrawBatch, error: &str) {
+    // Background
+    draw_batch.target(CL_TEXT);
+    draw_batch.fill_region(
+        Rect::with_size(0, 0, DISPLAY_WIDTH * 2, DISPLAY_HEIGHT * 2),
+        ColorPair::new(WHITESMOKE, MAIN_MENU_BG),
+        to_cp437(' '),
     );
+
+    let text_width = DISPLAY_WIDTH * 2 - 8;
+    let mut lines: Vec<String> = vec![];
+    for word in error.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.len() + word.len() < text_width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+
+    let menu_rect = Rect::with_size(3, 10, text_width + 2, lines.len() + 5);
+    draw_batch.draw_hollow_double_box(menu_rect, ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG));
     draw_batch.print_color(
-        Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 6),
-        format!("Sea Level (0-255): {}", world_cfg.height),
-        height,
+        Point::new(menu_rect.x1 + 1, menu_rect.y1 + 1),
+        "The game data in raws/ has a problem",
+        ColorPair::new(MAIN_MENU_TEXT_HL, MAIN_MENU_BG),
     );
+    for (idx, line) in lines.iter().enumerate() {
+        draw_batch.print_color(
+            Point::new(menu_rect.x1 + 1, menu_rect.y1 + 3 + idx as i32),
+            line,
+            ColorPair::new(WHITESMOKE, MAIN_MENU_BG),
+        );
+    }
     draw_batch.print_color(
-        Point::new(menu_start_x + 1, MENU_START_Y - menu_height + 7),
-        format!("Seed: {}", world_cfg.seed),
-        seed,
+        Point::new(menu_rect.x1 + 1, menu_rect.y2 - 1),
+        "Fix the file and press <ESC> to return to the main menu",
+        ColorPair::new(MAIN_MENU_ACCENT, MAIN_MENU_BG),
     );
-
-    draw_batch.print_color(Point::new(menu_start_x + 29 / 2, MENU_START_Y), "Finish".to_string(), finish);
-
-    for (idx, err) in form_errors.iter().enumerate() {
-        draw_batch.print_color(Point::new(menu_start_x + 29 / 2, MENU_START_Y - (menu_height + idx + 1)), err, hl);
-    }
 }
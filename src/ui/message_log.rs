@@ -8,11 +8,12 @@ use specs::{World, WorldExt};
 
 use crate::{
     colors::{DARKERBROWN, PL_MENU_ACCENT_TEXT, PL_MENU_TEXT, PL_ORANGE},
+    daylight::WorldLight,
     TurnCounter, CL_TEXT,
 };
 
 use super::{
-    drawing::AccentBox,
+    drawing::{draw_anchored, AccentBox},
     inventory::{INVENTORY_BACKGROUND, INVENTORY_OUTLINE},
 };
 
@@ -20,24 +21,38 @@ pub(crate) fn draw_message_log(draw_batch: &mut DrawBatch, ecs: &World) {
     let log = ecs.fetch::<MessageLog>();
 
     draw_batch.target(CL_TEXT);
-    draw_batch
-        .draw_accent_box(Rect::with_size(-1, 50, 70, 10), ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND));
-
-    for (y_offset, message) in log.nth_recent(9).enumerate() {
-        draw_batch.printer(Point::new(1, 51 + y_offset), message.colored(), TextAlign::Left, Some(RGBA::new()));
+    let messages_area = draw_anchored(draw_batch, "message_log", "messages").unwrap_or_else(|| {
+        draw_batch
+            .draw_accent_box(Rect::with_size(-1, 50, 70, 10), ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND));
+        Rect::with_size(1, 51, 68, 9)
+    });
+
+    for (y_offset, message) in log.nth_recent(messages_area.height() as usize).enumerate() {
+        draw_batch.printer(
+            Point::new(messages_area.x1, messages_area.y1 + y_offset as i32),
+            message.colored(),
+            TextAlign::Left,
+            Some(RGBA::new()),
+        );
     }
 }
 
+/// Shows the turn along with the time of day
 pub fn draw_turn_counter(draw_batch: &mut DrawBatch, ecs: &World) {
     let turn_counter = ecs.read_resource::<TurnCounter>();
+    let light = ecs.read_resource::<WorldLight>();
+    let text = format!("Turn:{} {}", turn_counter.0, light.phase.name());
     draw_batch.target(CL_TEXT);
-    draw_batch.draw_accent_box(
-        Rect::with_size(0, 1, 6 + turn_counter.0.to_string().len(), 2),
-        ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND),
-    );
+    let turns_area = draw_anchored(draw_batch, "turn_counter", "turns").unwrap_or_else(|| {
+        draw_batch.draw_accent_box(
+            Rect::with_size(0, 1, 1 + text.len(), 2),
+            ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND),
+        );
+        Rect::with_size(1, 2, text.len(), 1)
+    });
     draw_batch.print_color(
-        Point { x: 1, y: 2 },
-        format!("Turn:{}", turn_counter.0),
+        Point::new(turns_area.x1, turns_area.y1),
+        text,
         ColorPair { fg: DARKERBROWN.into(), bg: INVENTORY_BACKGROUND.into() },
     );
 }
@@ -8,6 +8,7 @@ use crate::{
     fov::draw_unseen_area,
     frame_animation::print_frame_animations,
     inventory::{check_inventory_selection, SelectionStatus},
+    map_gen::WorldPreview,
     saveload_menu::GameSaves,
     AppState, CL_EFFECTS, CL_EFFECTS2, CL_TEXT,
 };
@@ -15,7 +16,7 @@ use crate::{
 use self::{
     fishing::draw_fishing_bar,
     inventory::draw_inventory,
-    main_menu::{draw_main_menu, draw_new_game_menu, draw_settings},
+    main_menu::{draw_data_error, draw_keybindings_menu, draw_main_menu, draw_new_game_menu, draw_settings},
     message_log::{draw_message_log, draw_turn_counter},
     save_menu::draw_save_menu,
     use_menu::draw_use_menu,
@@ -45,7 +46,7 @@ pub fn draw_ui(ecs: &World, appstate: &AppState, cfg: &ConfigMaster) {
         AppState::PlayerInInventory => {
             draw_inventory(&mut draw_batch, ecs, &cfg.inventory);
             if check_inventory_selection(ecs) == SelectionStatus::SelectionWithoutAction {
-                draw_use_menu(&mut draw_batch, ecs);
+                draw_use_menu(&mut draw_batch, ecs, &cfg.keybindings);
             }
 
             draw_message_log(&mut draw_batch, ecs);
@@ -61,17 +62,24 @@ pub fn draw_ui(ecs: &World, appstate: &AppState, cfg: &ConfigMaster) {
             print_frame_animations(&mut draw_batch, ecs);
         }
         AppState::SaveGame => {
-            draw_save_menu(&mut draw_batch);
+            draw_save_menu(&mut draw_batch, &cfg.keybindings);
         }
-        AppState::SettingsMenu { .. } => {
-            draw_settings(&mut draw_batch, &cfg.general);
+        AppState::SettingsMenu { hovering } => {
+            draw_settings(&mut draw_batch, &cfg.general, hovering);
+        }
+        AppState::KeybindingsMenu { hovering, listening } => {
+            draw_keybindings_menu(&mut draw_batch, &cfg.keybindings, *hovering, *listening);
         }
         AppState::LoadGameMenu { hovering } => {
             let save_games = ecs.read_resource::<GameSaves>();
             draw_load_game_menu(&mut draw_batch, &save_games.saves, *hovering);
         }
         AppState::NewGameInitialize { hovering, world_cfg, form_errors } => {
-            draw_new_game_menu(&mut draw_batch, hovering, world_cfg, form_errors);
+            let preview = ecs.read_resource::<WorldPreview>();
+            draw_new_game_menu(&mut draw_batch, hovering, world_cfg, form_errors, &preview);
+        }
+        AppState::DataErrorScreen { error } => {
+            draw_data_error(&mut draw_batch, error);
         }
         _ => {}
     }
@@ -2,6 +2,7 @@ use bracket_lib::terminal::{to_char, ColorPair, DrawBatch, Point, Rect, TextAlig
 
 use crate::{
     colors::{MIDDLERED, PL_KEYBIND, SALMON},
+    keybindings::{Action, BindingContext, Keybindings},
     CL_TEXT, DISPLAY_HEIGHT, DISPLAY_WIDTH,
 };
 
@@ -12,7 +13,9 @@ const MENU_WIDTH: usize = DISPLAY_WIDTH * 2 / 4;
 const MENU_Y: usize = DISPLAY_HEIGHT - MENU_HEIGHT / 2;
 const MENU_HEIGHT: usize = DISPLAY_HEIGHT / 5;
 
-pub fn draw_save_menu(draw_batch: &mut DrawBatch) {
+/// Each choice is shown with the first key bound to it
+pub fn draw_save_menu(draw_batch: &mut DrawBatch, binds: &Keybindings) {
+    let hint = |action| binds.hint(BindingContext::SaveMenu, action);
     draw_batch.target(CL_TEXT);
     draw_batch
         .draw_accent_box(Rect::with_size(MENU_X, MENU_Y, MENU_WIDTH, MENU_HEIGHT), ColorPair::new(MIDDLERED, SALMON));
@@ -24,25 +27,25 @@ pub fn draw_save_menu(draw_batch: &mut DrawBatch) {
     );
     draw_batch.printer(
         Point::new(MENU_X + 2, MENU_Y + 1),
-        format!("#[white]Quicksave #[{}]<SPACE>", PL_KEYBIND),
+        format!("#[white]Quicksave #[{}]{}", PL_KEYBIND, hint(Action::QuickSave)),
         TextAlign::Left,
         Some(SALMON.into()),
     );
     draw_batch.printer(
         Point::new(MENU_X + 2, MENU_Y + 2),
-        format!("#[white]Save+Quit #[{}]<S>", PL_KEYBIND),
+        format!("#[white]Save+Quit #[{}]{}", PL_KEYBIND, hint(Action::Save)),
         TextAlign::Left,
         Some(SALMON.into()),
     );
     draw_batch.printer(
         Point::new(MENU_X + 2, MENU_Y + 3),
-        format!("#[white]Quit #[{}]<D>", PL_KEYBIND),
+        format!("#[white]Quit #[{}]{}", PL_KEYBIND, hint(Action::QuitWithoutSaving)),
         TextAlign::Left,
         Some(SALMON.into()),
     );
     draw_batch.printer(
         Point::new(MENU_X + 2, MENU_Y + 4),
-        format!("#[white]Return #[{}]<ESC>", PL_KEYBIND),
+        format!("#[white]Return #[{}]{}", PL_KEYBIND, hint(Action::CancelSave)),
         TextAlign::Left,
         Some(SALMON.into()),
     );
@@ -5,6 +5,7 @@ use crate::{
     colors::to_rgb,
     components::{Consumable, Equipable, SelectedInventoryItem},
     game_init::PlayerEntity,
+    keybindings::{Action, BindingContext, Keybindings},
 };
 
 use super::{
@@ -12,33 +13,58 @@ use super::{
     inventory::{INVENTORY_BACKGROUND, INVENTORY_OUTLINE},
 };
 
-const BASE_ACTIONS: [&str; 4] =
-    ["#[orange]U#[]se with", "#[orange]E#[]xamine", "#[orange]D#[]rop", "#[lightgray]<Esc>#[]"];
-const EQUIP_ACTION: &str = "#[]E#[orange]q#[]uip";
-const CONSUME_ACTION: &str = "#[orange]C#[]onsume";
+/// Actions shown for every item along with their label
+const BASE_ACTIONS: [(Action, &str); 3] =
+    [(Action::UseWith, "Use with"), (Action::Examine, "Examine"), (Action::Drop, "Drop")];
 
-pub fn draw_use_menu(draw_batch: &mut DrawBatch, ecs: &World) {
+pub fn draw_use_menu(draw_batch: &mut DrawBatch, ecs: &World, binds: &Keybindings) {
     let selected_items = ecs.read_storage::<SelectedInventoryItem>();
     let player_entity = ecs.read_resource::<PlayerEntity>();
     let mut use_menu_actions = BASE_ACTIONS.to_vec();
     let selected_inv = selected_items.get(player_entity.0).unwrap();
 
+    let equipables = ecs.read_storage::<Equipable>();
+    if equipables.get(selected_inv.first_item).is_some() {
+        use_menu_actions.push((Action::Equip, "Equip"));
+    }
+
     let consumables = ecs.read_storage::<Consumable>();
     if consumables.get(selected_inv.first_item).is_some() {
-        use_menu_actions.insert(3, CONSUME_ACTION);
+        use_menu_actions.push((Action::Consume, "Consume"));
     }
 
-    let equipables = ecs.read_storage::<Equipable>();
-    if equipables.get(selected_inv.first_item).is_some() {
-        use_menu_actions.insert(3, EQUIP_ACTION);
-    }
+    let mut lines: Vec<(String, usize)> = use_menu_actions
+        .iter()
+        .map(|(action, label)| action_line(label, binds.first_key(BindingContext::ItemMenu, *action)))
+        .collect();
+    let cancel = binds.hint(BindingContext::ItemMenu, Action::CancelItemMenu);
+    lines.push((format!("#[lightgray]{}#[]", cancel), cancel.len()));
 
+    let width = lines.iter().map(|(_, len)| *len).max().unwrap_or(0) + 2;
     draw_batch.draw_accent_box(
-        Rect::with_size(28, 6, 10, use_menu_actions.len() + 1),
+        Rect::with_size(28, 6, width, lines.len() + 1),
         ColorPair::new(INVENTORY_OUTLINE, INVENTORY_BACKGROUND),
     );
 
-    for (idx, action) in use_menu_actions.iter().enumerate() {
-        draw_batch.printer(Point::new(29, 7 + idx), action, TextAlign::Left, Some(to_rgb(INVENTORY_BACKGROUND).into()));
+    for (idx, (line, _)) in lines.iter().enumerate() {
+        draw_batch.printer(Point::new(29, 7 + idx), line, TextAlign::Left, Some(to_rgb(INVENTORY_BACKGROUND).into()));
+    }
+}
+
+/// The label with its key picked out in orange along with how many characters are shown. A single letter key is
+/// picked out inside of the label when the label has it, anything else is shown after the label.
+fn action_line(label: &str, key: Option<&str>) -> (String, usize) {
+    let key = match key {
+        Some(key) => key,
+        None => return (label.to_string(), label.len()),
+    };
+    if key.len() == 1 {
+        if let Some(idx) = label.to_ascii_lowercase().find(&key.to_ascii_lowercase()) {
+            let (before, rest) = label.split_at(idx);
+            let (letter, after) = rest.split_at(1);
+            return (format!("{}#[orange]{}#[]{}", before, letter, after), label.len());
+        }
     }
+    let key = key.to_uppercase();
+    (format!("{} #[orange]<{}>#[]", label, key), label.len() + key.len() + 3)
 }
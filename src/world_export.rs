@@ -0,0 +1,184 @@
+//! Writes a whole generated world out as a PNG and a plain text map, without opening the game window.
+//! Every chunk is generated the same way as in game so changes to the raws or world generation can be looked at
+//! and diffed a world at a time rather than a screen at a time.
+
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use specs::{Join, World, WorldExt};
+
+use crate::{
+    being::BeingID,
+    chunks::ChunkWindow,
+    components::{Name, Position, Stairs},
+    game_init::initialize_ecs,
+    indexing::index_map,
+    map::{Map, WorldTile},
+    map_gen::{empty_world_map, find_player_spawn, gen_chunk, WorldConfig},
+};
+
+pub const EXPORT_FOLDER: &str = "./exports/";
+/// Width and height in pixels of a tile in the PNG
+const PIXELS_PER_TILE: usize = 4;
+
+/// What stands on a tile, drawn over the tile itself
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Mark {
+    WorldObject,
+    Being,
+    Stairs,
+    PlayerStart,
+}
+
+impl Mark {
+    fn glyph(&self) -> char {
+        match self {
+            Mark::WorldObject => '*',
+            Mark::Being => 'b',
+            Mark::Stairs => '>',
+            Mark::PlayerStart => '@',
+        }
+    }
+
+    fn color(&self) -> [u8; 3] {
+        match self {
+            Mark::WorldObject => [30, 70, 40],
+            Mark::Being => [220, 60, 50],
+            Mark::Stairs => [140, 50, 160],
+            Mark::PlayerStart => [255, 255, 255],
+        }
+    }
+}
+
+/// Generates every chunk of the world and writes `<world name>.png` and `<world name>.txt` to the export folder,
+/// returns the paths written
+pub fn export_world(wc: &WorldConfig) -> io::Result<Vec<PathBuf>> {
+    let mut ecs = initialize_ecs();
+    let mut wc = wc.clone();
+    wc.player_start = (wc.width / 2, wc.height / 2);
+
+    let window = ChunkWindow::whole_world(&wc);
+    let mut map = empty_world_map(&wc, &window);
+    for chunk in window.chunks() {
+        gen_chunk(&mut ecs, &wc, &mut map, chunk);
+    }
+    index_map(&mut map, &ecs);
+
+    let marks = world_marks(&ecs, &map, &wc);
+    fs::create_dir_all(EXPORT_FOLDER)?;
+    let folder = PathBuf::from(EXPORT_FOLDER);
+    let text_path = folder.join(format!("{}.txt", wc.world_name));
+    let png_path = folder.join(format!("{}.png", wc.world_name));
+    fs::write(&text_path, ascii_map(&map, &marks))?;
+    fs::write(&png_path, png_map(&map, &marks)?)?;
+    Ok(vec![png_path, text_path])
+}
+
+/// The most important thing standing on each tile that has something
+fn world_marks(ecs: &World, map: &Map, wc: &WorldConfig) -> HashMap<usize, Mark> {
+    let positions = ecs.read_storage::<Position>();
+    let names = ecs.read_storage::<Name>();
+    let beings = ecs.read_storage::<BeingID>();
+    let stairs = ecs.read_storage::<Stairs>();
+
+    let mut marks = HashMap::new();
+    let mut mark = |idx: usize, new: Mark| {
+        let old = marks.entry(idx).or_insert(new);
+        *old = (*old).max(new);
+    };
+    for (entity, pos, _) in (&ecs.entities(), &positions, &names).join() {
+        let kind = if stairs.contains(entity) {
+            Mark::Stairs
+        } else if beings.contains(entity) {
+            Mark::Being
+        } else {
+            Mark::WorldObject
+        };
+        mark(map.xy_to_idx(pos.x, pos.y), kind);
+    }
+
+    let center = Position::new(wc.player_start.0, wc.player_start.1);
+    if let Some(start) = find_player_spawn(map, &center) {
+        mark(map.xy_to_idx(start.x, start.y), Mark::PlayerStart);
+    }
+    marks
+}
+
+fn ascii_map(map: &Map, marks: &HashMap<usize, Mark>) -> String {
+    let mut text = String::with_capacity((map.width + 1) * map.height);
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let idx = map.xy_to_idx(x, y);
+            text.push(marks.get(&idx).map_or_else(|| tile_glyph(&map.tiles[idx]), Mark::glyph));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn tile_glyph(tile: &WorldTile) -> char {
+    match tile.name.as_str() {
+        "Water" | "River" | "Lake" => '~',
+        "Mountain" => '^',
+        "Grass" => '.',
+        "Sand" => ':',
+        "Mud" => ',',
+        "Gravel" => ';',
+        "Road" => '=',
+        "Wall" => '#',
+        "Door" => '+',
+        "Wooden Floor" => '_',
+        _ if tile.is_blocked => '#',
+        _ => '?',
+    }
+}
+
+/// Land gets darker the lower it is so hills and valleys show up
+fn tile_color(tile: &WorldTile) -> [u8; 3] {
+    let base = match tile.name.as_str() {
+        "Water" => [40, 80, 150],
+        "River" | "Lake" => [60, 120, 190],
+        "Mountain" => [170, 170, 165],
+        "Grass" => [80, 140, 60],
+        "Sand" => [215, 200, 140],
+        "Mud" => [100, 80, 50],
+        "Gravel" => [130, 125, 110],
+        "Road" => [160, 130, 90],
+        "Wall" => [90, 60, 40],
+        "Door" => [200, 150, 60],
+        "Wooden Floor" => [150, 110, 70],
+        _ => [255, 0, 255],
+    };
+    if tile.is_water() {
+        return base;
+    }
+    let shade = 0.6 + 0.4 * tile.height as f32 / 255.0;
+    base.map(|channel| (channel as f32 * shade) as u8)
+}
+
+fn png_map(map: &Map, marks: &HashMap<usize, Mark>) -> io::Result<Vec<u8>> {
+    let (width, height) = (map.width * PIXELS_PER_TILE, map.height * PIXELS_PER_TILE);
+    let mut pixels = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let (tile_x, tile_y) = (x / PIXELS_PER_TILE, y / PIXELS_PER_TILE);
+            let idx = map.xy_to_idx(tile_x, tile_y);
+            // marks are drawn as a dot in the middle of the tile
+            let in_middle = (1..PIXELS_PER_TILE - 1).contains(&(x % PIXELS_PER_TILE))
+                && (1..PIXELS_PER_TILE - 1).contains(&(y % PIXELS_PER_TILE));
+            let color = match marks.get(&idx) {
+                Some(mark) if in_middle => mark.color(),
+                _ => tile_color(&map.tiles[idx]),
+            };
+            pixels.extend_from_slice(&color);
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
+    Ok(png)
+}